@@ -0,0 +1,10 @@
+-- Create Tokens Table
+CREATE TABLE tokens(
+   token_id uuid NOT NULL,
+   PRIMARY KEY (token_id),
+   user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+   issued_at timestamptz NOT NULL,
+   expires_at timestamptz NOT NULL,
+   revoked_at timestamptz
+);
+CREATE INDEX tokens_user_id_idx ON tokens (user_id);
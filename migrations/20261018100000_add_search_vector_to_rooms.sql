@@ -0,0 +1,7 @@
+-- Full-text search over lair titles and descriptions
+ALTER TABLE rooms ADD COLUMN search_vector tsvector
+   GENERATED ALWAYS AS (
+      setweight(to_tsvector('english', title), 'A') ||
+      setweight(to_tsvector('english', description), 'B')
+   ) STORED;
+CREATE INDEX rooms_search_vector_idx ON rooms USING GIN (search_vector);
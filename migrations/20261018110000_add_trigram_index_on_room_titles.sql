@@ -0,0 +1,3 @@
+-- Typo-tolerant search on lair titles
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE INDEX rooms_title_trgm_idx ON rooms USING GIN (title gin_trgm_ops);
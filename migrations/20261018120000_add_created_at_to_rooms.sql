@@ -0,0 +1,3 @@
+-- Creation date of lairs, used to sort them from the newest
+ALTER TABLE rooms ADD COLUMN created_at timestamptz NOT NULL DEFAULT now();
+CREATE INDEX rooms_created_at_idx ON rooms (created_at, room_id);
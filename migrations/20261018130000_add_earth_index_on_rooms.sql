@@ -0,0 +1,4 @@
+-- Great-circle distance searches around a point
+CREATE EXTENSION IF NOT EXISTS cube;
+CREATE EXTENSION IF NOT EXISTS earthdistance;
+CREATE INDEX rooms_earth_idx ON rooms USING GIST (ll_to_earth(lat, lon));
@@ -0,0 +1,15 @@
+-- Ordered photos of lairs, the first one being the cover kept in rooms.image
+CREATE TABLE lair_images(
+   image_id uuid NOT NULL,
+   PRIMARY KEY (image_id),
+   room_id uuid NOT NULL REFERENCES rooms (room_id) ON DELETE CASCADE,
+   position integer NOT NULL CHECK (position >= 0),
+   caption text,
+   url text NOT NULL,
+   thumbnail_url text NOT NULL,
+   storage_key text NOT NULL,
+   thumbnail_storage_key text NOT NULL,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   -- Deferred so that photos can swap positions within a transaction.
+   UNIQUE (room_id, position) DEFERRABLE INITIALLY DEFERRED
+);
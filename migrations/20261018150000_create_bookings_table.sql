@@ -0,0 +1,20 @@
+-- Create Bookings Table
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+CREATE TABLE bookings(
+   booking_id uuid NOT NULL,
+   PRIMARY KEY (booking_id),
+   room_id uuid NOT NULL REFERENCES rooms (room_id) ON DELETE CASCADE,
+   guest_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+   check_in date NOT NULL,
+   check_out date NOT NULL,
+   status text NOT NULL CHECK (status IN ('confirmed', 'cancelled')),
+   created_at timestamptz NOT NULL DEFAULT now(),
+   CHECK (check_in < check_out),
+   -- Guests leave on the morning of `check_out`, so that night stays free.
+   CONSTRAINT bookings_no_overlap EXCLUDE USING gist (
+      room_id WITH =,
+      daterange(check_in, check_out) WITH &&
+   ) WHERE (status = 'confirmed')
+);
+CREATE INDEX bookings_guest_id_idx ON bookings (guest_id, check_in);
@@ -0,0 +1,38 @@
+-- Bookings are now requested by guests and accepted by hosts
+ALTER TABLE bookings DROP CONSTRAINT bookings_no_overlap;
+ALTER TABLE bookings DROP CONSTRAINT bookings_status_check;
+UPDATE bookings SET status = 'accepted' WHERE status = 'confirmed';
+ALTER TABLE bookings ADD CONSTRAINT bookings_status_check CHECK (
+   status IN ('requested', 'accepted', 'declined', 'checked_in', 'completed', 'cancelled')
+);
+-- Requests may overlap; the stays hosts have agreed to may not.
+ALTER TABLE bookings ADD CONSTRAINT bookings_no_overlap EXCLUDE USING gist (
+   room_id WITH =,
+   daterange(check_in, check_out) WITH &&
+) WHERE (status IN ('accepted', 'checked_in', 'completed'));
+
+-- Mirrors `BookingStatus::after`, for anything writing to the table directly.
+CREATE FUNCTION check_booking_transition() RETURNS trigger AS $$
+BEGIN
+   IF TG_OP = 'INSERT' AND NEW.status <> 'requested' THEN
+      RAISE EXCEPTION 'A booking must start as requested, not %.', NEW.status
+         USING ERRCODE = 'check_violation', CONSTRAINT = 'bookings_status_transition';
+   END IF;
+   IF TG_OP = 'UPDATE' AND NEW.status IS DISTINCT FROM OLD.status AND (OLD.status, NEW.status) NOT IN (
+      ('requested', 'accepted'),
+      ('requested', 'declined'),
+      ('requested', 'cancelled'),
+      ('accepted', 'checked_in'),
+      ('accepted', 'cancelled'),
+      ('checked_in', 'completed')
+   ) THEN
+      RAISE EXCEPTION 'A booking cannot go from % to %.', OLD.status, NEW.status
+         USING ERRCODE = 'check_violation', CONSTRAINT = 'bookings_status_transition';
+   END IF;
+   RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER bookings_status_transition
+   BEFORE INSERT OR UPDATE OF status ON bookings
+   FOR EACH ROW EXECUTE FUNCTION check_booking_transition();
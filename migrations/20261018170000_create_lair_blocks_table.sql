@@ -0,0 +1,23 @@
+-- Nights hosts take their lair off the market, independently of bookings
+CREATE TABLE lair_blocks(
+   block_id uuid NOT NULL,
+   PRIMARY KEY (block_id),
+   room_id uuid NOT NULL REFERENCES rooms (room_id) ON DELETE CASCADE,
+   -- Like a stay, a block covers the nights from `starts_on` until the
+   -- morning of `ends_on`.
+   starts_on date NOT NULL,
+   ends_on date NOT NULL,
+   note text,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   CHECK (starts_on < ends_on)
+);
+CREATE INDEX lair_blocks_nights_idx ON lair_blocks USING gist (room_id, daterange(starts_on, ends_on));
+
+-- Every range of nights a lair cannot be booked for, and why.
+CREATE VIEW lair_unavailability AS
+   SELECT room_id, daterange(check_in, check_out) AS nights, 'booked' AS reason
+   FROM bookings
+   WHERE status IN ('accepted', 'checked_in', 'completed')
+   UNION ALL
+   SELECT room_id, daterange(starts_on, ends_on) AS nights, 'blocked' AS reason
+   FROM lair_blocks;
@@ -0,0 +1,31 @@
+-- Prices are exact decimals in the currency of the lair. A lair without a
+-- nightly price cannot be quoted yet.
+ALTER TABLE rooms
+   ADD COLUMN nightly_price numeric(12, 2) CHECK (nightly_price >= 0),
+   -- Replaces the nightly price on Friday and Saturday nights.
+   ADD COLUMN weekend_price numeric(12, 2) CHECK (weekend_price >= 0),
+   ADD COLUMN cleaning_fee numeric(12, 2) NOT NULL DEFAULT 0 CHECK (cleaning_fee >= 0),
+   ADD COLUMN currency text CHECK (currency ~ '^[A-Z]{3}$'),
+   ADD COLUMN min_nights integer NOT NULL DEFAULT 1 CHECK (min_nights >= 1),
+   ADD COLUMN max_nights integer CHECK (max_nights >= min_nights),
+   ADD CONSTRAINT rooms_price_has_currency CHECK ((nightly_price IS NULL) = (currency IS NULL)),
+   ADD CONSTRAINT rooms_prices_need_nightly_price CHECK (
+      nightly_price IS NOT NULL OR (weekend_price IS NULL AND cleaning_fee = 0)
+   );
+
+-- Nights priced differently from the rest of the year, whatever the day of
+-- the week.
+CREATE TABLE lair_seasons(
+   season_id uuid NOT NULL,
+   PRIMARY KEY (season_id),
+   room_id uuid NOT NULL REFERENCES rooms (room_id) ON DELETE CASCADE,
+   starts_on date NOT NULL,
+   ends_on date NOT NULL,
+   nightly_price numeric(12, 2) NOT NULL CHECK (nightly_price >= 0),
+   created_at timestamptz NOT NULL DEFAULT now(),
+   CHECK (starts_on < ends_on),
+   CONSTRAINT lair_seasons_no_overlap EXCLUDE USING gist (
+      room_id WITH =,
+      daterange(starts_on, ends_on) WITH &&
+   )
+);
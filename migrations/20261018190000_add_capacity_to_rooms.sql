@@ -0,0 +1,7 @@
+-- How many guests a lair sleeps, and in how many bedrooms. A studio has no
+-- bedroom.
+ALTER TABLE rooms
+   ADD COLUMN max_guests integer NOT NULL DEFAULT 1 CHECK (max_guests >= 1),
+   ADD COLUMN bedrooms integer NOT NULL DEFAULT 0 CHECK (bedrooms >= 0);
+
+CREATE INDEX rooms_nightly_price_idx ON rooms (nightly_price);
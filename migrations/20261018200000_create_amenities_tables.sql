@@ -0,0 +1,32 @@
+-- Administrators manage the catalogue of amenities.
+ALTER TABLE users ADD COLUMN is_admin boolean NOT NULL DEFAULT false;
+UPDATE users SET is_admin = true WHERE id = 'ddf8994f-d522-4659-8d02-c1d479057be6';
+
+-- What lairs can offer beyond their description, grouped by category.
+CREATE TABLE amenities(
+   amenity_id uuid NOT NULL,
+   PRIMARY KEY (amenity_id),
+   -- The stable name clients filter on, e.g. `wifi`.
+   slug text NOT NULL,
+   name text NOT NULL,
+   category text NOT NULL,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   CONSTRAINT amenities_slug_key UNIQUE (slug)
+);
+
+CREATE TABLE room_amenities(
+   room_id uuid NOT NULL REFERENCES rooms (room_id) ON DELETE CASCADE,
+   amenity_id uuid NOT NULL REFERENCES amenities (amenity_id) ON DELETE CASCADE,
+   PRIMARY KEY (room_id, amenity_id)
+);
+CREATE INDEX room_amenities_amenity_id_idx ON room_amenities (amenity_id);
+
+INSERT INTO amenities (amenity_id, slug, name, category) VALUES
+   (gen_random_uuid(), 'wifi', 'Wi-Fi', 'Essentials'),
+   (gen_random_uuid(), 'heating', 'Heating', 'Essentials'),
+   (gen_random_uuid(), 'kitchen', 'Kitchen', 'Essentials'),
+   (gen_random_uuid(), 'parking', 'Free parking', 'Location'),
+   (gen_random_uuid(), 'pet-friendly', 'Pets allowed', 'Policies'),
+   (gen_random_uuid(), 'step-free-access', 'Step-free access', 'Accessibility'),
+   (gen_random_uuid(), 'wide-doorways', 'Wide doorways', 'Accessibility'),
+   (gen_random_uuid(), 'moat', 'Moat', 'Security');
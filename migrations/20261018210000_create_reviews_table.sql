@@ -0,0 +1,44 @@
+-- Guests review the lairs they stayed in, and hosts reply once in public.
+CREATE TABLE reviews(
+   review_id uuid NOT NULL,
+   PRIMARY KEY (review_id),
+   room_id uuid NOT NULL REFERENCES rooms (room_id) ON DELETE CASCADE,
+   guest_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+   rating integer NOT NULL CHECK (rating BETWEEN 1 AND 5),
+   body text NOT NULL,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   host_reply text,
+   host_replied_at timestamptz,
+   CHECK ((host_reply IS NULL) = (host_replied_at IS NULL)),
+   CONSTRAINT reviews_one_per_guest UNIQUE (room_id, guest_id)
+);
+CREATE INDEX reviews_room_id_created_at_idx ON reviews (room_id, created_at, review_id);
+
+-- The ratings of each lair, cached for the map.
+ALTER TABLE rooms
+   ADD COLUMN review_count integer NOT NULL DEFAULT 0,
+   ADD COLUMN rating_total integer NOT NULL DEFAULT 0,
+   ADD COLUMN average_rating numeric(3, 2)
+      GENERATED ALWAYS AS (round(rating_total::numeric / NULLIF(review_count, 0), 2)) STORED;
+
+-- Counts are adjusted rather than recomputed, so that concurrent reviews of
+-- the same lair add up once they wait for each other's row lock.
+CREATE FUNCTION refresh_room_rating() RETURNS trigger AS $$
+BEGIN
+   IF TG_OP IN ('UPDATE', 'DELETE') THEN
+      UPDATE rooms
+      SET review_count = review_count - 1, rating_total = rating_total - OLD.rating
+      WHERE room_id = OLD.room_id;
+   END IF;
+   IF TG_OP IN ('INSERT', 'UPDATE') THEN
+      UPDATE rooms
+      SET review_count = review_count + 1, rating_total = rating_total + NEW.rating
+      WHERE room_id = NEW.room_id;
+   END IF;
+   RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reviews_room_rating
+   AFTER INSERT OR DELETE OR UPDATE OF rating, room_id ON reviews
+   FOR EACH ROW EXECUTE FUNCTION refresh_room_rating();
@@ -0,0 +1,18 @@
+-- Named lists of the lairs a user wants to remember.
+CREATE TABLE wishlists(
+   wishlist_id uuid NOT NULL,
+   PRIMARY KEY (wishlist_id),
+   user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+   name text NOT NULL,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   CONSTRAINT wishlists_name_per_user UNIQUE (user_id, name)
+);
+
+CREATE TABLE wishlist_lairs(
+   wishlist_id uuid NOT NULL REFERENCES wishlists (wishlist_id) ON DELETE CASCADE,
+   room_id uuid NOT NULL REFERENCES rooms (room_id) ON DELETE CASCADE,
+   added_at timestamptz NOT NULL DEFAULT now(),
+   PRIMARY KEY (wishlist_id, room_id)
+);
+-- Tells whether a lair is one of the favourites of the user looking at it.
+CREATE INDEX wishlist_lairs_room_id_idx ON wishlist_lairs (room_id);
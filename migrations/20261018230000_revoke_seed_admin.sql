@@ -0,0 +1,3 @@
+-- Nobody is an administrator until promoted by hand, see ADMIN_ACCOUNT in
+-- scripts/init_db.sh.
+UPDATE users SET is_admin = false WHERE id = 'ddf8994f-d522-4659-8d02-c1d479057be6';
@@ -0,0 +1,2 @@
+-- Signed into the share link, replacing it revokes the links handed out.
+ALTER TABLE wishlists ADD COLUMN share_nonce uuid NOT NULL DEFAULT gen_random_uuid();
@@ -60,4 +60,12 @@ export DATABASE_URL=postgres://${DB_USER}:${DB_PASSWORD}@${DB_HOST}:${DB_PORT}/$
 sqlx database create
 sqlx migrate run
 
+# Promote an existing account to administrator if one is named
+if [[ -n "${ADMIN_ACCOUNT}" ]]
+then
+  PGPASSWORD="${DB_PASSWORD}" psql -h "${DB_HOST}" -U "${DB_USER}" -p "${DB_PORT}" -d "${DB_NAME}" \
+      -v ON_ERROR_STOP=1 -v account="${ADMIN_ACCOUNT}" \
+      <<< "UPDATE users SET is_admin = true WHERE account_name = :'account';"
+fi
+
 >&2 echo "Postgres has been migrated, ready to go!"
\ No newline at end of file
@@ -0,0 +1,270 @@
+use crate::api_error::{violates_constraint, ApiError};
+use crate::authentication::{AdminUser, AuthenticatedUser};
+use crate::domain::{AmenityName, AmenitySlug, NewAmenity};
+use crate::get_documents_from_id::{lock_owned_lair, RoomId};
+use crate::routes::InvalidFields;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+/// The constraint that keeps two amenities from sharing a slug.
+const UNIQUE_SLUG_CONSTRAINT: &str = "amenities_slug_key";
+/// The most amenities a lair can list.
+const MAX_AMENITIES_PER_LAIR: usize = 100;
+
+#[derive(Debug, Serialize)]
+pub struct Amenity {
+    #[serde(rename = "id")]
+    amenity_id: Uuid,
+    slug: String,
+    name: String,
+    category: String,
+}
+
+#[derive(Deserialize)]
+pub struct AmenityInfo {
+    slug: String,
+    name: String,
+    category: String,
+}
+
+impl TryFrom<AmenityInfo> for NewAmenity {
+    type Error = InvalidFields;
+
+    fn try_from(value: AmenityInfo) -> Result<Self, Self::Error> {
+        let mut invalid_fields = InvalidFields::default();
+        let slug = invalid_fields.check("slug", AmenitySlug::parse(value.slug));
+        let name = invalid_fields.check("name", AmenityName::parse(value.name));
+        let category = invalid_fields.check("category", AmenityName::parse(value.category));
+
+        match (slug, name, category) {
+            (Some(slug), Some(name), Some(category)) => Ok(Self {
+                slug,
+                name,
+                category,
+            }),
+            _ => Err(invalid_fields),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AmenityId {
+    id: Uuid,
+}
+
+/// The slugs of every amenity of a lair.
+#[derive(Deserialize)]
+pub struct LairAmenities {
+    amenities: Vec<String>,
+}
+
+//#[get("/amenities")]
+#[tracing::instrument(name = "Listing the amenities", skip(pool))]
+pub async fn list_amenities(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    let amenities = sqlx::query_as!(
+        Amenity,
+        "SELECT amenity_id, slug, name, category FROM amenities ORDER BY category, name"
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch the amenities.")?;
+    Ok(HttpResponse::Ok().json(json!({ "amenities": amenities })))
+}
+
+//#[post("/amenities")]
+#[tracing::instrument(name = "Adding an amenity", skip(pool, amenity_info), fields(user_id=%admin))]
+pub async fn add_amenity(
+    pool: web::Data<PgPool>,
+    admin: AdminUser,
+    amenity_info: web::Json<AmenityInfo>,
+) -> Result<HttpResponse, ApiError> {
+    let new_amenity: NewAmenity = amenity_info
+        .0
+        .try_into()
+        .map_err(ApiError::ValidationError)?;
+    let amenity = sqlx::query_as!(
+        Amenity,
+        r#"
+        INSERT INTO amenities (amenity_id, slug, name, category)
+        VALUES ($1, $2, $3, $4)
+        RETURNING amenity_id, slug, name, category
+        "#,
+        Uuid::new_v4(),
+        new_amenity.slug.as_ref(),
+        new_amenity.name.as_ref(),
+        new_amenity.category.as_ref(),
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| saving_error(e, &new_amenity))?;
+    Ok(HttpResponse::Created().json(amenity))
+}
+
+//#[put("/amenities/{id}")]
+#[tracing::instrument(
+    name = "Replacing an amenity",
+    skip(path, pool, amenity_info),
+    fields(user_id=%admin)
+)]
+pub async fn replace_amenity(
+    path: web::Path<AmenityId>,
+    pool: web::Data<PgPool>,
+    admin: AdminUser,
+    amenity_info: web::Json<AmenityInfo>,
+) -> Result<HttpResponse, ApiError> {
+    let new_amenity: NewAmenity = amenity_info
+        .0
+        .try_into()
+        .map_err(ApiError::ValidationError)?;
+    let amenity = sqlx::query_as!(
+        Amenity,
+        r#"
+        UPDATE amenities SET slug = $2, name = $3, category = $4
+        WHERE amenity_id = $1
+        RETURNING amenity_id, slug, name, category
+        "#,
+        path.id,
+        new_amenity.slug.as_ref(),
+        new_amenity.name.as_ref(),
+        new_amenity.category.as_ref(),
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| saving_error(e, &new_amenity))?
+    .ok_or(ApiError::NotFound("The amenity does not exist."))?;
+    Ok(HttpResponse::Ok().json(amenity))
+}
+
+//#[delete("/amenities/{id}")]
+/// Removes an amenity from the catalogue and from every lair.
+#[tracing::instrument(name = "Removing an amenity", skip(path, pool), fields(user_id=%admin))]
+pub async fn remove_amenity(
+    path: web::Path<AmenityId>,
+    pool: web::Data<PgPool>,
+    admin: AdminUser,
+) -> Result<HttpResponse, ApiError> {
+    let deleted = sqlx::query!("DELETE FROM amenities WHERE amenity_id = $1", path.id)
+        .execute(pool.get_ref())
+        .await
+        .context("Failed to delete an amenity.")?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound("The amenity does not exist."));
+    }
+    Ok(HttpResponse::Ok().json(json!({"status": "success"})))
+}
+
+fn saving_error(e: sqlx::Error, new_amenity: &NewAmenity) -> ApiError {
+    if violates_constraint(&e, UNIQUE_SLUG_CONSTRAINT) {
+        ApiError::Conflict(format!(
+            "There already is an amenity called {}.",
+            new_amenity.slug.as_ref()
+        ))
+    } else {
+        ApiError::UnexpectedError(anyhow::Error::new(e).context("Failed to save an amenity."))
+    }
+}
+
+//#[put("/lair/{id}/amenities")]
+/// Replaces the amenities of a lair with the ones listed.
+#[tracing::instrument(
+    name = "Setting the amenities of a lair",
+    skip(path, pool, lair_amenities),
+    fields(user_id=%user)
+)]
+pub async fn set_lair_amenities(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    lair_amenities: web::Json<LairAmenities>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let mut slugs = lair_amenities.0.amenities;
+    slugs.sort();
+    slugs.dedup();
+    if slugs.len() > MAX_AMENITIES_PER_LAIR {
+        return Err(ApiError::ValidationError(InvalidFields::single(
+            "amenities",
+            format!(
+                "A lair cannot list more than {} amenities.",
+                MAX_AMENITIES_PER_LAIR
+            ),
+        )));
+    }
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+    let known = sqlx::query_scalar!(
+        "SELECT slug FROM amenities WHERE slug = ANY($1)",
+        &slugs[..]
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .context("Failed to look the amenities up.")?;
+    let unknown: Vec<_> = slugs.iter().filter(|slug| !known.contains(slug)).collect();
+    if !unknown.is_empty() {
+        return Err(ApiError::ValidationError(InvalidFields::single(
+            "amenities",
+            format!(
+                "Unknown amenities: {}.",
+                unknown
+                    .iter()
+                    .map(|slug| slug.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )));
+    }
+
+    sqlx::query!("DELETE FROM room_amenities WHERE room_id = $1", room_id)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to clear the amenities of a lair.")?;
+    sqlx::query!(
+        r#"
+        INSERT INTO room_amenities (room_id, amenity_id)
+        SELECT $1, amenity_id FROM amenities WHERE slug = ANY($2)
+        "#,
+        room_id,
+        &slugs[..]
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to save the amenities of a lair.")?;
+    let amenities = fetch_lair_amenities(room_id, &mut *transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to set the amenities of a lair.")?;
+    Ok(HttpResponse::Ok().json(json!({ "amenities": amenities })))
+}
+
+#[tracing::instrument(name = "Fetching the amenities of a lair", skip(executor))]
+pub async fn fetch_lair_amenities<'c, E>(
+    room_id: Uuid,
+    executor: E,
+) -> Result<Vec<Amenity>, anyhow::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query_as!(
+        Amenity,
+        r#"
+        SELECT a.amenity_id, a.slug, a.name, a.category
+        FROM room_amenities ra
+        JOIN amenities a USING (amenity_id)
+        WHERE ra.room_id = $1
+        ORDER BY a.category, a.name
+        "#,
+        room_id
+    )
+    .fetch_all(executor)
+    .await
+    .context("Failed to fetch the amenities of a lair.")
+}
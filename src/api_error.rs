@@ -0,0 +1,65 @@
+use crate::routes::{error_chain_fmt, InvalidFields};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+
+/// The errors of the JSON endpoints. Apart from the unexpected ones, they
+/// tell the client what went wrong.
+#[derive(thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    ValidationError(InvalidFields),
+    #[error("{0}")]
+    NotFound(&'static str),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ApiError {
+    /// A validation error about a single field.
+    pub fn invalid(field: &'static str, message: String) -> Self {
+        ApiError::ValidationError(InvalidFields::single(field, message))
+    }
+}
+
+impl std::fmt::Debug for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ApiError::ValidationError(invalid_fields) => invalid_fields.error_response(),
+            ApiError::UnexpectedError(_) => HttpResponse::new(self.status_code()),
+            _ => HttpResponse::build(self.status_code())
+                .json(json!({"status": "error", "message": self.to_string()})),
+        }
+    }
+}
+
+/// Whether the database refused a write because of `constraint`.
+pub fn violates_constraint(e: &sqlx::Error, constraint: &str) -> bool {
+    matches!(e, sqlx::Error::Database(e) if e.constraint() == Some(constraint))
+}
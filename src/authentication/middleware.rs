@@ -0,0 +1,192 @@
+use crate::authentication::{bearer_token, validate_token, TokenError};
+use crate::routes::error_chain_fmt;
+use crate::session_state::TypedSession;
+use crate::startup::HmacSecret;
+use crate::utils::see_other;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::header::WWW_AUTHENTICATE;
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use actix_web_lab::middleware::Next;
+use anyhow::Context;
+use reqwest::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+use std::future::Future;
+use std::pin::Pin;
+use uuid::Uuid;
+
+/// The user on whose behalf a request is made.
+///
+/// An `Authorization: Bearer` header takes precedence and must be valid when
+/// present; otherwise the user is looked up in the session. Requests with
+/// neither are rejected with a 401.
+#[derive(Copy, Clone, Debug)]
+pub struct AuthenticatedUser(Uuid);
+
+impl AuthenticatedUser {
+    pub fn user_id(&self) -> Uuid {
+        self.0
+    }
+}
+
+impl std::fmt::Display for AuthenticatedUser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AuthenticationError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // Already resolved by `reject_anonymous_users`.
+        if let Some(user) = req.extensions().get::<AuthenticatedUser>().copied() {
+            return Box::pin(async move { Ok(user) });
+        }
+
+        let token = bearer_token(req).map(|token| token.map(str::to_owned));
+        let secret = req.app_data::<web::Data<HmacSecret>>().cloned();
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+        let session = TypedSession::from_request(req, payload);
+        Box::pin(async move {
+            match token? {
+                Some(token) => {
+                    let (secret, pool) = secret
+                        .zip(pool)
+                        .context("The HMAC secret or the database pool is not registered.")?;
+                    let token = validate_token(&token, &secret, &pool).await?;
+                    Ok(AuthenticatedUser(token.user_id))
+                }
+                None => {
+                    let user_id = session
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{}", e))?
+                        .get_user_id()
+                        .context("Failed to read the user id from the session.")?;
+                    user_id
+                        .map(AuthenticatedUser)
+                        .ok_or(AuthenticationError::MissingCredentials)
+                }
+            }
+        })
+    }
+}
+
+/// A user allowed to manage what all hosts share, like the catalogue of
+/// amenities. Other users are rejected with a 403.
+#[derive(Copy, Clone, Debug)]
+pub struct AdminUser(AuthenticatedUser);
+
+impl AdminUser {
+    pub fn user_id(&self) -> Uuid {
+        self.0.user_id()
+    }
+}
+
+impl std::fmt::Display for AdminUser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromRequest for AdminUser {
+    type Error = AuthenticationError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+        Box::pin(async move {
+            let user = user.await?;
+            let pool = pool.context("The database pool is not registered.")?;
+            let is_admin =
+                sqlx::query_scalar!("SELECT is_admin FROM users WHERE id = $1", user.user_id())
+                    .fetch_optional(pool.get_ref())
+                    .await
+                    .context("Failed to check whether a user is an administrator.")?
+                    .unwrap_or(false);
+            if is_admin {
+                Ok(AdminUser(user))
+            } else {
+                Err(AuthenticationError::NotAnAdmin)
+            }
+        })
+    }
+}
+
+/// Sends anonymous visitors of the HTML pages it wraps to the login form.
+pub async fn reject_anonymous_users(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let user = {
+        let (http_request, payload) = req.parts_mut();
+        AuthenticatedUser::from_request(http_request, payload).await
+    };
+
+    match user {
+        Ok(user) => {
+            req.extensions_mut().insert(user);
+            next.call(req).await
+        }
+        Err(AuthenticationError::UnexpectedError(e)) => {
+            Err(InternalError::new(e, StatusCode::INTERNAL_SERVER_ERROR).into())
+        }
+        Err(e) => Err(InternalError::from_response(e, see_other("/login")).into()),
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum AuthenticationError {
+    #[error("Authentication required.")]
+    MissingCredentials,
+    #[error("Invalid credentials.")]
+    InvalidCredentials(#[source] anyhow::Error),
+    #[error("Only administrators can do this.")]
+    NotAnAdmin,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl From<TokenError> for AuthenticationError {
+    fn from(e: TokenError) -> Self {
+        match e {
+            TokenError::MissingToken => AuthenticationError::MissingCredentials,
+            TokenError::InvalidToken(e) => AuthenticationError::InvalidCredentials(e),
+            TokenError::UnexpectedError(e) => AuthenticationError::UnexpectedError(e),
+        }
+    }
+}
+
+impl std::fmt::Debug for AuthenticationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for AuthenticationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthenticationError::MissingCredentials
+            | AuthenticationError::InvalidCredentials(_) => StatusCode::UNAUTHORIZED,
+            AuthenticationError::NotAnAdmin => StatusCode::FORBIDDEN,
+            AuthenticationError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AuthenticationError::UnexpectedError(_) => {
+                HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+            AuthenticationError::NotAnAdmin => HttpResponse::Forbidden()
+                .json(json!({"status":"error", "message": self.to_string()})),
+            _ => HttpResponse::Unauthorized()
+                .insert_header((WWW_AUTHENTICATE, r#"Bearer realm="publish""#))
+                .json(json!({"status":"error", "message": self.to_string()})),
+        }
+    }
+}
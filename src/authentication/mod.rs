@@ -0,0 +1,10 @@
+mod middleware;
+mod password;
+mod token;
+
+pub use middleware::{reject_anonymous_users, AdminUser, AuthenticatedUser, AuthenticationError};
+pub use password::{compute_password_hash, validate_credentials, AuthError, Credentials};
+pub(crate) use token::mac;
+pub use token::{
+    bearer_token, issue_token, revoke_token, validate_token, AuthToken, BearerToken, TokenError,
+};
@@ -0,0 +1,152 @@
+use crate::telemetry::spawn_blocking_with_tracing;
+use anyhow::Context;
+use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Used when the username does not exist, so that an unknown user costs as
+// much to reject as a wrong password.
+const FALLBACK_PASSWORD_HASH: &str = "$argon2id$v=19$m=15000,t=2,p=1$\
+    gZiV/M1gPc22ElAH/Jh1Hw$\
+    CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno";
+
+#[derive(thiserror::Error, Debug)]
+pub enum AuthError {
+    #[error("Invalid credentials.")]
+    InvalidCredentials(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+pub struct Credentials {
+    pub username: String,
+    pub password: Secret<String>,
+}
+
+/// Outcome of a successful password check.
+enum PasswordMatch {
+    /// The stored value is a PHC string and the password matched it.
+    Hashed,
+    /// The stored value predates hashing and matched as plaintext: it must
+    /// be replaced with a hash of the password we were given.
+    LegacyPlaintext(Secret<String>),
+}
+
+#[tracing::instrument(name = "Get stored credentials", skip(username, pool))]
+async fn get_stored_credentials(
+    username: &str,
+    pool: &PgPool,
+) -> Result<Option<(Uuid, Secret<String>)>, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, account_password
+        FROM users
+        WHERE account_name = $1
+        "#,
+        username,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to perform a query to retrieve stored credentials.")?
+    .map(|row| (row.id, Secret::new(row.account_password)));
+    Ok(row)
+}
+
+#[tracing::instrument(name = "Validate credentials", skip(credentials, pool))]
+pub async fn validate_credentials(
+    credentials: Credentials,
+    pool: &PgPool,
+) -> Result<Uuid, AuthError> {
+    let mut user_id = None;
+    let mut expected_password = Secret::new(FALLBACK_PASSWORD_HASH.to_string());
+
+    if let Some((stored_user_id, stored_password)) =
+        get_stored_credentials(&credentials.username, pool).await?
+    {
+        user_id = Some(stored_user_id);
+        expected_password = stored_password;
+    }
+
+    let password_match = spawn_blocking_with_tracing(move || {
+        verify_password(expected_password, credentials.password)
+    })
+    .await
+    .context("Failed to spawn blocking task.")??;
+
+    // This is only set to `Some` if we found credentials in the store.
+    // So, even if the fallback hash ends up matching the provided password,
+    // we never authenticate a non-existing user.
+    let user_id = user_id
+        .ok_or_else(|| AuthError::InvalidCredentials(anyhow::anyhow!("Unknown username.")))?;
+
+    if let PasswordMatch::LegacyPlaintext(password) = password_match {
+        rehash_legacy_password(user_id, password, pool).await?;
+    }
+
+    Ok(user_id)
+}
+
+#[tracing::instrument(name = "Verify password", skip(expected_password, password_candidate))]
+fn verify_password(
+    expected_password: Secret<String>,
+    password_candidate: Secret<String>,
+) -> Result<PasswordMatch, AuthError> {
+    match PasswordHash::new(expected_password.expose_secret()) {
+        Ok(expected_password_hash) => {
+            Argon2::default()
+                .verify_password(
+                    password_candidate.expose_secret().as_bytes(),
+                    &expected_password_hash,
+                )
+                .context("Invalid password.")
+                .map_err(AuthError::InvalidCredentials)?;
+            Ok(PasswordMatch::Hashed)
+        }
+        // Accounts created before we started hashing passwords still hold
+        // them in plaintext.
+        Err(_) if expected_password.expose_secret() == password_candidate.expose_secret() => {
+            Ok(PasswordMatch::LegacyPlaintext(password_candidate))
+        }
+        Err(_) => Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "Invalid password."
+        ))),
+    }
+}
+
+#[tracing::instrument(name = "Rehash legacy password", skip(password, pool))]
+async fn rehash_legacy_password(
+    user_id: Uuid,
+    password: Secret<String>,
+    pool: &PgPool,
+) -> Result<(), anyhow::Error> {
+    let password_hash = spawn_blocking_with_tracing(move || compute_password_hash(password))
+        .await
+        .context("Failed to spawn blocking task.")??;
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET account_password = $1
+        WHERE id = $2
+        "#,
+        password_hash.expose_secret(),
+        user_id,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to replace a plaintext password with its hash.")?;
+    Ok(())
+}
+
+pub fn compute_password_hash(password: Secret<String>) -> Result<Secret<String>, anyhow::Error> {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let password_hash = Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(15000, 2, 1, None).unwrap(),
+    )
+    .hash_password(password.expose_secret().as_bytes(), &salt)?
+    .to_string();
+    Ok(Secret::new(password_hash))
+}
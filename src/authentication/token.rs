@@ -0,0 +1,301 @@
+use crate::authentication::AuthenticationError;
+use crate::routes::error_chain_fmt;
+use crate::startup::HmacSecret;
+use actix_web::dev::Payload;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, FromRequest, HttpRequest};
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use sha2::Sha256;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use std::future::Future;
+use std::pin::Pin;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const TOKEN_LIFETIME_IN_DAYS: i64 = 7;
+const TOKEN_CONTEXT: &[u8] = b"";
+
+/// The claims carried by a bearer token.
+///
+/// On the wire a token is `<payload>.<signature>`, both URL-safe base64:
+/// the payload is the token id, the user id and the expiry as a big-endian
+/// unix timestamp, and the signature is an HMAC-SHA256 of the payload keyed
+/// with the configured `HmacSecret`.
+#[derive(Debug, PartialEq)]
+pub struct AuthToken {
+    pub token_id: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl AuthToken {
+    const PAYLOAD_LENGTH: usize = 16 + 16 + 8;
+
+    pub fn new(user_id: Uuid) -> Self {
+        let expires_at = Utc::now() + Duration::days(TOKEN_LIFETIME_IN_DAYS);
+        Self {
+            token_id: Uuid::new_v4(),
+            user_id,
+            // The wire format only keeps whole seconds.
+            expires_at: Utc.timestamp_opt(expires_at.timestamp(), 0).unwrap(),
+        }
+    }
+
+    pub fn encode(&self, secret: &HmacSecret) -> String {
+        let mut payload = Vec::with_capacity(Self::PAYLOAD_LENGTH);
+        payload.extend_from_slice(self.token_id.as_bytes());
+        payload.extend_from_slice(self.user_id.as_bytes());
+        payload.extend_from_slice(&self.expires_at.timestamp().to_be_bytes());
+
+        let signature = mac(secret, TOKEN_CONTEXT, &payload).finalize().into_bytes();
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            URL_SAFE_NO_PAD.encode(signature)
+        )
+    }
+
+    /// Checks the signature and the expiry of `token`.
+    /// It does not check whether the token has been revoked.
+    pub fn decode(token: &str, secret: &HmacSecret) -> Result<Self, anyhow::Error> {
+        let (payload, signature) = token
+            .split_once('.')
+            .context("The token is not made of a payload and a signature.")?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .context("The token payload is not valid base64.")?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("The token signature is not valid base64.")?;
+        mac(secret, TOKEN_CONTEXT, &payload)
+            .verify_slice(&signature)
+            .context("The token signature does not match its payload.")?;
+
+        if payload.len() != Self::PAYLOAD_LENGTH {
+            anyhow::bail!("The token payload has an unexpected length.");
+        }
+        let token_id = Uuid::from_slice(&payload[0..16])?;
+        let user_id = Uuid::from_slice(&payload[16..32])?;
+        let expires_at = i64::from_be_bytes(payload[32..40].try_into()?);
+        let expires_at = Utc
+            .timestamp_opt(expires_at, 0)
+            .single()
+            .context("The token expiry is not a valid timestamp.")?;
+        if expires_at <= Utc::now() {
+            anyhow::bail!("The token has expired.");
+        }
+
+        Ok(Self {
+            token_id,
+            user_id,
+            expires_at,
+        })
+    }
+}
+
+/// An HMAC-SHA256 of `payload` keyed with the configured `HmacSecret`.
+/// `context` is signed first, so that a signature made for one purpose never
+/// passes for another made with the same secret. Tokens use an empty one, as
+/// they were signed before there was any other purpose.
+pub(crate) fn mac(secret: &HmacSecret, context: &[u8], payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.0.expose_secret().as_bytes())
+        .expect("HMAC can take a key of any size");
+    mac.update(context);
+    mac.update(payload);
+    mac
+}
+
+#[tracing::instrument(name = "Issuing a new token", skip(secret, transaction))]
+pub async fn issue_token(
+    user_id: Uuid,
+    secret: &HmacSecret,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<String, sqlx::Error> {
+    let token = AuthToken::new(user_id);
+    let query = sqlx::query!(
+        r#"
+    INSERT INTO tokens (token_id, user_id, issued_at, expires_at)
+    VALUES ($1, $2, now(), $3)
+            "#,
+        token.token_id,
+        token.user_id,
+        token.expires_at,
+    );
+    transaction.execute(query).await?;
+    Ok(token.encode(secret))
+}
+
+#[tracing::instrument(name = "Revoking a token", skip(pool))]
+pub async fn revoke_token(token_id: Uuid, pool: &PgPool) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE tokens
+        SET revoked_at = now()
+        WHERE token_id = $1 AND revoked_at IS NULL
+        "#,
+        token_id,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to revoke the token.")?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Validating a bearer token", skip(token, secret, pool))]
+pub async fn validate_token(
+    token: &str,
+    secret: &HmacSecret,
+    pool: &PgPool,
+) -> Result<AuthToken, TokenError> {
+    let token = AuthToken::decode(token, secret).map_err(TokenError::InvalidToken)?;
+    let row = sqlx::query!(
+        r#"
+        SELECT user_id FROM tokens
+        WHERE token_id = $1 AND revoked_at IS NULL AND expires_at > now()
+        "#,
+        token.token_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to perform a query to retrieve a token.")?;
+
+    match row {
+        Some(row) if row.user_id == token.user_id => Ok(token),
+        _ => Err(TokenError::InvalidToken(anyhow::anyhow!(
+            "The token is unknown, expired or has been revoked."
+        ))),
+    }
+}
+
+/// Returns the token of an `Authorization: Bearer <token>` header, if any.
+pub fn bearer_token(request: &HttpRequest) -> Result<Option<&str>, TokenError> {
+    let Some(header) = request.headers().get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let header = header
+        .to_str()
+        .context("The 'Authorization' header was not a valid UTF8 string.")
+        .map_err(TokenError::InvalidToken)?;
+    match header.split_once(' ') {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => Ok(Some(token.trim())),
+        _ => Err(TokenError::InvalidToken(anyhow::anyhow!(
+            "The authorization scheme was not 'Bearer'."
+        ))),
+    }
+}
+
+/// Extracts and validates the bearer token of a request, rejecting it with a
+/// 401 if the token is missing, forged, expired or revoked.
+///
+/// Only use it where the token itself matters (e.g. to revoke it): routes
+/// that just need to know who is calling take an `AuthenticatedUser`.
+pub struct BearerToken(AuthToken);
+
+impl BearerToken {
+    pub fn user_id(&self) -> Uuid {
+        self.0.user_id
+    }
+
+    pub fn token_id(&self) -> Uuid {
+        self.0.token_id
+    }
+}
+
+impl FromRequest for BearerToken {
+    type Error = AuthenticationError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token = bearer_token(req).map(|token| token.map(str::to_owned));
+        let secret = req.app_data::<web::Data<HmacSecret>>().cloned();
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+        Box::pin(async move {
+            let token = token?.ok_or(TokenError::MissingToken)?;
+            let (secret, pool) = secret
+                .zip(pool)
+                .context("The HMAC secret or the database pool is not registered.")?;
+            let token = validate_token(&token, &secret, &pool).await?;
+            Ok(BearerToken(token))
+        })
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum TokenError {
+    #[error("Missing bearer token.")]
+    MissingToken,
+    #[error("Invalid bearer token.")]
+    InvalidToken(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AuthToken;
+    use crate::startup::HmacSecret;
+    use chrono::{Duration, TimeZone, Utc};
+    use claims::{assert_err, assert_ok};
+    use secrecy::Secret;
+    use uuid::Uuid;
+
+    fn secret() -> HmacSecret {
+        HmacSecret(Secret::new("a-secret-only-used-in-tests".to_string()))
+    }
+
+    #[test]
+    fn an_encoded_token_is_decoded_back() {
+        let token = AuthToken::new(Uuid::new_v4());
+        let decoded = AuthToken::decode(&token.encode(&secret()), &secret());
+        assert_eq!(decoded.unwrap(), token);
+    }
+
+    #[test]
+    fn a_token_signed_with_another_secret_is_rejected() {
+        let token = AuthToken::new(Uuid::new_v4()).encode(&secret());
+        let other_secret = HmacSecret(Secret::new("another-secret".to_string()));
+        assert_err!(AuthToken::decode(&token, &other_secret));
+    }
+
+    #[test]
+    fn a_token_with_a_tampered_payload_is_rejected() {
+        let token = AuthToken::new(Uuid::new_v4()).encode(&secret());
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged_payload = AuthToken::new(Uuid::new_v4()).encode(&secret());
+        let (forged_payload, _) = forged_payload.split_once('.').unwrap();
+        let forged = format!("{}.{}", forged_payload, signature);
+        assert_err!(AuthToken::decode(&forged, &secret()));
+    }
+
+    #[test]
+    fn an_expired_token_is_rejected() {
+        let mut token = AuthToken::new(Uuid::new_v4());
+        let expired = Utc::now() - Duration::minutes(1);
+        token.expires_at = Utc.timestamp_opt(expired.timestamp(), 0).unwrap();
+        assert_err!(AuthToken::decode(&token.encode(&secret()), &secret()));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for token in ["", ".", "not-a-token", "a.b", "YWJj.YWJj"] {
+            assert_err!(AuthToken::decode(token, &secret()));
+        }
+    }
+
+    #[test]
+    fn a_fresh_token_is_valid() {
+        let token = AuthToken::new(Uuid::new_v4()).encode(&secret());
+        assert_ok!(AuthToken::decode(&token, &secret()));
+    }
+}
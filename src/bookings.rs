@@ -0,0 +1,406 @@
+use crate::api_error::{violates_constraint, ApiError};
+use crate::authentication::AuthenticatedUser;
+use crate::domain::{
+    BookingAction, BookingParty, BookingStatus, Stay, StayLimits, SubscriberEmail,
+};
+use crate::email_client::EmailClient;
+use crate::get_documents_from_id::RoomId;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Executor, PgPool, Postgres, QueryBuilder, Transaction};
+use uuid::Uuid;
+
+/// The constraint that keeps confirmed stays in a lair from overlapping.
+const NO_OVERLAP_CONSTRAINT: &str = "bookings_no_overlap";
+
+/// Which bookings `fetch_bookings` returns.
+#[derive(Debug)]
+enum BookingFilter {
+    Id(Uuid),
+    /// The stays a user booked.
+    Guest(Uuid),
+    /// The stays booked in the lairs of a user.
+    Host(Uuid),
+}
+
+#[derive(Deserialize)]
+pub struct BookingRequest {
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+}
+
+#[derive(Deserialize)]
+pub struct BookingTransitionPath {
+    booking_id: Uuid,
+    action: BookingAction,
+}
+
+/// A booking, along with what the guest and the host need to know about
+/// each other.
+#[derive(Serialize, sqlx::FromRow)]
+pub struct Booking {
+    #[serde(rename = "id")]
+    booking_id: Uuid,
+    #[serde(rename = "lair_id")]
+    room_id: Uuid,
+    lair_title: String,
+    host_id: Uuid,
+    guest_id: Uuid,
+    guest_name: String,
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+    status: BookingStatus,
+    created_at: DateTime<Utc>,
+}
+
+//#[post("/lair/{id}/bookings")]
+#[tracing::instrument(
+    name = "Booking a lair",
+    skip(path, pool, email_client, booking_request),
+    fields(user_id=%user)
+)]
+pub async fn book_lair(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    user: AuthenticatedUser,
+    booking_request: web::Json<BookingRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let stay = Stay::parse(
+        booking_request.check_in,
+        booking_request.check_out,
+        Utc::now().date_naive(),
+    )
+    .map_err(|e| ApiError::invalid("check_out", e))?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    let lair = sqlx::query!(
+        "SELECT account_id, min_nights, max_nights FROM rooms WHERE room_id = $1 FOR SHARE",
+        room_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to perform a query to retrieve a lair.")?
+    .ok_or(ApiError::NotFound("The lair does not exist."))?;
+    if lair.account_id == user.user_id() {
+        return Err(ApiError::Forbidden(
+            "You cannot book your own lair.".to_string(),
+        ));
+    }
+    StayLimits::parse(lair.min_nights, lair.max_nights)
+        .map_err(anyhow::Error::msg)
+        .context("The lair has invalid stay limits.")?
+        .check(&stay)
+        .map_err(|e| ApiError::invalid("check_out", e))?;
+
+    let booking_id = Uuid::new_v4();
+    insert_booking(booking_id, room_id, user.user_id(), &stay, &mut transaction).await?;
+    let booking = fetch_booking(booking_id, &mut *transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to store a new booking.")?;
+
+    notify(
+        &pool,
+        &email_client,
+        booking.host_id,
+        &booking_requested_email(&booking),
+    )
+    .await;
+    Ok(HttpResponse::Created().json(booking))
+}
+
+#[tracing::instrument(name = "Saving a new booking", skip(transaction))]
+async fn insert_booking(
+    booking_id: Uuid,
+    room_id: Uuid,
+    guest_id: Uuid,
+    stay: &Stay,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), ApiError> {
+    // Requests do not hold the dates, so the constraint would let this one
+    // through even though the host could never accept it.
+    if nights_are_taken(room_id, stay, &mut **transaction).await? {
+        return Err(ApiError::Conflict(
+            "The lair is not available for some of these nights.".to_string(),
+        ));
+    }
+
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO bookings (booking_id, room_id, guest_id, check_in, check_out, status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        booking_id,
+        room_id,
+        guest_id,
+        stay.check_in,
+        stay.check_out,
+        BookingStatus::Requested as BookingStatus,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to insert a new booking.")?;
+    Ok(())
+}
+
+/// Whether some nights of `stay` are already booked or blocked by the host.
+#[tracing::instrument(name = "Checking the availability of a lair", skip(executor))]
+pub async fn nights_are_taken<'c, E>(
+    room_id: Uuid,
+    stay: &Stay,
+    executor: E,
+) -> Result<bool, anyhow::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM lair_unavailability
+            WHERE room_id = $1 AND nights && daterange($2, $3)
+        ) AS "taken!"
+        "#,
+        room_id,
+        stay.check_in,
+        stay.check_out,
+    )
+    .fetch_one(executor)
+    .await
+    .context("Failed to check the availability of a lair.")
+}
+
+#[tracing::instrument(name = "Fetching a booking", skip(executor))]
+pub async fn fetch_booking<'c, E>(booking_id: Uuid, executor: E) -> Result<Booking, anyhow::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    fetch_bookings(BookingFilter::Id(booking_id), executor)
+        .await?
+        .pop()
+        .context("The booking does not exist.")
+}
+
+/// Fetches the bookings matching `filter`, soonest first.
+#[tracing::instrument(name = "Fetching bookings", skip(executor))]
+async fn fetch_bookings<'c, E>(
+    filter: BookingFilter,
+    executor: E,
+) -> Result<Vec<Booking>, anyhow::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"
+        SELECT b.booking_id, b.room_id, r.title AS lair_title, r.account_id AS host_id,
+            b.guest_id, u.account_name AS guest_name, b.check_in, b.check_out, b.status,
+            b.created_at
+        FROM bookings b
+        JOIN rooms r USING (room_id)
+        JOIN users u ON u.id = b.guest_id
+        WHERE "#,
+    );
+    match filter {
+        BookingFilter::Id(booking_id) => query.push("b.booking_id = ").push_bind(booking_id),
+        BookingFilter::Guest(guest_id) => query.push("b.guest_id = ").push_bind(guest_id),
+        BookingFilter::Host(host_id) => query.push("r.account_id = ").push_bind(host_id),
+    };
+    query.push(" ORDER BY b.check_in, b.booking_id");
+
+    query
+        .build_query_as::<Booking>()
+        .fetch_all(executor)
+        .await
+        .context("Failed to fetch bookings.")
+}
+
+//#[post("/bookings/{booking_id}/{action}")]
+/// Moves a booking along its lifecycle and lets the other side know.
+#[tracing::instrument(
+    name = "Changing the status of a booking",
+    skip(path, pool, email_client),
+    fields(user_id=%user, booking_id=%path.booking_id, action=?path.action)
+)]
+pub async fn transition_booking(
+    path: web::Path<BookingTransitionPath>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let BookingTransitionPath { booking_id, action } = path.into_inner();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    let current = sqlx::query!(
+        r#"
+        SELECT b.status AS "status: BookingStatus", b.guest_id, b.room_id, b.check_in,
+            b.check_out, r.account_id AS host_id
+        FROM bookings b JOIN rooms r USING (room_id)
+        WHERE b.booking_id = $1
+        -- Sharing the lock on the lair waits for the nights being blocked.
+        FOR UPDATE OF b FOR SHARE OF r
+        "#,
+        booking_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to perform a query to retrieve a booking.")?
+    .ok_or(ApiError::NotFound("The booking does not exist."))?;
+
+    let party = if user.user_id() == current.host_id {
+        BookingParty::Host
+    } else if user.user_id() == current.guest_id {
+        BookingParty::Guest
+    } else {
+        return Err(ApiError::NotFound("The booking does not exist."));
+    };
+    if !action.allowed_for(party) {
+        return Err(ApiError::Forbidden(
+            "Only the host can do this to a booking.".to_string(),
+        ));
+    }
+    let status = current.status.after(action).ok_or_else(|| {
+        ApiError::Conflict(format!(
+            "The booking is {} and cannot be changed this way.",
+            current.status
+        ))
+    })?;
+    // The constraint covers the other bookings, but not the blocked nights.
+    let stay = Stay {
+        check_in: current.check_in,
+        check_out: current.check_out,
+    };
+    if status == BookingStatus::Accepted
+        && nights_are_taken(current.room_id, &stay, &mut *transaction).await?
+    {
+        return Err(ApiError::Conflict(
+            "The lair is not available for some of these nights.".to_string(),
+        ));
+    }
+
+    let query = sqlx::query!(
+        "UPDATE bookings SET status = $2 WHERE booking_id = $1",
+        booking_id,
+        status as BookingStatus,
+    );
+    transaction.execute(query).await.map_err(|e| {
+        if violates_constraint(&e, NO_OVERLAP_CONSTRAINT) {
+            ApiError::Conflict(
+                "Another stay has already been accepted for some of these nights.".to_string(),
+            )
+        } else {
+            anyhow::Error::new(e)
+                .context("Failed to update the status of a booking.")
+                .into()
+        }
+    })?;
+    let booking = fetch_booking(booking_id, &mut *transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to change a booking.")?;
+
+    let recipient = match party {
+        BookingParty::Host => booking.guest_id,
+        BookingParty::Guest => booking.host_id,
+    };
+    notify(
+        &pool,
+        &email_client,
+        recipient,
+        &status_changed_email(&booking),
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(booking))
+}
+
+struct BookingEmail {
+    subject: String,
+    html: String,
+    text: String,
+}
+
+fn booking_requested_email(booking: &Booking) -> BookingEmail {
+    let text = format!(
+        "{} would like to stay in {} from {} to {}. Accept or decline the request from your bookings.",
+        booking.guest_name, booking.lair_title, booking.check_in, booking.check_out
+    );
+    BookingEmail {
+        subject: format!("New booking request for {}", booking.lair_title),
+        html: format!("<p>{}</p>", htmlescape::encode_minimal(&text)),
+        text,
+    }
+}
+
+fn status_changed_email(booking: &Booking) -> BookingEmail {
+    let text = format!(
+        "The stay of {} in {} from {} to {} is now {}.",
+        booking.guest_name, booking.lair_title, booking.check_in, booking.check_out, booking.status
+    );
+    BookingEmail {
+        subject: format!("Booking of {} {}", booking.lair_title, booking.status),
+        html: format!("<p>{}</p>", htmlescape::encode_minimal(&text)),
+        text,
+    }
+}
+
+/// Emails `user_id` about a booking. The booking has already changed by
+/// then, so a failure is logged rather than returned.
+#[tracing::instrument(
+    name = "Notifying a user about a booking",
+    skip(pool, email_client, email)
+)]
+async fn notify(pool: &PgPool, email_client: &EmailClient, user_id: Uuid, email: &BookingEmail) {
+    let sent: Result<(), anyhow::Error> = async {
+        let address = sqlx::query_scalar!("SELECT account_email FROM users WHERE id = $1", user_id)
+            .fetch_one(pool)
+            .await
+            .context("Failed to fetch the email address of a user.")?;
+        let recipient = SubscriberEmail::parse(address).map_err(anyhow::Error::msg)?;
+        email_client
+            .send_email(&recipient, &email.subject, &email.html, &email.text)
+            .await
+            .context("Failed to send a booking notification.")?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = sent {
+        tracing::error!(error.cause_chain = ?e, "Failed to notify a user about a booking.");
+    }
+}
+
+//#[get("/me/bookings")]
+/// The stays the user booked, soonest first.
+#[tracing::instrument(name = "Listing the bookings of a guest", skip(pool), fields(user_id=%user))]
+pub async fn my_bookings(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let bookings = fetch_bookings(BookingFilter::Guest(user.user_id()), pool.get_ref())
+        .await
+        .context("Failed to fetch the bookings of a guest.")?;
+    Ok(HttpResponse::Ok().json(json!({ "bookings": bookings })))
+}
+
+//#[get("/me/hosting/bookings")]
+/// The stays booked in the lairs of the user, soonest first.
+#[tracing::instrument(name = "Listing the bookings of a host", skip(pool), fields(user_id=%user))]
+pub async fn incoming_bookings(
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let bookings = fetch_bookings(BookingFilter::Host(user.user_id()), pool.get_ref())
+        .await
+        .context("Failed to fetch the bookings of a host.")?;
+    Ok(HttpResponse::Ok().json(json!({ "bookings": bookings })))
+}
@@ -10,6 +10,9 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
     pub email_client: EmailClientSettings,
+    pub map: MapSettings,
+    pub storage: StorageSettings,
+    pub remote_images: RemoteImageSettings,
     pub redis_uri: Secret<String>,
 }
 
@@ -22,6 +25,43 @@ pub struct ApplicationSettings {
     pub hmac_secret: Secret<String>,
 }
 
+/// How the map groups lairs when zoomed out.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct MapSettings {
+    /// Below this zoom level, the map receives clusters instead of lairs.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub cluster_below_zoom: u8,
+    /// The number of grid cells along the side of a 256px map tile.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub cluster_cells_per_tile: u32,
+}
+
+/// Where uploaded images are kept and served from.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct StorageSettings {
+    pub local_directory: String,
+    /// The path the stored files are served under, e.g. `/uploads`.
+    pub public_path: String,
+}
+
+/// Whether image URLs submitted with a lair are downloaded and rehosted.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct RemoteImageSettings {
+    pub enabled: bool,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_milliseconds: u64,
+    /// Lets the fetcher reach loopback and private networks. Only meant for
+    /// tests against a local mock server.
+    #[serde(default)]
+    pub allow_private_addresses: bool,
+}
+
+impl RemoteImageSettings {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout_milliseconds)
+    }
+}
+
 #[derive(serde::Deserialize, Clone)]
 pub struct DatabaseSettings {
     pub username: String,
@@ -0,0 +1,48 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug)]
+pub struct AmenityName(String);
+
+impl AmenityName {
+    /// Returns the name without its surrounding whitespace, as long as it is
+    /// not blank and at most 100 graphemes long.
+    pub fn parse(s: String) -> Result<AmenityName, String> {
+        let name = s.trim();
+        if name.is_empty() || name.graphemes(true).count() > 100 {
+            Err(format!("{} is not a valid amenity name.", s))
+        } else {
+            Ok(Self(name.to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for AmenityName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::AmenityName;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_name_is_trimmed() {
+        assert_eq!(
+            AmenityName::parse("  Wi-Fi ".to_string()).unwrap().as_ref(),
+            "Wi-Fi"
+        );
+    }
+
+    #[test]
+    fn a_blank_name_is_rejected() {
+        assert_err!(AmenityName::parse(" ".to_string()));
+    }
+
+    #[test]
+    fn a_name_longer_than_100_graphemes_is_rejected() {
+        assert_ok!(AmenityName::parse("a".repeat(100)));
+        assert_err!(AmenityName::parse("a".repeat(101)));
+    }
+}
@@ -0,0 +1,65 @@
+/// The longest slug of an amenity.
+const MAX_SLUG_LENGTH: usize = 50;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmenitySlug(String);
+
+impl AmenitySlug {
+    /// Returns an instance of `AmenitySlug` if the input is made of lower
+    /// case ASCII letters and digits, separated by single dashes.
+    pub fn parse(s: String) -> Result<AmenitySlug, String> {
+        let is_valid = !s.is_empty()
+            && s.len() <= MAX_SLUG_LENGTH
+            && s.split('-').all(|word| {
+                !word.is_empty()
+                    && word
+                        .bytes()
+                        .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+            });
+        if is_valid {
+            Ok(Self(s))
+        } else {
+            Err(format!("{} is not a valid amenity slug.", s))
+        }
+    }
+}
+
+impl AsRef<str> for AmenitySlug {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::AmenitySlug;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn lower_case_words_separated_by_dashes_are_valid() {
+        for slug in ["wifi", "pet-friendly", "step-free-access", "24h-check-in"] {
+            assert_ok!(AmenitySlug::parse(slug.to_string()));
+        }
+    }
+
+    #[test]
+    fn other_slugs_are_rejected() {
+        for slug in [
+            "",
+            "Wi-Fi",
+            "pet friendly",
+            "-wifi",
+            "wifi-",
+            "pet--friendly",
+            "café",
+        ] {
+            assert_err!(AmenitySlug::parse(slug.to_string()));
+        }
+    }
+
+    #[test]
+    fn a_slug_longer_than_50_characters_is_rejected() {
+        assert_ok!(AmenitySlug::parse("a".repeat(50)));
+        assert_err!(AmenitySlug::parse("a".repeat(51)));
+    }
+}
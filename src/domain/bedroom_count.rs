@@ -0,0 +1,43 @@
+/// The most bedrooms a single lair can have.
+const MAX_BEDROOMS: i32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BedroomCount(i32);
+
+impl BedroomCount {
+    /// Returns an instance of `BedroomCount` if the count is between 0, for
+    /// a studio, and `MAX_BEDROOMS`.
+    pub fn parse(bedrooms: i32) -> Result<BedroomCount, String> {
+        if (0..=MAX_BEDROOMS).contains(&bedrooms) {
+            Ok(Self(bedrooms))
+        } else {
+            Err(format!(
+                "The number of bedrooms must be between 0 and {}.",
+                MAX_BEDROOMS
+            ))
+        }
+    }
+}
+
+impl AsRef<i32> for BedroomCount {
+    fn as_ref(&self) -> &i32 {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::BedroomCount;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_studio_has_no_bedroom() {
+        assert_ok!(BedroomCount::parse(0));
+    }
+
+    #[test]
+    fn counts_out_of_range_are_rejected() {
+        assert_err!(BedroomCount::parse(-1));
+        assert_err!(BedroomCount::parse(51));
+    }
+}
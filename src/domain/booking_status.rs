@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a booking stands. Guests request a stay, which the host accepts or
+/// declines; accepted stays get checked in, then completed. Either side can
+/// cancel a stay that has not started yet.
+#[derive(Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BookingStatus {
+    Requested,
+    Accepted,
+    Declined,
+    CheckedIn,
+    Completed,
+    Cancelled,
+}
+
+/// What the host or the guest can do to a booking.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BookingAction {
+    Accept,
+    Decline,
+    CheckIn,
+    Complete,
+    Cancel,
+}
+
+/// The side of a booking someone is on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BookingParty {
+    Host,
+    Guest,
+}
+
+impl BookingStatus {
+    /// The status a booking moves to after `action`, if `action` can be taken
+    /// from this one.
+    pub fn after(self, action: BookingAction) -> Option<BookingStatus> {
+        use BookingAction::*;
+        use BookingStatus::*;
+
+        match (self, action) {
+            (Requested, Accept) => Some(Accepted),
+            (Requested, Decline) => Some(Declined),
+            (Requested | Accepted, Cancel) => Some(Cancelled),
+            (Accepted, CheckIn) => Some(CheckedIn),
+            (CheckedIn, Complete) => Some(Completed),
+            _ => None,
+        }
+    }
+}
+
+impl BookingAction {
+    /// Whether `party` may take this action. Only cancelling is open to
+    /// guests.
+    pub fn allowed_for(self, party: BookingParty) -> bool {
+        party == BookingParty::Host || self == BookingAction::Cancel
+    }
+}
+
+impl std::fmt::Display for BookingStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            BookingStatus::Requested => "requested",
+            BookingStatus::Accepted => "accepted",
+            BookingStatus::Declined => "declined",
+            BookingStatus::CheckedIn => "checked in",
+            BookingStatus::Completed => "completed",
+            BookingStatus::Cancelled => "cancelled",
+        };
+        f.write_str(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BookingAction::*;
+    use super::BookingParty::*;
+    use super::BookingStatus::*;
+    use claims::{assert_none, assert_some_eq};
+
+    #[test]
+    fn a_stay_goes_from_request_to_completion() {
+        let status = Requested.after(Accept).unwrap();
+        let status = status.after(CheckIn).unwrap();
+        assert_some_eq!(status.after(Complete), Completed);
+    }
+
+    #[test]
+    fn only_requests_can_be_accepted_or_declined() {
+        assert_some_eq!(Requested.after(Decline), Declined);
+        for status in [Accepted, Declined, CheckedIn, Completed, Cancelled] {
+            assert_none!(status.after(Accept));
+            assert_none!(status.after(Decline));
+        }
+    }
+
+    #[test]
+    fn only_stays_that_have_not_started_can_be_cancelled() {
+        assert_some_eq!(Requested.after(Cancel), Cancelled);
+        assert_some_eq!(Accepted.after(Cancel), Cancelled);
+        for status in [Declined, CheckedIn, Completed, Cancelled] {
+            assert_none!(status.after(Cancel));
+        }
+    }
+
+    #[test]
+    fn finished_bookings_cannot_change() {
+        for action in [Accept, Decline, CheckIn, Complete, Cancel] {
+            for status in [Declined, Completed, Cancelled] {
+                assert_none!(status.after(action));
+            }
+        }
+    }
+
+    #[test]
+    fn a_stay_cannot_be_checked_in_or_completed_out_of_order() {
+        assert_none!(Requested.after(CheckIn));
+        assert_none!(Requested.after(Complete));
+        assert_none!(Accepted.after(Complete));
+    }
+
+    #[test]
+    fn guests_can_only_cancel() {
+        assert!(Cancel.allowed_for(Guest));
+        for action in [Accept, Decline, CheckIn, Complete] {
+            assert!(!action.allowed_for(Guest));
+            assert!(action.allowed_for(Host));
+        }
+        assert!(Cancel.allowed_for(Host));
+    }
+}
@@ -0,0 +1,127 @@
+/// The part of the map a client is looking at.
+///
+/// Longitudes are split into at most two ranges, so that a viewport crossing
+/// the antimeridian (e.g. from 170 to 190, or from 170 to -170) matches the
+/// lairs on both sides of it. Viewports spanning the whole globe match every
+/// longitude.
+#[derive(Debug, PartialEq)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub north: f64,
+    pub lon_ranges: [(f64, f64); 2],
+}
+
+impl BoundingBox {
+    pub fn parse(tl_lat: f64, tl_lng: f64, br_lat: f64, br_lng: f64) -> Result<Self, String> {
+        if ![tl_lat, tl_lng, br_lat, br_lng]
+            .iter()
+            .all(|c| c.is_finite())
+        {
+            return Err("The bounding box coordinates must be finite numbers.".to_string());
+        }
+        if br_lat > tl_lat {
+            return Err(format!(
+                "The bottom latitude {} is north of the top latitude {}.",
+                br_lat, tl_lat
+            ));
+        }
+
+        let (west, east) = (tl_lng, br_lng);
+        let lon_ranges = if east - west >= 360.0 {
+            [(-180.0, 180.0); 2]
+        } else {
+            let (west, east) = (wrap(west), wrap(east));
+            if west <= east {
+                [(west, east); 2]
+            } else {
+                [(west, 180.0), (-180.0, east)]
+            }
+        };
+
+        Ok(Self {
+            south: br_lat,
+            north: tl_lat,
+            lon_ranges,
+        })
+    }
+
+    /// The `(lat, lon)` of the middle of the viewport.
+    pub fn centre(&self) -> (f64, f64) {
+        let lat = (self.south + self.north) / 2.0;
+        let [(west, _), (_, east)] = self.lon_ranges;
+        let lon = if west <= east {
+            (west + east) / 2.0
+        } else {
+            wrap((west + east + 360.0) / 2.0)
+        };
+        (lat, lon)
+    }
+}
+
+/// Brings a longitude back into -180..180.
+fn wrap(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::BoundingBox;
+    use claims::assert_err;
+
+    #[test]
+    fn a_regular_viewport_has_a_single_lon_range() {
+        let bbox = BoundingBox::parse(10.0, -20.0, -10.0, 20.0).unwrap();
+        assert_eq!(bbox.south, -10.0);
+        assert_eq!(bbox.north, 10.0);
+        assert_eq!(bbox.lon_ranges, [(-20.0, 20.0); 2]);
+    }
+
+    #[test]
+    fn an_unwrapped_viewport_over_the_antimeridian_is_split() {
+        let bbox = BoundingBox::parse(10.0, 170.0, -10.0, 190.0).unwrap();
+        assert_eq!(bbox.lon_ranges, [(170.0, 180.0), (-180.0, -170.0)]);
+    }
+
+    #[test]
+    fn a_wrapped_viewport_over_the_antimeridian_is_split() {
+        let bbox = BoundingBox::parse(10.0, 170.0, -10.0, -170.0).unwrap();
+        assert_eq!(bbox.lon_ranges, [(170.0, 180.0), (-180.0, -170.0)]);
+    }
+
+    #[test]
+    fn a_viewport_shifted_by_whole_turns_is_wrapped() {
+        let bbox = BoundingBox::parse(10.0, 350.0, -10.0, 370.0).unwrap();
+        assert_eq!(bbox.lon_ranges, [(-10.0, 10.0); 2]);
+    }
+
+    #[test]
+    fn a_world_wide_viewport_matches_every_lon() {
+        let bbox = BoundingBox::parse(85.0, -400.0, -85.0, 400.0).unwrap();
+        assert_eq!(bbox.lon_ranges, [(-180.0, 180.0); 2]);
+    }
+
+    #[test]
+    fn the_centre_of_a_regular_viewport_is_its_middle() {
+        let bbox = BoundingBox::parse(10.0, -20.0, -30.0, 40.0).unwrap();
+        assert_eq!(bbox.centre(), (-10.0, 10.0));
+    }
+
+    #[test]
+    fn the_centre_of_a_viewport_over_the_antimeridian_is_on_it() {
+        let bbox = BoundingBox::parse(10.0, 170.0, -10.0, -170.0).unwrap();
+        assert_eq!(bbox.centre(), (0.0, -180.0));
+        let bbox = BoundingBox::parse(10.0, 160.0, -10.0, -170.0).unwrap();
+        assert_eq!(bbox.centre(), (0.0, 175.0));
+    }
+
+    #[test]
+    fn an_upside_down_viewport_is_rejected() {
+        assert_err!(BoundingBox::parse(-10.0, -20.0, 10.0, 20.0));
+    }
+
+    #[test]
+    fn non_finite_coordinates_are_rejected() {
+        assert_err!(BoundingBox::parse(f64::NAN, -20.0, -10.0, 20.0));
+        assert_err!(BoundingBox::parse(10.0, f64::NEG_INFINITY, -10.0, 20.0));
+    }
+}
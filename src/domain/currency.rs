@@ -0,0 +1,42 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency(String);
+
+impl Currency {
+    /// Returns an instance of `Currency` if the input is a three-letter ISO
+    /// 4217 code, in upper case.
+    pub fn parse(s: String) -> Result<Currency, String> {
+        let code = s.trim().to_ascii_uppercase();
+        if code.len() == 3 && code.bytes().all(|byte| byte.is_ascii_uppercase()) {
+            Ok(Self(code))
+        } else {
+            Err(format!("{} is not a valid currency code.", s))
+        }
+    }
+}
+
+impl AsRef<str> for Currency {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Currency;
+    use claims::assert_err;
+
+    #[test]
+    fn a_currency_code_is_upper_cased() {
+        assert_eq!(
+            Currency::parse(" eur ".to_string()).unwrap().as_ref(),
+            "EUR"
+        );
+    }
+
+    #[test]
+    fn codes_that_are_not_three_letters_are_rejected() {
+        for code in ["", "EU", "EURO", "E1R", "€€€"] {
+            assert_err!(Currency::parse(code.to_string()));
+        }
+    }
+}
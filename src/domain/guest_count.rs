@@ -0,0 +1,45 @@
+/// The most guests a single lair can host.
+pub const MAX_GUESTS: i32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GuestCount(i32);
+
+impl GuestCount {
+    /// Returns an instance of `GuestCount` if there is at least one guest
+    /// and at most `MAX_GUESTS`.
+    pub fn parse(guests: i32) -> Result<GuestCount, String> {
+        if (1..=MAX_GUESTS).contains(&guests) {
+            Ok(Self(guests))
+        } else {
+            Err(format!(
+                "The number of guests must be between 1 and {}.",
+                MAX_GUESTS
+            ))
+        }
+    }
+}
+
+impl AsRef<i32> for GuestCount {
+    fn as_ref(&self) -> &i32 {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{GuestCount, MAX_GUESTS};
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn one_to_max_guests_are_valid() {
+        assert_ok!(GuestCount::parse(1));
+        assert_ok!(GuestCount::parse(MAX_GUESTS));
+    }
+
+    #[test]
+    fn counts_out_of_range_are_rejected() {
+        for guests in [-1, 0, MAX_GUESTS + 1] {
+            assert_err!(GuestCount::parse(guests));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use unicode_segmentation::UnicodeSegmentation;
+use url::Url;
 
 #[derive(Debug)]
 pub struct LairImage(String);
@@ -22,7 +23,11 @@ impl LairImage {
         // the recommended one.
         let is_too_long = s.graphemes(true).count() > 100000;
 
-        if is_empty_or_whitespace || is_too_long {
+        // Absolute URLs must point to the web, like `file:` ones cannot.
+        let has_foreign_scheme =
+            Url::parse(&s).is_ok_and(|url| !matches!(url.scheme(), "http" | "https"));
+
+        if is_empty_or_whitespace || is_too_long || has_foreign_scheme {
             Err(format!("{} is not a valid lair image.", s))
         } else {
             Ok(Self(s))
@@ -65,6 +70,20 @@ mod tests {
         assert_err!(LairImage::parse(image));
     }
 
+    #[test]
+    fn urls_outside_of_the_web_are_rejected() {
+        for image in ["file:///etc/passwd", "ftp://example.com/lair.png"] {
+            assert_err!(LairImage::parse(image.to_string()));
+        }
+    }
+
+    #[test]
+    fn web_and_relative_urls_are_valid() {
+        for image in ["https://example.com/lair.png", "/uploads/lairs/lair.png"] {
+            assert_ok!(LairImage::parse(image.to_string()));
+        }
+    }
+
     #[test]
     fn a_valid_image_is_parsed_successfully() {
         let image = "Welcome to lair Kefir".to_string();
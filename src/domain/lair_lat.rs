@@ -2,16 +2,15 @@
 pub struct LairLat(f64);
 
 impl LairLat {
-    /// Returns an instance of `SubscriberName` if the input satisfies all
-    /// our validation constraints on subscriber names.  
-    /// It panics otherwise.
+    /// Returns an instance of `LairLat` if the input is a finite latitude
+    /// between -90 and 90 degrees.
     pub fn parse(s: f64) -> Result<LairLat, String> {
-        let is_a_float = s.is_nan();
+        let is_in_range = s.is_finite() && (-90.0..=90.0).contains(&s);
 
-        if is_a_float {
-            Err(format!("{} is not a valid lon value.", s))
-        } else {
+        if is_in_range {
             Ok(Self(s))
+        } else {
+            Err(format!("{} is not a valid latitude.", s))
         }
     }
 }
@@ -25,11 +24,45 @@ impl AsRef<f64> for LairLat {
 #[cfg(test)]
 mod tests {
     use crate::domain::LairLat;
-    use claims::assert_ok;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_valid_lat_is_parsed_successfully() {
+        let lat = 1.1;
+        assert_ok!(LairLat::parse(lat));
+    }
+
+    #[test]
+    fn the_poles_are_valid() {
+        assert_ok!(LairLat::parse(90.0));
+        assert_ok!(LairLat::parse(-90.0));
+    }
+
+    #[test]
+    fn a_lat_beyond_the_poles_is_rejected() {
+        assert_err!(LairLat::parse(90.000001));
+        assert_err!(LairLat::parse(-9000.0));
+    }
 
     #[test]
-    fn a_valid_lon_is_parsed_successfully() {
-        let lon = 1.1;
-        assert_ok!(LairLat::parse(lon));
+    fn non_finite_lats_are_rejected() {
+        for lat in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_err!(LairLat::parse(lat));
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct AnyLatFixture(pub f64);
+
+    impl quickcheck::Arbitrary for AnyLatFixture {
+        fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
+            // Spread the samples well beyond the valid range.
+            Self(f64::arbitrary(g) * 3.0)
+        }
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn only_lats_between_the_poles_are_accepted(lat: AnyLatFixture) -> bool {
+        LairLat::parse(lat.0).is_ok() == (-90.0..=90.0).contains(&lat.0)
     }
 }
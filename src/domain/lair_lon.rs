@@ -2,16 +2,18 @@
 pub struct LairLon(f64);
 
 impl LairLon {
-    /// Returns an instance of `SubscriberName` if the input satisfies all
-    /// our validation constraints on subscriber names.  
-    /// It panics otherwise.
+    /// Returns an instance of `LairLon` if the input is a finite longitude.
+    /// Longitudes outside of -180..=180 degrees are wrapped back into that
+    /// range, e.g. 190 becomes -170.
     pub fn parse(s: f64) -> Result<LairLon, String> {
-        let is_a_float = s.is_nan();
+        if !s.is_finite() {
+            return Err(format!("{} is not a valid longitude.", s));
+        }
 
-        if is_a_float {
-            Err(format!("{} is not a valid lon value.", s))
-        } else {
+        if (-180.0..=180.0).contains(&s) {
             Ok(Self(s))
+        } else {
+            Ok(Self((s + 180.0).rem_euclid(360.0) - 180.0))
         }
     }
 }
@@ -25,11 +27,53 @@ impl AsRef<f64> for LairLon {
 #[cfg(test)]
 mod tests {
     use crate::domain::LairLon;
-    use claims::assert_ok;
+    use claims::{assert_err, assert_ok};
 
     #[test]
     fn a_valid_lon_is_parsed_successfully() {
         let lon = 1.1;
         assert_ok!(LairLon::parse(lon));
     }
+
+    #[test]
+    fn the_antimeridian_is_kept_as_is() {
+        assert_eq!(*LairLon::parse(180.0).unwrap().as_ref(), 180.0);
+        assert_eq!(*LairLon::parse(-180.0).unwrap().as_ref(), -180.0);
+    }
+
+    #[test]
+    fn lons_outside_the_range_are_wrapped() {
+        assert_eq!(*LairLon::parse(190.0).unwrap().as_ref(), -170.0);
+        assert_eq!(*LairLon::parse(-190.0).unwrap().as_ref(), 170.0);
+        assert_eq!(*LairLon::parse(725.0).unwrap().as_ref(), 5.0);
+    }
+
+    #[test]
+    fn non_finite_lons_are_rejected() {
+        for lon in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_err!(LairLon::parse(lon));
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct AnyLonFixture(pub f64);
+
+    impl quickcheck::Arbitrary for AnyLonFixture {
+        fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
+            // Spread the samples over several turns around the globe.
+            Self(f64::arbitrary(g) * 20.0)
+        }
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn finite_lons_are_wrapped_into_range(lon: AnyLonFixture) -> bool {
+        let parsed = *LairLon::parse(lon.0).unwrap().as_ref();
+        (-180.0..=180.0).contains(&parsed)
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn lons_in_range_are_left_untouched(lon: AnyLonFixture) -> bool {
+        let lon = lon.0 % 180.0;
+        *LairLon::parse(lon).unwrap().as_ref() == lon
+    }
 }
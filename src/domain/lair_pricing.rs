@@ -0,0 +1,12 @@
+use super::{Currency, Price};
+
+/// What a night in a lair costs, in `currency`.
+#[derive(Debug)]
+pub struct LairPricing {
+    pub nightly_price: Price,
+    /// Replaces `nightly_price` on Friday and Saturday nights.
+    pub weekend_price: Option<Price>,
+    /// Charged once per stay.
+    pub cleaning_fee: Price,
+    pub currency: Currency,
+}
@@ -1,21 +1,55 @@
+mod amenity_name;
+mod amenity_slug;
+mod bedroom_count;
+mod booking_status;
+mod bounding_box;
+mod currency;
+mod guest_count;
 mod lair_description;
 mod lair_image;
 mod lair_lat;
 mod lair_lon;
+mod lair_pricing;
 mod lair_title;
+mod new_amenity;
 mod new_lair;
 mod new_subscriber;
+mod photo_caption;
+mod price;
+mod review_rating;
+mod review_text;
+mod stay;
+mod stay_limits;
 mod subscriber_email;
 mod subscriber_name;
 mod subscriber_password;
+mod tile_id;
+mod wishlist_name;
 
+pub use amenity_name::AmenityName;
+pub use amenity_slug::AmenitySlug;
+pub use bedroom_count::BedroomCount;
+pub use booking_status::{BookingAction, BookingParty, BookingStatus};
+pub use bounding_box::BoundingBox;
+pub use currency::Currency;
+pub use guest_count::{GuestCount, MAX_GUESTS};
 pub use lair_description::LairDescription;
 pub use lair_image::LairImage;
 pub use lair_lat::LairLat;
 pub use lair_lon::LairLon;
+pub use lair_pricing::LairPricing;
 pub use lair_title::LairTitle;
+pub use new_amenity::NewAmenity;
 pub use new_lair::NewLair;
 pub use new_subscriber::NewSubscriber;
+pub use photo_caption::PhotoCaption;
+pub use price::Price;
+pub use review_rating::{ReviewRating, MAX_RATING, MIN_RATING};
+pub use review_text::ReviewText;
+pub use stay::{Stay, MAX_NIGHTS};
+pub use stay_limits::StayLimits;
 pub use subscriber_email::SubscriberEmail;
 pub use subscriber_name::SubscriberName;
 pub use subscriber_password::SubscriberPassword;
+pub use tile_id::TileId;
+pub use wishlist_name::WishlistName;
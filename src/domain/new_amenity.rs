@@ -0,0 +1,8 @@
+use super::{AmenityName, AmenitySlug};
+
+pub struct NewAmenity {
+    pub slug: AmenitySlug,
+    pub name: AmenityName,
+    /// Groups amenities in the catalogue, e.g. `Accessibility`.
+    pub category: AmenityName,
+}
@@ -1,4 +1,7 @@
-use super::{LairDescription, LairImage, LairLat, LairLon, LairTitle};
+use super::{
+    BedroomCount, GuestCount, LairDescription, LairImage, LairLat, LairLon, LairPricing, LairTitle,
+    StayLimits,
+};
 
 pub struct NewLair {
     pub title: LairTitle,
@@ -6,4 +9,9 @@ pub struct NewLair {
     pub image: LairImage,
     pub lon: LairLon,
     pub lat: LairLat,
+    pub max_guests: GuestCount,
+    pub bedrooms: BedroomCount,
+    /// `None` until the host sets a nightly price.
+    pub pricing: Option<LairPricing>,
+    pub stay_limits: StayLimits,
 }
@@ -0,0 +1,52 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug)]
+pub struct PhotoCaption(String);
+
+impl PhotoCaption {
+    /// Returns the caption without its surrounding whitespace, as long as it
+    /// is not blank and at most 300 graphemes long.
+    pub fn parse(s: String) -> Result<PhotoCaption, String> {
+        let caption = s.trim();
+        if caption.is_empty() || caption.graphemes(true).count() > 300 {
+            Err(format!("{} is not a valid photo caption.", s))
+        } else {
+            Ok(Self(caption.to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for PhotoCaption {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::PhotoCaption;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_300_grapheme_long_caption_is_valid() {
+        let caption = "a̐".repeat(300);
+        assert_ok!(PhotoCaption::parse(caption));
+    }
+
+    #[test]
+    fn a_caption_longer_than_300_graphemes_is_rejected() {
+        let caption = "a".repeat(301);
+        assert_err!(PhotoCaption::parse(caption));
+    }
+
+    #[test]
+    fn whitespace_only_captions_are_rejected() {
+        assert_err!(PhotoCaption::parse(" \n".to_string()));
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_trimmed() {
+        let caption = PhotoCaption::parse("  The lava pool \n".to_string()).unwrap();
+        assert_eq!(caption.as_ref(), "The lava pool");
+    }
+}
@@ -0,0 +1,63 @@
+use rust_decimal::Decimal;
+
+/// The highest price of a night or a fee, to catch typos.
+const MAX_PRICE: i64 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price(Decimal);
+
+impl Price {
+    pub const ZERO: Price = Price(Decimal::ZERO);
+
+    /// Returns an instance of `Price` if the amount is not negative, has at
+    /// most two decimal places and stays below `MAX_PRICE`.
+    pub fn parse(amount: Decimal) -> Result<Price, String> {
+        if amount.is_sign_negative()
+            || amount > Decimal::from(MAX_PRICE)
+            || amount.normalize().scale() > 2
+        {
+            Err(format!("{} is not a valid price.", amount))
+        } else {
+            Ok(Self(amount.normalize()))
+        }
+    }
+}
+
+impl AsRef<Decimal> for Price {
+    fn as_ref(&self) -> &Decimal {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Price;
+    use claims::{assert_err, assert_ok};
+    use rust_decimal::Decimal;
+
+    fn decimal(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn prices_with_up_to_two_decimal_places_are_valid() {
+        for amount in ["0", "120", "99.9", "99.95", "1000000", "12.500"] {
+            assert_ok!(Price::parse(decimal(amount)));
+        }
+    }
+
+    #[test]
+    fn fractions_of_a_cent_are_rejected() {
+        assert_err!(Price::parse(decimal("99.999")));
+    }
+
+    #[test]
+    fn negative_prices_are_rejected() {
+        assert_err!(Price::parse(decimal("-1")));
+    }
+
+    #[test]
+    fn prices_above_the_maximum_are_rejected() {
+        assert_err!(Price::parse(decimal("1000000.01")));
+    }
+}
@@ -0,0 +1,48 @@
+/// The fewest stars a review can give.
+pub const MIN_RATING: i32 = 1;
+/// The most stars a review can give.
+pub const MAX_RATING: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReviewRating(i32);
+
+impl ReviewRating {
+    /// Returns an instance of `ReviewRating` if the number of stars is
+    /// between `MIN_RATING` and `MAX_RATING`.
+    pub fn parse(stars: i32) -> Result<ReviewRating, String> {
+        if (MIN_RATING..=MAX_RATING).contains(&stars) {
+            Ok(Self(stars))
+        } else {
+            Err(format!(
+                "A rating must be between {} and {} stars.",
+                MIN_RATING, MAX_RATING
+            ))
+        }
+    }
+}
+
+impl AsRef<i32> for ReviewRating {
+    fn as_ref(&self) -> &i32 {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{ReviewRating, MAX_RATING, MIN_RATING};
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn one_to_five_stars_are_valid() {
+        for stars in MIN_RATING..=MAX_RATING {
+            assert_ok!(ReviewRating::parse(stars));
+        }
+    }
+
+    #[test]
+    fn ratings_out_of_range_are_rejected() {
+        for stars in [-1, 0, 6] {
+            assert_err!(ReviewRating::parse(stars));
+        }
+    }
+}
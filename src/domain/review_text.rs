@@ -0,0 +1,59 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The longest review or reply, in graphemes.
+const MAX_REVIEW_LENGTH: usize = 5000;
+
+#[derive(Debug)]
+pub struct ReviewText(String);
+
+impl ReviewText {
+    /// Returns the text of a review or of the reply of a host without its
+    /// surrounding whitespace, as long as it is not blank and at most
+    /// `MAX_REVIEW_LENGTH` graphemes long.
+    pub fn parse(s: String) -> Result<ReviewText, String> {
+        let text = s.trim();
+        if text.is_empty() {
+            Err("A review cannot be blank.".to_string())
+        } else if text.graphemes(true).count() > MAX_REVIEW_LENGTH {
+            Err(format!(
+                "A review cannot be longer than {} characters.",
+                MAX_REVIEW_LENGTH
+            ))
+        } else {
+            Ok(Self(text.to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for ReviewText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::ReviewText;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_review_is_trimmed() {
+        assert_eq!(
+            ReviewText::parse(" Lovely moat.\n".to_string())
+                .unwrap()
+                .as_ref(),
+            "Lovely moat."
+        );
+    }
+
+    #[test]
+    fn a_blank_review_is_rejected() {
+        assert_err!(ReviewText::parse(" \n\t".to_string()));
+    }
+
+    #[test]
+    fn a_review_longer_than_5000_graphemes_is_rejected() {
+        assert_ok!(ReviewText::parse("ё".repeat(5000)));
+        assert_err!(ReviewText::parse("ё".repeat(5001)));
+    }
+}
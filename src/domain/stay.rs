@@ -0,0 +1,104 @@
+use chrono::NaiveDate;
+
+/// The longest stay that can be booked in one go, in nights.
+pub const MAX_NIGHTS: i64 = 365;
+
+/// The nights from `check_in` until the morning of `check_out`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stay {
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+}
+
+impl Stay {
+    /// Returns the stay if it lasts at least one night, at most `MAX_NIGHTS`,
+    /// and does not start before `today`.
+    pub fn parse(
+        check_in: NaiveDate,
+        check_out: NaiveDate,
+        today: NaiveDate,
+    ) -> Result<Self, String> {
+        if check_out <= check_in {
+            return Err(format!(
+                "The check-out date {} is not after the check-in date {}.",
+                check_out, check_in
+            ));
+        }
+        if check_in < today {
+            return Err(format!("The check-in date {} is in the past.", check_in));
+        }
+        let stay = Self {
+            check_in,
+            check_out,
+        };
+        if stay.nights() > MAX_NIGHTS {
+            return Err(format!(
+                "A stay cannot last more than {} nights.",
+                MAX_NIGHTS
+            ));
+        }
+        Ok(stay)
+    }
+
+    pub fn nights(&self) -> i64 {
+        (self.check_out - self.check_in).num_days()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Stay, MAX_NIGHTS};
+    use chrono::{Duration, NaiveDate};
+    use claims::{assert_err, assert_ok};
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn a_stay_of_one_night_is_valid() {
+        let stay = Stay::parse(date("2026-11-01"), date("2026-11-02"), date("2026-10-18")).unwrap();
+        assert_eq!(stay.nights(), 1);
+    }
+
+    #[test]
+    fn a_stay_can_start_today() {
+        assert_ok!(Stay::parse(
+            date("2026-10-18"),
+            date("2026-10-20"),
+            date("2026-10-18")
+        ));
+    }
+
+    #[test]
+    fn a_stay_must_last_at_least_one_night() {
+        let today = date("2026-10-18");
+        assert_err!(Stay::parse(date("2026-11-01"), date("2026-11-01"), today));
+        assert_err!(Stay::parse(date("2026-11-02"), date("2026-11-01"), today));
+    }
+
+    #[test]
+    fn a_stay_in_the_past_is_rejected() {
+        assert_err!(Stay::parse(
+            date("2026-10-17"),
+            date("2026-10-20"),
+            date("2026-10-18")
+        ));
+    }
+
+    #[test]
+    fn a_stay_over_the_maximum_length_is_rejected() {
+        let check_in = date("2026-11-01");
+        let today = date("2026-10-18");
+        assert_ok!(Stay::parse(
+            check_in,
+            check_in + Duration::days(MAX_NIGHTS),
+            today
+        ));
+        assert_err!(Stay::parse(
+            check_in,
+            check_in + Duration::days(MAX_NIGHTS + 1),
+            today
+        ));
+    }
+}
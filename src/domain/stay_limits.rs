@@ -0,0 +1,119 @@
+use super::{Stay, MAX_NIGHTS};
+
+/// How short and how long the stays in a lair can be, in nights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StayLimits {
+    min_nights: i32,
+    max_nights: Option<i32>,
+}
+
+impl StayLimits {
+    /// Returns the limits if both are between 1 and `MAX_NIGHTS` nights and
+    /// the maximum is not below the minimum.
+    pub fn parse(min_nights: i32, max_nights: Option<i32>) -> Result<StayLimits, String> {
+        let in_range = |nights: i32| (1..=MAX_NIGHTS).contains(&i64::from(nights));
+        if !in_range(min_nights) {
+            return Err(format!(
+                "The minimum stay must be between 1 and {} nights.",
+                MAX_NIGHTS
+            ));
+        }
+        match max_nights {
+            Some(max_nights) if !in_range(max_nights) => Err(format!(
+                "The maximum stay must be between 1 and {} nights.",
+                MAX_NIGHTS
+            )),
+            Some(max_nights) if max_nights < min_nights => Err(format!(
+                "The maximum stay cannot be shorter than the minimum stay of {} nights.",
+                min_nights
+            )),
+            _ => Ok(Self {
+                min_nights,
+                max_nights,
+            }),
+        }
+    }
+
+    pub fn min_nights(&self) -> i32 {
+        self.min_nights
+    }
+
+    pub fn max_nights(&self) -> Option<i32> {
+        self.max_nights
+    }
+
+    /// Checks that `stay` lasts long enough, but not too long.
+    pub fn check(&self, stay: &Stay) -> Result<(), String> {
+        if stay.nights() < i64::from(self.min_nights) {
+            return Err(format!(
+                "This lair can only be booked for {} nights or more.",
+                self.min_nights
+            ));
+        }
+        match self.max_nights {
+            Some(max_nights) if stay.nights() > i64::from(max_nights) => Err(format!(
+                "This lair can only be booked for {} nights or less.",
+                max_nights
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for StayLimits {
+    /// Any stay that `Stay::parse` accepts.
+    fn default() -> Self {
+        Self {
+            min_nights: 1,
+            max_nights: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{Stay, StayLimits, MAX_NIGHTS};
+    use chrono::{Duration, NaiveDate};
+    use claims::{assert_err, assert_ok};
+
+    fn stay_of(nights: i64) -> Stay {
+        let check_in: NaiveDate = "2026-11-01".parse().unwrap();
+        Stay::parse(check_in, check_in + Duration::days(nights), check_in).unwrap()
+    }
+
+    #[test]
+    fn limits_without_a_maximum_are_valid() {
+        assert_ok!(StayLimits::parse(1, None));
+        assert_ok!(StayLimits::parse(MAX_NIGHTS as i32, None));
+    }
+
+    #[test]
+    fn limits_out_of_range_are_rejected() {
+        assert_err!(StayLimits::parse(0, None));
+        assert_err!(StayLimits::parse(MAX_NIGHTS as i32 + 1, None));
+        assert_err!(StayLimits::parse(1, Some(0)));
+        assert_err!(StayLimits::parse(1, Some(MAX_NIGHTS as i32 + 1)));
+    }
+
+    #[test]
+    fn a_maximum_below_the_minimum_is_rejected() {
+        assert_ok!(StayLimits::parse(3, Some(3)));
+        assert_err!(StayLimits::parse(3, Some(2)));
+    }
+
+    #[test]
+    fn stays_must_fit_within_the_limits() {
+        let limits = StayLimits::parse(2, Some(7)).unwrap();
+        assert_err!(limits.check(&stay_of(1)));
+        assert_ok!(limits.check(&stay_of(2)));
+        assert_ok!(limits.check(&stay_of(7)));
+        assert_err!(limits.check(&stay_of(8)));
+    }
+
+    #[test]
+    fn the_default_limits_accept_any_stay() {
+        let limits = StayLimits::default();
+        assert_ok!(limits.check(&stay_of(1)));
+        assert_ok!(limits.check(&stay_of(MAX_NIGHTS)));
+    }
+}
@@ -0,0 +1,97 @@
+use std::f64::consts::PI;
+
+/// The deepest zoom level tiles are served at.
+const MAX_TILE_ZOOM: u8 = 22;
+
+/// An XYZ tile of the Web Mercator map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileId {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    /// Returns an instance of `TileId` if the zoom level is supported and the
+    /// tile exists at that zoom level.
+    pub fn parse(z: u8, x: u32, y: u32) -> Result<TileId, String> {
+        if z > MAX_TILE_ZOOM {
+            return Err(format!(
+                "{} is not a valid zoom level, the maximum is {}.",
+                z, MAX_TILE_ZOOM
+            ));
+        }
+        let tiles = 1u64 << z;
+        if u64::from(x) >= tiles || u64::from(y) >= tiles {
+            return Err(format!("{}/{}/{} is not a valid tile.", z, x, y));
+        }
+        Ok(Self { z, x, y })
+    }
+
+    fn tiles_per_side(&self) -> f64 {
+        (1u64 << self.z) as f64
+    }
+
+    /// The `(north, west, south, east)` edges of the tile, in degrees.
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        let n = self.tiles_per_side();
+        let lon = |x: f64| x / n * 360.0 - 180.0;
+        let lat = |y: f64| (PI * (1.0 - 2.0 * y / n)).sinh().atan().to_degrees();
+        let (x, y) = (f64::from(self.x), f64::from(self.y));
+        (lat(y), lon(x), lat(y + 1.0), lon(x + 1.0))
+    }
+
+    /// Projects a point to the coordinates of the tile, `(0, 0)` being its
+    /// top left corner and `(extent, extent)` its bottom right one.
+    pub fn project(&self, lat: f64, lon: f64, extent: u32) -> (i32, i32) {
+        let n = self.tiles_per_side();
+        let world_x = (lon + 180.0) / 360.0 * n;
+        let lat = lat.to_radians();
+        let world_y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+        let extent = f64::from(extent);
+        (
+            ((world_x - f64::from(self.x)) * extent).round() as i32,
+            ((world_y - f64::from(self.y)) * extent).round() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::TileId;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn the_whole_world_is_a_valid_tile() {
+        assert_ok!(TileId::parse(0, 0, 0));
+    }
+
+    #[test]
+    fn tiles_beyond_the_edge_of_the_map_are_rejected() {
+        assert_err!(TileId::parse(0, 1, 0));
+        assert_err!(TileId::parse(2, 0, 4));
+    }
+
+    #[test]
+    fn zoom_levels_beyond_the_maximum_are_rejected() {
+        assert_err!(TileId::parse(23, 0, 0));
+    }
+
+    #[test]
+    fn the_world_tile_covers_the_mercator_map() {
+        let (north, west, south, east) = TileId::parse(0, 0, 0).unwrap().bounds();
+        assert!((north - 85.0511).abs() < 1e-4);
+        assert_eq!(west, -180.0);
+        assert!((south + 85.0511).abs() < 1e-4);
+        assert_eq!(east, 180.0);
+    }
+
+    #[test]
+    fn points_are_projected_inside_their_tile() {
+        let tile = TileId::parse(1, 1, 0).unwrap();
+        assert_eq!(tile.project(0.0, 0.0, 4096), (0, 4096));
+        assert_eq!(tile.project(0.0, 90.0, 4096), (2048, 4096));
+        let (x, y) = tile.project(48.8530, 2.3499, 4096);
+        assert!((0..4096).contains(&x) && (0..4096).contains(&y));
+    }
+}
@@ -0,0 +1,51 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug)]
+pub struct WishlistName(String);
+
+impl WishlistName {
+    /// Returns the name without its surrounding whitespace, as long as it is
+    /// not blank and at most 50 graphemes long.
+    pub fn parse(s: String) -> Result<WishlistName, String> {
+        let name = s.trim();
+        if name.is_empty() || name.graphemes(true).count() > 50 {
+            Err(format!("{} is not a valid wishlist name.", s))
+        } else {
+            Ok(Self(name.to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for WishlistName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::WishlistName;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_name_is_trimmed() {
+        assert_eq!(
+            WishlistName::parse(" Summer lairs ".to_string())
+                .unwrap()
+                .as_ref(),
+            "Summer lairs"
+        );
+    }
+
+    #[test]
+    fn a_blank_name_is_rejected() {
+        assert_err!(WishlistName::parse("".to_string()));
+        assert_err!(WishlistName::parse("   ".to_string()));
+    }
+
+    #[test]
+    fn a_name_longer_than_50_graphemes_is_rejected() {
+        assert_ok!(WishlistName::parse("å".repeat(50)));
+        assert_err!(WishlistName::parse("å".repeat(51)));
+    }
+}
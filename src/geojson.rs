@@ -0,0 +1,188 @@
+use actix_web::dev::Payload;
+use actix_web::http::header::{self, ContentType};
+use actix_web::{error, web, FromRequest, HttpRequest, HttpResponse};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::future::{ready, Ready};
+
+pub const GEOJSON_MIME_TYPE: &str = "application/geo+json";
+
+/// The representation a client asked for, either with `?format=geojson` or
+/// with an `Accept: application/geo+json` header. The query parameter wins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResponseFormat {
+    Json,
+    GeoJson,
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    format: Option<String>,
+}
+
+impl FromRequest for ResponseFormat {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<ResponseFormat, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let format = web::Query::<FormatQuery>::from_query(req.query_string())
+            .ok()
+            .and_then(|query| query.into_inner().format);
+        let format = match format.as_deref() {
+            Some("geojson") => Ok(ResponseFormat::GeoJson),
+            Some("json") => Ok(ResponseFormat::Json),
+            Some(other) => Err(error::ErrorBadRequest(format!(
+                "{} is not a supported format. Use either `json` or `geojson`.",
+                other
+            ))),
+            None => Ok(accepted_format(req)),
+        };
+        ready(format)
+    }
+}
+
+fn accepted_format(req: &HttpRequest) -> ResponseFormat {
+    let accepts_geojson = req
+        .headers()
+        .get_all(header::ACCEPT)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_range| {
+            media_range
+                .split(';')
+                .next()
+                .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(GEOJSON_MIME_TYPE))
+        });
+    if accepts_geojson {
+        ResponseFormat::GeoJson
+    } else {
+        ResponseFormat::Json
+    }
+}
+
+/// An RFC 7946 `Feature` with a `Point` geometry.
+#[derive(Debug, Serialize)]
+pub struct Feature {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<Value>,
+    geometry: Point,
+    properties: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct Point {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// `[longitude, latitude]`, in that order.
+    coordinates: [f64; 2],
+}
+
+impl Feature {
+    /// Turns anything serialised with `lon` and `lat` fields, and optionally
+    /// an `id`, into a point feature carrying its other fields as properties.
+    pub fn point(item: &impl Serialize) -> Result<Self, anyhow::Error> {
+        let Value::Object(mut properties) = serde_json::to_value(item)? else {
+            anyhow::bail!("Only structs can be turned into features.");
+        };
+        let mut coordinate = |name: &str| {
+            properties
+                .remove(name)
+                .and_then(|value| value.as_f64())
+                .with_context(|| format!("A feature needs a numeric `{}`.", name))
+        };
+        let lon = coordinate("lon")?;
+        let lat = coordinate("lat")?;
+        let id = properties.remove("id");
+        Ok(Self {
+            kind: "Feature",
+            id,
+            geometry: Point {
+                kind: "Point",
+                coordinates: [lon, lat],
+            },
+            properties,
+        })
+    }
+}
+
+/// An RFC 7946 `FeatureCollection`.
+#[derive(Serialize)]
+pub struct FeatureCollection {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    features: Vec<Feature>,
+    /// Foreign members, such as the cursor of the next page.
+    #[serde(flatten)]
+    members: Map<String, Value>,
+}
+
+impl FeatureCollection {
+    pub fn of_points<'a, T: Serialize + 'a>(
+        items: impl IntoIterator<Item = &'a T>,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            kind: "FeatureCollection",
+            features: items
+                .into_iter()
+                .map(Feature::point)
+                .collect::<Result<_, _>>()?,
+            members: Map::new(),
+        })
+    }
+
+    pub fn with_member(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.members.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+pub fn geojson_response(body: &impl Serialize) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType(GEOJSON_MIME_TYPE.parse().unwrap()))
+        .json(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Feature, FeatureCollection};
+    use claims::assert_err;
+    use serde_json::json;
+
+    #[test]
+    fn coordinates_and_id_are_moved_out_of_the_properties() {
+        let lair = json!({"id": "abc", "title": "Volcano lair", "lon": 2.5, "lat": 48.0});
+        let feature = serde_json::to_value(Feature::point(&lair).unwrap()).unwrap();
+        assert_eq!(
+            feature,
+            json!({
+                "type": "Feature",
+                "id": "abc",
+                "geometry": {"type": "Point", "coordinates": [2.5, 48.0]},
+                "properties": {"title": "Volcano lair"},
+            })
+        );
+    }
+
+    #[test]
+    fn an_item_without_coordinates_is_rejected() {
+        assert_err!(Feature::point(&json!({"title": "Nowhere"})));
+        assert_err!(Feature::point(&json!({"lon": "east", "lat": 1.0})));
+        assert_err!(Feature::point(&json!([1.0, 2.0])));
+    }
+
+    #[test]
+    fn a_collection_carries_its_foreign_members() {
+        let points = [json!({"lon": 1.0, "lat": 2.0})];
+        let collection = FeatureCollection::of_points(&points)
+            .unwrap()
+            .with_member("next_cursor", json!(null));
+        let collection = serde_json::to_value(collection).unwrap();
+        assert_eq!(collection["type"], "FeatureCollection");
+        assert_eq!(collection["features"].as_array().unwrap().len(), 1);
+        assert!(collection["next_cursor"].is_null());
+        assert!(collection.as_object().unwrap().contains_key("next_cursor"));
+    }
+}
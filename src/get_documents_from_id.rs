@@ -1,11 +1,21 @@
 use crate::{
-    create_cookie::{extract_cookie, UserInfo},
-    routes::error_chain_fmt,
+    amenities::{fetch_lair_amenities, Amenity},
+    api_error::ApiError,
+    authentication::AuthenticatedUser,
+    domain::NewLair,
+    geojson::{geojson_response, Feature, ResponseFormat},
+    image_storage::ImageStorage,
+    lair_images::{
+        delete_stored_files, fetch_lair_photos, insert_cover_photo, rehost_image, LairPhoto,
+        StoredImage,
+    },
+    remote_images::RemoteImageFetcher,
+    routes::LairInfo,
 };
-use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use actix_web::{web, HttpResponse};
 use actix_web_flash_messages::FlashMessage;
 use anyhow::Context;
-use reqwest::StatusCode;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Executor, PgPool, Postgres, Transaction};
@@ -25,26 +35,46 @@ pub struct RoomId {
 pub async fn looking_at_lair(
     path: web::Path<RoomId>,
     pool: web::Data<PgPool>,
-) -> Result<HttpResponse, InsertError> {
+    format: ResponseFormat,
+) -> Result<HttpResponse, ApiError> {
     let path = path.id;
 
     let transaction = pool
         .begin()
         .await
-        .with_context(|| format!("Failed to launch the transaction"))?;
+        .context("Failed to launch the transaction")?;
 
     let found_lair: Lair = fetch_lair_by_id(path, pool.clone())
         .await
-        .context("Failed to find lair")?;
+        .context("Failed to find lair")?
+        .ok_or(ApiError::NotFound("The lair does not exist."))?;
     transaction
         .commit()
         .await
         .context("Failed to get lair on id.")?;
-    Ok(HttpResponse::Ok().json(found_lair))
+    match format {
+        ResponseFormat::Json => Ok(HttpResponse::Ok().json(found_lair)),
+        ResponseFormat::GeoJson => {
+            let feature =
+                Feature::point(&found_lair).context("Failed to turn the lair into GeoJSON.")?;
+            Ok(geojson_response(&feature))
+        }
+    }
 }
 
+/// A lair along with its photos, in order, and its amenities. `image` is the
+/// cover photo, kept for the clients that only know about a single image.
 #[derive(Serialize)]
 pub struct Lair {
+    #[serde(flatten)]
+    details: LairDetails,
+    images: Vec<LairPhoto>,
+    amenities: Vec<Amenity>,
+}
+
+/// A row of `rooms`.
+#[derive(Serialize)]
+pub struct LairDetails {
     account_id: Uuid,
     title: String,
     description: String,
@@ -53,25 +83,47 @@ pub struct Lair {
     lat: f64,
     #[serde(rename = "id")]
     room_id: Uuid,
+    max_guests: i32,
+    bedrooms: i32,
+    nightly_price: Option<Decimal>,
+    weekend_price: Option<Decimal>,
+    cleaning_fee: Decimal,
+    currency: Option<String>,
+    min_nights: i32,
+    max_nights: Option<i32>,
 }
 
 #[tracing::instrument(
     name = "Saving new subscriber details in the database",
     skip(path, pool)
 )]
-pub async fn fetch_lair_by_id(path: Uuid, pool: web::Data<PgPool>) -> Result<Lair, anyhow::Error> {
-    let query = sqlx::query_as!(
-        Lair,
+pub async fn fetch_lair_by_id(
+    path: Uuid,
+    pool: web::Data<PgPool>,
+) -> Result<Option<Lair>, anyhow::Error> {
+    let Some(details) = sqlx::query_as!(
+        LairDetails,
         r#"
-        SELECT * FROM rooms WHERE room_id = $1 
+        SELECT account_id, title, description, image, lon, lat, room_id, max_guests, bedrooms,
+            nightly_price, weekend_price, cleaning_fee, currency, min_nights, max_nights
+        FROM rooms WHERE room_id = $1
             "#,
         path
     )
-    .fetch_one(&*pool.clone().into_inner())
+    .fetch_optional(&*pool.clone().into_inner())
     .await
-    .context("Failed to perform a query to retrieve a username.")?;
+    .context("Failed to perform a query to retrieve a username.")?
+    else {
+        return Ok(None);
+    };
+    let images = fetch_lair_photos(path, pool.get_ref()).await?;
+    let amenities = fetch_lair_amenities(path, pool.get_ref()).await?;
 
-    Ok(query)
+    Ok(Some(Lair {
+        details,
+        images,
+        amenities,
+    }))
 
     //Ok(Lair { title: query.title, description: query.description, image: query.image, lon: query.lon, lat: query.lat })
 }
@@ -79,39 +131,55 @@ pub async fn fetch_lair_by_id(path: Uuid, pool: web::Data<PgPool>) -> Result<Lai
 //#[delete("/lair/{id}")]
 #[tracing::instrument(
     name = "Saving new subscriber details in the database",
-    skip(path, pool)
+    skip(path, pool, storage),
+    fields(user_id=%user)
 )]
 pub async fn deleting_lair(
     path: web::Path<RoomId>,
     pool: web::Data<PgPool>,
-    request: HttpRequest,
-) -> Result<HttpResponse, InsertError> {
-    let account_information = extract_cookie(request.clone()).unwrap();
+    storage: web::Data<dyn ImageStorage>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
     let path = path.id;
-    let user_id: Option<Uuid> = get_id_user(account_information, pool.clone()).await?;
 
     let mut transaction = pool
         .begin()
         .await
-        .with_context(|| format!("Failed to launch the transaction"))?;
+        .context("Failed to launch the transaction")?;
 
-    delete_lair(path, user_id.unwrap(), &mut transaction)
+    let image_keys = delete_lair(path, user.user_id(), &mut transaction)
         .await
         .context("Failed delete lair")?;
     transaction
         .commit()
         .await
         .context("Failed to delete lair.")?;
+    delete_stored_files(storage.get_ref(), &image_keys).await;
     FlashMessage::info("You've deleted the lair successfully").send();
     Ok(HttpResponse::Ok().json(json!({"status":"success"})))
 }
 
+/// Deletes a lair along with its photos, returning the storage keys of the
+/// files they leave behind.
 #[tracing::instrument(name = "Deleting lair", skip(transaction,))]
 pub async fn delete_lair(
     path: Uuid,
     user_id_as_uuid: Uuid,
     transaction: &mut Transaction<'_, Postgres>,
-) -> Result<HttpResponse, anyhow::Error> {
+) -> Result<Vec<String>, anyhow::Error> {
+    let images = sqlx::query!(
+        r#"
+        DELETE FROM lair_images
+        USING rooms
+        WHERE lair_images.room_id = rooms.room_id
+            AND rooms.room_id = $1 AND rooms.account_id = $2
+        RETURNING lair_images.storage_key, lair_images.thumbnail_storage_key
+            "#,
+        path,
+        user_id_as_uuid,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
     let query = sqlx::query!(
         r#"
         DELETE FROM rooms WHERE room_id = $1 AND account_id = $2
@@ -120,60 +188,336 @@ pub async fn delete_lair(
         user_id_as_uuid,
     );
     transaction.execute(query).await?;
-    Ok(HttpResponse::Ok().finish())
+    Ok(images
+        .into_iter()
+        .flat_map(|image| [image.storage_key, image.thumbnail_storage_key])
+        .collect())
+}
+
+/// The fields to change in a lair. The fields that a lair can go without
+/// are cleared by sending `null`, which is why they tell a missing field
+/// (`None`) from a null one (`Some(None)`). Dropping the pricing takes
+/// `null` for every price and for the currency.
+#[derive(Deserialize)]
+pub struct LairPatch {
+    title: Option<String>,
+    description: Option<String>,
+    image: Option<String>,
+    lon: Option<f64>,
+    lat: Option<f64>,
+    max_guests: Option<i32>,
+    bedrooms: Option<i32>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    nightly_price: Option<Option<Decimal>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    weekend_price: Option<Option<Decimal>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    cleaning_fee: Option<Option<Decimal>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    currency: Option<Option<String>>,
+    min_nights: Option<i32>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    max_nights: Option<Option<i32>>,
+}
+
+/// Wraps a present field in `Some`, even when it is `null`; `default` leaves
+/// missing fields as `None`.
+fn deserialize_nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
 }
 
-#[tracing::instrument(name = "checking if user exists", skip(account_information, pool))]
-pub async fn get_id_user(
-    account_information: UserInfo,
+//#[put("/lair/{id}")]
+#[tracing::instrument(
+    name = "Replacing a lair",
+    skip(path, pool, lair_info, fetcher, storage),
+    fields(user_id=%user)
+)]
+pub async fn replacing_lair(
+    path: web::Path<RoomId>,
     pool: web::Data<PgPool>,
-) -> Result<Option<Uuid>, anyhow::Error> {
-    let name = &account_information.name;
-    let password = &account_information.password;
-    let query = sqlx::query!(
-        r#"
-    SELECT id FROM users WHERE account_name = $1 AND account_password = $2
-            "#,
-        name,
-        password,
+    user: AuthenticatedUser,
+    lair_info: web::Json<LairInfo>,
+    fetcher: web::Data<RemoteImageFetcher>,
+    storage: web::Data<dyn ImageStorage>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let new_lair: NewLair = lair_info.0.try_into().map_err(ApiError::ValidationError)?;
+    let rehosted = rehost_new_image(
+        room_id,
+        user.user_id(),
+        Some(new_lair.image.as_ref()),
+        &pool,
+        &fetcher,
+        storage.get_ref(),
+    )
+    .await?;
+
+    let saved = async {
+        let mut transaction = pool
+            .begin()
+            .await
+            .context("Failed to launch the transaction")?;
+        lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+        save_lair_update(room_id, &new_lair, rehosted.as_ref(), transaction).await
+    }
+    .await;
+    let updated_lair = discard_rehosted_on_error(saved, rehosted, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(updated_lair))
+}
+
+//#[patch("/lair/{id}")]
+#[tracing::instrument(
+    name = "Patching a lair",
+    skip(path, pool, patch, fetcher, storage),
+    fields(user_id=%user)
+)]
+pub async fn patching_lair(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    patch: web::Json<LairPatch>,
+    fetcher: web::Data<RemoteImageFetcher>,
+    storage: web::Data<dyn ImageStorage>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let patch = patch.0;
+    let rehosted = rehost_new_image(
+        room_id,
+        user.user_id(),
+        patch.image.as_deref(),
+        &pool,
+        &fetcher,
+        storage.get_ref(),
+    )
+    .await?;
+
+    let saved = async {
+        let mut transaction = pool
+            .begin()
+            .await
+            .context("Failed to launch the transaction")?;
+        let current = lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+        let new_lair: NewLair = LairInfo {
+            title: patch.title.unwrap_or_else(|| current.title.clone()),
+            description: patch
+                .description
+                .unwrap_or_else(|| current.description.clone()),
+            image: patch.image.unwrap_or_else(|| current.image.clone()),
+            lon: patch.lon.unwrap_or(current.lon),
+            lat: patch.lat.unwrap_or(current.lat),
+            max_guests: patch.max_guests.or(Some(current.max_guests)),
+            bedrooms: patch.bedrooms.or(Some(current.bedrooms)),
+            nightly_price: patch.nightly_price.unwrap_or(current.nightly_price),
+            weekend_price: patch.weekend_price.unwrap_or(current.weekend_price),
+            // A lair without a price has no fee to keep.
+            cleaning_fee: patch
+                .cleaning_fee
+                .unwrap_or(current.nightly_price.map(|_| current.cleaning_fee)),
+            currency: patch.currency.unwrap_or_else(|| current.currency.clone()),
+            min_nights: patch.min_nights.or(Some(current.min_nights)),
+            max_nights: patch.max_nights.unwrap_or(current.max_nights),
+        }
+        .try_into()
+        .map_err(ApiError::ValidationError)?;
+        save_lair_update(room_id, &new_lair, rehosted.as_ref(), transaction).await
+    }
+    .await;
+    let updated_lair = discard_rehosted_on_error(saved, rehosted, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(updated_lair))
+}
+
+/// Rehosts `image` when it is a new remote image. This happens before the
+/// lair is locked, so the download does not hold the lock, which is why the
+/// ownership is checked here too.
+async fn rehost_new_image(
+    room_id: Uuid,
+    user_id: Uuid,
+    image: Option<&str>,
+    pool: &PgPool,
+    fetcher: &RemoteImageFetcher,
+    storage: &dyn ImageStorage,
+) -> Result<Option<StoredImage>, ApiError> {
+    let Some(image) = image.filter(|image| fetcher.should_rehost(image)) else {
+        return Ok(None);
+    };
+    let current = sqlx::query!(
+        "SELECT account_id, image FROM rooms WHERE room_id = $1",
+        room_id
     )
-    .fetch_one(&*pool.clone().into_inner())
+    .fetch_optional(pool)
     .await
-    .context("Failed to perform a query to retrieve a username.")?;
-    Ok(Some(query.id))
+    .context("Failed to perform a query to retrieve a lair.")?
+    .ok_or(ApiError::NotFound("The lair does not exist."))?;
+    if current.account_id != user_id {
+        return Err(ApiError::Forbidden(
+            "The lair belongs to another user.".to_string(),
+        ));
+    }
+    if current.image == image {
+        return Ok(None);
+    }
+    Ok(Some(rehost_image(room_id, image, fetcher, storage).await?))
 }
 
-#[derive(thiserror::Error)]
-pub enum InsertError {
-    #[error("{0}")]
-    ValidationError(String),
-    #[error(transparent)]
-    UnexpectedError(#[from] anyhow::Error),
+/// Saves the new version of a locked lair and commits. A rehosted image
+/// becomes the cover photo, any other new image is refused once the lair has
+/// photos, since its cover is the first of them.
+async fn save_lair_update(
+    room_id: Uuid,
+    new_lair: &NewLair,
+    rehosted: Option<&StoredImage>,
+    mut transaction: Transaction<'_, Postgres>,
+) -> Result<Lair, ApiError> {
+    if let Some(stored) = rehosted {
+        insert_cover_photo(room_id, stored, &mut transaction).await?;
+    } else {
+        let cover = sqlx::query_scalar!(
+            "SELECT url FROM lair_images WHERE room_id = $1 AND position = 0",
+            room_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .context("Failed to perform a query to retrieve the cover photo of a lair.")?;
+        if cover.is_some_and(|cover| cover != new_lair.image.as_ref()) {
+            return Err(ApiError::Conflict(
+                "The image of a lair with photos is its first photo, reorder or upload photos to change it."
+                    .to_string(),
+            ));
+        }
+    }
+    let updated_lair = update_lair(room_id, new_lair, &mut transaction)
+        .await
+        .context("Failed to update lair")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to update a lair.")?;
+    Ok(updated_lair)
 }
 
-impl std::fmt::Debug for InsertError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        error_chain_fmt(self, f)
+/// Deletes the files of the rehosted image when the update it was meant for
+/// failed.
+async fn discard_rehosted_on_error(
+    saved: Result<Lair, ApiError>,
+    rehosted: Option<StoredImage>,
+    storage: &dyn ImageStorage,
+) -> Result<Lair, ApiError> {
+    if saved.is_err() {
+        if let Some(stored) = rehosted {
+            delete_stored_files(storage, &stored.keys()).await;
+        }
     }
+    saved
 }
 
-/*pub fn error_chain_fmt(
-    e: &impl std::error::Error,
-    f: &mut std::fmt::Formatter<'_>,
-) -> std::fmt::Result {
-    writeln!(f, "{}\n", e)?;
-    let mut current = e.source();
-    while let Some(cause) = current {
-        writeln!(f, "Caused by:\n\t{}", cause)?;
-        current = cause.source();
+/// Checks that the lair belongs to `user_id`, without locking it, for the
+/// requests that only read what belongs to the lair.
+#[tracing::instrument(name = "Checking the owner of a lair", skip(executor))]
+pub async fn check_owned_lair<'c, E>(
+    room_id: Uuid,
+    user_id: Uuid,
+    executor: E,
+) -> Result<(), ApiError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let account_id =
+        sqlx::query_scalar!("SELECT account_id FROM rooms WHERE room_id = $1", room_id)
+            .fetch_optional(executor)
+            .await
+            .context("Failed to perform a query to retrieve a lair.")?
+            .ok_or(ApiError::NotFound("The lair does not exist."))?;
+    if account_id != user_id {
+        return Err(ApiError::Forbidden(
+            "The lair belongs to another user.".to_string(),
+        ));
     }
     Ok(())
-} */
-impl ResponseError for InsertError {
-    fn status_code(&self) -> StatusCode {
-        match self {
-            InsertError::ValidationError(_) => StatusCode::BAD_REQUEST,
-            InsertError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        }
+}
+
+/// Locks the lair for the rest of the transaction after checking that it
+/// belongs to `user_id`.
+#[tracing::instrument(name = "Locking an owned lair", skip(transaction))]
+pub async fn lock_owned_lair(
+    room_id: Uuid,
+    user_id: Uuid,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<LairDetails, ApiError> {
+    let lair = sqlx::query_as!(
+        LairDetails,
+        r#"
+        SELECT account_id, title, description, image, lon, lat, room_id, max_guests, bedrooms,
+            nightly_price, weekend_price, cleaning_fee, currency, min_nights, max_nights
+        FROM rooms WHERE room_id = $1
+        FOR UPDATE
+            "#,
+        room_id,
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to perform a query to retrieve a lair.")?
+    .ok_or(ApiError::NotFound("The lair does not exist."))?;
+
+    if lair.account_id != user_id {
+        return Err(ApiError::Forbidden(
+            "The lair belongs to another user.".to_string(),
+        ));
     }
+    Ok(lair)
+}
+
+#[tracing::instrument(name = "Updating lair", skip(new_lair, transaction))]
+pub async fn update_lair(
+    room_id: Uuid,
+    new_lair: &NewLair,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Lair, anyhow::Error> {
+    let pricing = new_lair.pricing.as_ref();
+    let details = sqlx::query_as!(
+        LairDetails,
+        r#"
+        UPDATE rooms
+        SET title = $1, description = $2, lon = $4, lat = $5,
+            -- A lair with photos keeps the first one as its image.
+            image = COALESCE(
+                (SELECT url FROM lair_images WHERE room_id = $6 AND position = 0),
+                $3
+            ),
+            nightly_price = $7, weekend_price = $8, cleaning_fee = $9, currency = $10,
+            min_nights = $11, max_nights = $12, max_guests = $13, bedrooms = $14
+        WHERE room_id = $6
+        RETURNING account_id, title, description, image, lon, lat, room_id, max_guests, bedrooms,
+            nightly_price, weekend_price, cleaning_fee, currency, min_nights, max_nights
+            "#,
+        new_lair.title.as_ref(),
+        new_lair.description.as_ref(),
+        new_lair.image.as_ref(),
+        new_lair.lon.as_ref(),
+        new_lair.lat.as_ref(),
+        room_id,
+        pricing.map(|pricing| *pricing.nightly_price.as_ref()),
+        pricing
+            .and_then(|pricing| pricing.weekend_price)
+            .map(|price| *price.as_ref()),
+        pricing.map_or(Decimal::ZERO, |pricing| *pricing.cleaning_fee.as_ref()),
+        pricing.map(|pricing| pricing.currency.as_ref()),
+        new_lair.stay_limits.min_nights(),
+        new_lair.stay_limits.max_nights(),
+        new_lair.max_guests.as_ref(),
+        new_lair.bedrooms.as_ref(),
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .context("Failed to update the lair.")?;
+    let images = fetch_lair_photos(room_id, &mut **transaction).await?;
+    let amenities = fetch_lair_amenities(room_id, &mut **transaction).await?;
+    Ok(Lair {
+        details,
+        images,
+        amenities,
+    })
 }
@@ -0,0 +1,109 @@
+use crate::configuration::StorageSettings;
+use anyhow::Context;
+use std::path::{Component, Path, PathBuf};
+
+/// Somewhere to keep uploaded files and serve them from.
+///
+/// Keys are relative paths such as `lairs/<room_id>/<image_id>.png`; the
+/// storage decides where the bytes end up and which URL serves them.
+#[async_trait::async_trait]
+pub trait ImageStorage: Send + Sync {
+    /// Stores `bytes` under `key` and returns the URL they are served at.
+    async fn store(&self, key: &str, bytes: Vec<u8>) -> Result<String, anyhow::Error>;
+
+    /// Deletes the file stored under `key`, if any.
+    async fn delete(&self, key: &str) -> Result<(), anyhow::Error>;
+}
+
+/// Stores files in a local directory, served by the application itself
+/// under `public_path`.
+pub struct LocalImageStorage {
+    directory: PathBuf,
+    public_path: String,
+}
+
+impl LocalImageStorage {
+    pub fn new(settings: &StorageSettings) -> Self {
+        Self {
+            directory: PathBuf::from(&settings.local_directory),
+            public_path: settings.public_path.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn path_of(&self, key: &str) -> Result<PathBuf, anyhow::Error> {
+        let key = Path::new(key);
+        if !key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            anyhow::bail!("{} is not a valid storage key.", key.display());
+        }
+        Ok(self.directory.join(key))
+    }
+}
+
+#[async_trait::async_trait]
+impl ImageStorage for LocalImageStorage {
+    #[tracing::instrument(name = "Storing a file locally", skip(self, bytes))]
+    async fn store(&self, key: &str, bytes: Vec<u8>) -> Result<String, anyhow::Error> {
+        let path = self.path_of(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {}.", parent.display()))?;
+        }
+        tokio::fs::write(&path, bytes)
+            .await
+            .with_context(|| format!("Failed to write {}.", path.display()))?;
+        Ok(format!("{}/{}", self.public_path, key))
+    }
+
+    #[tracing::instrument(name = "Deleting a local file", skip(self))]
+    async fn delete(&self, key: &str) -> Result<(), anyhow::Error> {
+        let path = self.path_of(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete {}.", path.display())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageStorage, LocalImageStorage};
+    use crate::configuration::StorageSettings;
+    use claims::{assert_err, assert_ok};
+    use uuid::Uuid;
+
+    fn storage() -> (LocalImageStorage, std::path::PathBuf) {
+        let directory = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let storage = LocalImageStorage::new(&StorageSettings {
+            local_directory: directory.to_string_lossy().into_owned(),
+            public_path: "/uploads/".to_string(),
+        });
+        (storage, directory)
+    }
+
+    #[tokio::test]
+    async fn a_stored_file_is_written_and_served_under_the_public_path() {
+        let (storage, directory) = storage();
+        let url = storage.store("lairs/a/b.png", vec![1, 2, 3]).await.unwrap();
+        assert_eq!(url, "/uploads/lairs/a/b.png");
+        assert_eq!(
+            std::fs::read(directory.join("lairs/a/b.png")).unwrap(),
+            vec![1, 2, 3]
+        );
+
+        assert_ok!(storage.delete("lairs/a/b.png").await);
+        assert!(!directory.join("lairs/a/b.png").exists());
+        assert_ok!(storage.delete("lairs/a/b.png").await);
+    }
+
+    #[tokio::test]
+    async fn keys_cannot_escape_the_directory() {
+        let (storage, _) = storage();
+        assert_err!(storage.store("../escaped.png", vec![]).await);
+        assert_err!(storage.store("/etc/passwd", vec![]).await);
+    }
+}
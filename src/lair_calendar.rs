@@ -0,0 +1,234 @@
+use crate::api_error::ApiError;
+use crate::authentication::AuthenticatedUser;
+use crate::bookings::nights_are_taken;
+use crate::domain::Stay;
+use crate::get_documents_from_id::{check_owned_lair, lock_owned_lair, RoomId};
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The most days a calendar can show at once.
+const MAX_CALENDAR_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarQuery {
+    from: NaiveDate,
+    /// The first day after the calendar, like the check-out of a stay.
+    to: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct CalendarDay {
+    date: NaiveDate,
+    available: bool,
+    /// `booked` or `blocked` when the lair is not available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NewBlock {
+    from: NaiveDate,
+    to: NaiveDate,
+    note: Option<String>,
+}
+
+/// Nights a host took the lair off the market for.
+#[derive(Serialize)]
+pub struct Block {
+    #[serde(rename = "id")]
+    block_id: Uuid,
+    #[serde(rename = "from")]
+    starts_on: NaiveDate,
+    #[serde(rename = "to")]
+    ends_on: NaiveDate,
+    note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BlockPath {
+    id: Uuid,
+    block_id: Uuid,
+}
+
+//#[get("/lair/{id}/calendar")]
+/// Tells for each night from `from` until the day before `to` whether the
+/// lair can be booked.
+#[tracing::instrument(name = "Getting the calendar of a lair", skip(path, pool))]
+pub async fn lair_calendar(
+    path: web::Path<RoomId>,
+    query: web::Query<CalendarQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let days = (query.to - query.from).num_days();
+    if !(1..=MAX_CALENDAR_DAYS).contains(&days) {
+        return Err(ApiError::invalid(
+            "to",
+            format!(
+                "The calendar must cover between 1 and {} days.",
+                MAX_CALENDAR_DAYS
+            ),
+        ));
+    }
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM rooms WHERE room_id = $1) AS "exists!""#,
+        room_id
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .context("Failed to perform a query to retrieve a lair.")?;
+    if !exists {
+        return Err(ApiError::NotFound("The lair does not exist."));
+    }
+
+    let days = sqlx::query!(
+        r#"
+        SELECT day::date AS "date!", (
+            SELECT u.reason FROM lair_unavailability u
+            WHERE u.room_id = $1 AND u.nights @> day::date
+            ORDER BY u.reason
+            LIMIT 1
+        ) AS reason
+        FROM generate_series($2::date, $3::date - 1, interval '1 day') AS day
+        ORDER BY day
+        "#,
+        room_id,
+        query.from,
+        query.to,
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch the calendar of a lair.")?
+    .into_iter()
+    .map(|day| CalendarDay {
+        date: day.date,
+        available: day.reason.is_none(),
+        reason: day.reason,
+    })
+    .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "lair_id": room_id,
+        "from": query.from,
+        "to": query.to,
+        "days": days,
+    })))
+}
+
+//#[get("/lair/{id}/blocks")]
+#[tracing::instrument(name = "Listing the blocks of a lair", skip(path, pool), fields(user_id=%user))]
+pub async fn lair_blocks(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    check_owned_lair(room_id, user.user_id(), pool.get_ref()).await?;
+    let blocks = sqlx::query_as!(
+        Block,
+        r#"
+        SELECT block_id, starts_on, ends_on, note FROM lair_blocks
+        WHERE room_id = $1
+        ORDER BY starts_on, block_id
+        "#,
+        room_id
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch the blocks of a lair.")?;
+    Ok(HttpResponse::Ok().json(json!({ "blocks": blocks })))
+}
+
+//#[post("/lair/{id}/blocks")]
+/// Takes the lair off the market for some nights. Nights that are already
+/// booked cannot be blocked: the stay has to be cancelled first.
+#[tracing::instrument(
+    name = "Blocking nights of a lair",
+    skip(path, pool, new_block),
+    fields(user_id=%user)
+)]
+pub async fn block_lair_nights(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    new_block: web::Json<NewBlock>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let NewBlock { from, to, note } = new_block.0;
+    let nights =
+        Stay::parse(from, to, Utc::now().date_naive()).map_err(|e| ApiError::invalid("to", e))?;
+    let note = note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+    if nights_are_taken(room_id, &nights, &mut *transaction).await? {
+        return Err(ApiError::Conflict(
+            "Some of these nights are already booked or blocked.".to_string(),
+        ));
+    }
+    let block = sqlx::query_as!(
+        Block,
+        r#"
+        INSERT INTO lair_blocks (block_id, room_id, starts_on, ends_on, note)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING block_id, starts_on, ends_on, note
+        "#,
+        Uuid::new_v4(),
+        room_id,
+        nights.check_in,
+        nights.check_out,
+        note,
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .context("Failed to save a block.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to block nights.")?;
+    Ok(HttpResponse::Created().json(block))
+}
+
+//#[delete("/lair/{id}/blocks/{block_id}")]
+#[tracing::instrument(name = "Unblocking nights of a lair", skip(path, pool), fields(user_id=%user))]
+pub async fn unblock_lair_nights(
+    path: web::Path<BlockPath>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let BlockPath {
+        id: room_id,
+        block_id,
+    } = path.into_inner();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+    let deleted = sqlx::query!(
+        "DELETE FROM lair_blocks WHERE room_id = $1 AND block_id = $2",
+        room_id,
+        block_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to delete a block.")?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound("The block does not exist."));
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to unblock nights.")?;
+    Ok(HttpResponse::Ok().json(json!({"status": "success"})))
+}
@@ -0,0 +1,603 @@
+use crate::api_error::ApiError;
+use crate::authentication::AuthenticatedUser;
+use crate::domain::PhotoCaption;
+use crate::get_documents_from_id::{lock_owned_lair, RoomId};
+use crate::image_storage::ImageStorage;
+use crate::remote_images::RemoteImageFetcher;
+use crate::telemetry::spawn_blocking_with_tracing;
+use actix_multipart::{Field, Multipart};
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use futures_util::TryStreamExt;
+use image::codecs::jpeg::JpegEncoder;
+use image::io::{Limits, Reader};
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use std::io::Cursor;
+use uuid::Uuid;
+
+/// The largest image we accept, in bytes.
+pub const MAX_IMAGE_SIZE: usize = 5 * 1024 * 1024;
+/// The largest width or height of an image we are willing to decode.
+const MAX_IMAGE_DIMENSION: u32 = 10_000;
+/// Thumbnails fit in a square of this side, in pixels.
+pub const THUMBNAIL_SIZE: u32 = 400;
+/// The largest caption we read from a form, in bytes.
+const MAX_CAPTION_SIZE: usize = 4 * 1024;
+
+/// An image whose content has been checked, with the thumbnail made out of it.
+#[derive(Debug)]
+pub struct ProcessedImage {
+    pub format: ImageFormat,
+    pub bytes: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+}
+
+/// A photo of a lair. The one at position 0 is the cover, copied into
+/// `rooms.image`.
+#[derive(Serialize)]
+pub struct LairPhoto {
+    #[serde(rename = "id")]
+    image_id: Uuid,
+    url: String,
+    thumbnail_url: String,
+    caption: Option<String>,
+    position: i32,
+}
+
+/// Where an image and its thumbnail ended up in the storage.
+#[derive(Debug)]
+pub struct StoredImage {
+    pub image_id: Uuid,
+    pub url: String,
+    pub storage_key: String,
+    pub thumbnail_url: String,
+    pub thumbnail_storage_key: String,
+}
+
+impl StoredImage {
+    /// The storage keys of the image and its thumbnail.
+    pub fn keys(self) -> [String; 2] {
+        [self.storage_key, self.thumbnail_storage_key]
+    }
+}
+
+/// The fields of the form used to add a photo.
+pub struct PhotoForm {
+    pub format: ImageFormat,
+    pub bytes: Vec<u8>,
+    pub caption: Option<PhotoCaption>,
+}
+
+#[derive(Deserialize)]
+pub struct LairImagePath {
+    id: Uuid,
+    image_id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct PhotoOrder {
+    image_ids: Vec<Uuid>,
+}
+
+//#[post("/lair/{id}/images")]
+/// Adds the `image` field of a multipart form, with its optional `caption`,
+/// after the other photos of a lair. The first photo becomes the cover.
+#[tracing::instrument(
+    name = "Uploading a lair image",
+    skip(path, pool, storage, payload),
+    fields(user_id=%user)
+)]
+pub async fn upload_lair_image(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ImageStorage>,
+    user: AuthenticatedUser,
+    payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    // Reading and resizing the image can take a while: the lair is only
+    // locked once its files are stored.
+    let form = read_photo_form(payload).await?;
+    let format = form.format;
+    let bytes = form.bytes;
+    let image = spawn_blocking_with_tracing(move || process_image(format, bytes))
+        .await
+        .context("Failed to spawn blocking task.")??;
+    let stored = store_image(room_id, image, storage.get_ref()).await?;
+
+    let saved: Result<LairPhoto, ApiError> = async {
+        let mut transaction = pool
+            .begin()
+            .await
+            .context("Failed to launch the transaction")?;
+        lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+        let photo = insert_photo(room_id, &stored, form.caption, &mut transaction).await?;
+        sync_cover_image(room_id, &mut transaction).await?;
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit SQL transaction to upload an image.")?;
+        Ok(photo)
+    }
+    .await;
+    let photo = match saved {
+        Ok(photo) => photo,
+        Err(e) => {
+            delete_stored_files(storage.get_ref(), &stored.keys()).await;
+            return Err(e);
+        }
+    };
+
+    Ok(HttpResponse::Created().json(json!({
+        "status": "success",
+        "id": photo.image_id,
+        "url": photo.url,
+        "thumbnail_url": photo.thumbnail_url,
+        "caption": photo.caption,
+        "position": photo.position,
+    })))
+}
+
+//#[put("/lair/{id}/images/order")]
+/// Puts the photos of a lair in the order of `image_ids`, which must list
+/// each of them exactly once.
+#[tracing::instrument(
+    name = "Reordering lair images",
+    skip(path, pool, order),
+    fields(user_id=%user)
+)]
+pub async fn reordering_lair_images(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    order: web::Json<PhotoOrder>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let new_order = order.0.image_ids;
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+
+    let mut current_ids: Vec<Uuid> = fetch_lair_photos(room_id, &mut *transaction)
+        .await?
+        .into_iter()
+        .map(|photo| photo.image_id)
+        .collect();
+    let mut requested_ids = new_order.clone();
+    current_ids.sort_unstable();
+    requested_ids.sort_unstable();
+    if current_ids != requested_ids {
+        return Err(ApiError::invalid(
+            "image_ids",
+            "`image_ids` must list every photo of the lair exactly once.".to_string(),
+        ));
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE lair_images
+        SET position = new_order.ordinality - 1
+        FROM unnest($2::uuid[]) WITH ORDINALITY AS new_order(image_id, ordinality)
+        WHERE lair_images.room_id = $1 AND lair_images.image_id = new_order.image_id
+        "#,
+        room_id,
+        &new_order,
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to reorder the images.")?;
+    sync_cover_image(room_id, &mut transaction).await?;
+    let images = fetch_lair_photos(room_id, &mut *transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to reorder images.")?;
+
+    Ok(HttpResponse::Ok().json(json!({"status": "success", "images": images})))
+}
+
+//#[delete("/lair/{id}/images/{image_id}")]
+/// Deletes a photo of a lair, closing the gap it leaves in the order. The
+/// last photo cannot be deleted: the lair would be left without a cover.
+#[tracing::instrument(
+    name = "Deleting a lair image",
+    skip(path, pool, storage),
+    fields(user_id=%user)
+)]
+pub async fn deleting_lair_image(
+    path: web::Path<LairImagePath>,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ImageStorage>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let LairImagePath {
+        id: room_id,
+        image_id,
+    } = path.into_inner();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+
+    let photos = fetch_lair_photos(room_id, &mut *transaction).await?;
+    if !photos.iter().any(|photo| photo.image_id == image_id) {
+        return Err(ApiError::NotFound("The photo does not exist."));
+    }
+    if photos.len() == 1 {
+        return Err(ApiError::Conflict(
+            "The last photo of a lair cannot be deleted.".to_string(),
+        ));
+    }
+    let deleted = sqlx::query!(
+        r#"
+        DELETE FROM lair_images WHERE room_id = $1 AND image_id = $2
+        RETURNING position, storage_key, thumbnail_storage_key
+        "#,
+        room_id,
+        image_id,
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .context("Failed to delete the image.")?;
+
+    sqlx::query!(
+        r#"
+        UPDATE lair_images SET position = position - 1
+        WHERE room_id = $1 AND position > $2
+        "#,
+        room_id,
+        deleted.position,
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to close the gap left by the deleted image.")?;
+    sync_cover_image(room_id, &mut transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to delete an image.")?;
+
+    delete_stored_files(
+        storage.get_ref(),
+        &[deleted.storage_key, deleted.thumbnail_storage_key],
+    )
+    .await;
+    Ok(HttpResponse::Ok().json(json!({"status": "success"})))
+}
+
+/// Fetches the photos of a lair, cover first.
+#[tracing::instrument(name = "Fetching lair images", skip(executor))]
+pub async fn fetch_lair_photos<'c, E>(
+    room_id: Uuid,
+    executor: E,
+) -> Result<Vec<LairPhoto>, anyhow::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query_as!(
+        LairPhoto,
+        r#"
+        SELECT image_id, url, thumbnail_url, caption, position
+        FROM lair_images WHERE room_id = $1
+        ORDER BY position
+        "#,
+        room_id
+    )
+    .fetch_all(executor)
+    .await
+    .context("Failed to fetch the images of a lair.")
+}
+
+#[tracing::instrument(name = "Saving a lair image", skip(stored, caption, transaction))]
+async fn insert_photo(
+    room_id: Uuid,
+    stored: &StoredImage,
+    caption: Option<PhotoCaption>,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<LairPhoto, anyhow::Error> {
+    sqlx::query_as!(
+        LairPhoto,
+        r#"
+        INSERT INTO lair_images
+            (image_id, room_id, position, caption, url, thumbnail_url, storage_key, thumbnail_storage_key)
+        SELECT $1, $2, COALESCE(MAX(position) + 1, 0), $3, $4, $5, $6, $7
+        FROM lair_images WHERE room_id = $2
+        RETURNING image_id, url, thumbnail_url, caption, position
+        "#,
+        stored.image_id,
+        room_id,
+        caption.as_ref().map(|caption| caption.as_ref()),
+        stored.url,
+        stored.thumbnail_url,
+        stored.storage_key,
+        stored.thumbnail_storage_key,
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .context("Failed to save the uploaded image.")
+}
+
+/// Saves an image as the new cover of a lair, in front of its other photos.
+#[tracing::instrument(name = "Saving a lair cover image", skip(stored, transaction))]
+pub async fn insert_cover_photo(
+    room_id: Uuid,
+    stored: &StoredImage,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        "UPDATE lair_images SET position = position + 1 WHERE room_id = $1",
+        room_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to make room for a cover image.")?;
+    sqlx::query!(
+        r#"
+        INSERT INTO lair_images
+            (image_id, room_id, position, url, thumbnail_url, storage_key, thumbnail_storage_key)
+        VALUES ($1, $2, 0, $3, $4, $5, $6)
+        "#,
+        stored.image_id,
+        room_id,
+        stored.url,
+        stored.thumbnail_url,
+        stored.storage_key,
+        stored.thumbnail_storage_key,
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to save the cover image.")?;
+    sync_cover_image(room_id, transaction).await
+}
+
+/// Downloads a remote image, checks it and keeps a copy of it and of its
+/// thumbnail in the storage.
+#[tracing::instrument(name = "Rehosting a remote image", skip(fetcher, storage))]
+pub async fn rehost_image(
+    room_id: Uuid,
+    url: &str,
+    fetcher: &RemoteImageFetcher,
+    storage: &dyn ImageStorage,
+) -> Result<StoredImage, ApiError> {
+    let (format, bytes) = fetcher
+        .fetch(url)
+        .await
+        .map_err(|e| ApiError::invalid("image", e.to_string()))?;
+    let image = spawn_blocking_with_tracing(move || process_image(format, bytes))
+        .await
+        .context("Failed to spawn blocking task.")??;
+    Ok(store_image(room_id, image, storage).await?)
+}
+
+/// Copies the URL of the first photo of a lair, if it has any, into
+/// `rooms.image`.
+#[tracing::instrument(name = "Updating the cover image of a lair", skip(transaction))]
+pub async fn sync_cover_image(
+    room_id: Uuid,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE rooms SET image = lair_images.url
+        FROM lair_images
+        WHERE rooms.room_id = $1
+            AND lair_images.room_id = rooms.room_id
+            AND lair_images.position = 0
+        "#,
+        room_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to update the cover image of a lair.")?;
+    Ok(())
+}
+
+/// Deletes files that are no longer referenced. A failure only leaves an
+/// orphaned file behind, so it is logged rather than returned.
+pub async fn delete_stored_files(storage: &dyn ImageStorage, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = storage.delete(key).await {
+            tracing::warn!(error.cause_chain = ?e, "Failed to delete {}.", key);
+        }
+    }
+}
+
+/// Reads the `image` field of a multipart form and its optional `caption`,
+/// rejecting unsupported content types and files over `MAX_IMAGE_SIZE`.
+pub async fn read_photo_form(mut payload: Multipart) -> Result<PhotoForm, ApiError> {
+    let mut image = None;
+    let mut caption = None;
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|e| ApiError::invalid("form", e.to_string()))?
+    {
+        match field.name() {
+            "image" => {
+                let content_type = field
+                    .content_type()
+                    .map(|mime| mime.essence_str().to_string())
+                    .unwrap_or_default();
+                let format = format_of(&content_type).ok_or_else(|| {
+                    ApiError::UnsupportedMediaType(format!(
+                        "{} is not a supported image type. Use JPEG, PNG, GIF or WebP.",
+                        content_type
+                    ))
+                })?;
+                let bytes = read_field(&mut field, MAX_IMAGE_SIZE)
+                    .await?
+                    .ok_or_else(|| {
+                        ApiError::PayloadTooLarge(format!(
+                            "The image is larger than {} bytes.",
+                            MAX_IMAGE_SIZE
+                        ))
+                    })?;
+                image = Some((format, bytes));
+            }
+            "caption" => {
+                let bytes = read_field(&mut field, MAX_CAPTION_SIZE)
+                    .await?
+                    .ok_or_else(|| {
+                        ApiError::invalid("caption", "The caption is too long.".to_string())
+                    })?;
+                let text = String::from_utf8(bytes).map_err(|_| {
+                    ApiError::invalid("caption", "The caption is not valid UTF-8.".to_string())
+                })?;
+                if !text.trim().is_empty() {
+                    caption = Some(
+                        PhotoCaption::parse(text).map_err(|e| ApiError::invalid("caption", e))?,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    let (format, bytes) = image
+        .ok_or_else(|| ApiError::invalid("image", "The form has no `image` field.".to_string()))?;
+    Ok(PhotoForm {
+        format,
+        bytes,
+        caption,
+    })
+}
+
+/// Reads a multipart field, or returns `None` once it is over `limit` bytes.
+async fn read_field(field: &mut Field, limit: usize) -> Result<Option<Vec<u8>>, ApiError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field
+        .try_next()
+        .await
+        .map_err(|e| ApiError::invalid("form", e.to_string()))?
+    {
+        if bytes.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+pub fn format_of(content_type: &str) -> Option<ImageFormat> {
+    match content_type {
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/png" => Some(ImageFormat::Png),
+        "image/gif" => Some(ImageFormat::Gif),
+        "image/webp" => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+/// Checks that `bytes` really hold an image of `format` and makes its
+/// thumbnail. CPU-bound: run it on the blocking thread pool.
+pub fn process_image(format: ImageFormat, bytes: Vec<u8>) -> Result<ProcessedImage, ApiError> {
+    let invalid = || {
+        ApiError::invalid(
+            "image",
+            format!(
+                "The file is not a valid {} image.",
+                format.extensions_str()[0]
+            ),
+        )
+    };
+    if image::guess_format(&bytes).ok() != Some(format) {
+        return Err(invalid());
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    let mut reader = Reader::with_format(Cursor::new(&bytes), format);
+    reader.limits(limits);
+    let decoded = reader.decode().map_err(|_| invalid())?;
+
+    let thumbnail = decoded.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8();
+    let mut encoded_thumbnail = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded_thumbnail, 80)
+        .encode_image(&thumbnail)
+        .context("Failed to encode the thumbnail.")?;
+
+    Ok(ProcessedImage {
+        format,
+        bytes,
+        thumbnail: encoded_thumbnail,
+    })
+}
+
+/// Stores an image of a lair and its thumbnail.
+#[tracing::instrument(name = "Storing a lair image", skip(image, storage))]
+pub async fn store_image(
+    room_id: Uuid,
+    image: ProcessedImage,
+    storage: &dyn ImageStorage,
+) -> Result<StoredImage, anyhow::Error> {
+    let image_id = Uuid::new_v4();
+    let extension = image.format.extensions_str()[0];
+    let storage_key = format!("lairs/{}/{}.{}", room_id, image_id, extension);
+    let url = storage.store(&storage_key, image.bytes).await?;
+    let thumbnail_storage_key = format!("lairs/{}/{}_thumbnail.jpg", room_id, image_id);
+    let thumbnail_url = match storage.store(&thumbnail_storage_key, image.thumbnail).await {
+        Ok(thumbnail_url) => thumbnail_url,
+        Err(e) => {
+            delete_stored_files(storage, &[storage_key]).await;
+            return Err(e);
+        }
+    };
+    Ok(StoredImage {
+        image_id,
+        url,
+        storage_key,
+        thumbnail_url,
+        thumbnail_storage_key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_of, process_image, THUMBNAIL_SIZE};
+    use claims::{assert_err, assert_none, assert_ok};
+    use image::{ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RgbImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn only_web_image_types_are_supported() {
+        assert_eq!(format_of("image/png"), Some(ImageFormat::Png));
+        assert_none!(format_of("image/svg+xml"));
+        assert_none!(format_of("text/html"));
+    }
+
+    #[test]
+    fn a_thumbnail_fits_in_its_square_and_keeps_the_aspect_ratio() {
+        let processed = process_image(ImageFormat::Png, png(1200, 600)).unwrap();
+        let thumbnail = image::load_from_memory(&processed.thumbnail).unwrap();
+        assert_eq!(thumbnail.width(), THUMBNAIL_SIZE);
+        assert_eq!(thumbnail.height(), THUMBNAIL_SIZE / 2);
+    }
+
+    #[test]
+    fn a_file_that_does_not_match_its_content_type_is_rejected() {
+        assert_err!(process_image(ImageFormat::Jpeg, png(10, 10)));
+        assert_err!(process_image(ImageFormat::Png, b"<svg></svg>".to_vec()));
+    }
+
+    #[test]
+    fn a_truncated_image_is_rejected() {
+        let mut bytes = png(100, 100);
+        bytes.truncate(60);
+        assert_err!(process_image(ImageFormat::Png, bytes));
+        assert_ok!(process_image(ImageFormat::Png, png(100, 100)));
+    }
+}
@@ -0,0 +1,456 @@
+use crate::api_error::{violates_constraint, ApiError};
+use crate::authentication::AuthenticatedUser;
+use crate::bookings::nights_are_taken;
+use crate::domain::{GuestCount, Price, Stay, StayLimits};
+use crate::get_documents_from_id::{check_owned_lair, lock_owned_lair, RoomId};
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use chrono::{Datelike, NaiveDate, Utc, Weekday};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The constraint that keeps the seasons of a lair from overlapping.
+const NO_OVERLAP_CONSTRAINT: &str = "lair_seasons_no_overlap";
+
+#[derive(Debug, Deserialize)]
+pub struct QuoteQuery {
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+    guests: Option<i32>,
+}
+
+/// The regular prices of a lair, as stored in `rooms`.
+#[derive(Debug)]
+pub struct Rates {
+    pub nightly_price: Decimal,
+    /// Replaces `nightly_price` on Friday and Saturday nights.
+    pub weekend_price: Option<Decimal>,
+    pub cleaning_fee: Decimal,
+}
+
+/// Nights from `starts_on` until the day before `ends_on`, priced at
+/// `nightly_price` whatever the day of the week.
+#[derive(Debug, Serialize)]
+pub struct Season {
+    #[serde(rename = "id")]
+    season_id: Uuid,
+    #[serde(rename = "from")]
+    starts_on: NaiveDate,
+    #[serde(rename = "to")]
+    ends_on: NaiveDate,
+    nightly_price: Decimal,
+}
+
+impl Season {
+    fn covers(&self, night: NaiveDate) -> bool {
+        self.starts_on <= night && night < self.ends_on
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteLineKind {
+    Nights,
+    WeekendNights,
+    SeasonNights,
+    CleaningFee,
+}
+
+/// A line of a quote: `quantity` nights, or a fee, at `unit_price`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct QuoteLine {
+    kind: QuoteLineKind,
+    quantity: i64,
+    unit_price: Decimal,
+    amount: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct Quote {
+    lair_id: Uuid,
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+    nights: i64,
+    guests: i32,
+    currency: String,
+    lines: Vec<QuoteLine>,
+    total: Decimal,
+    /// Whether the nights can still be booked.
+    available: bool,
+}
+
+/// Prices each night of `stay`, then adds the fees. Nights priced the same
+/// way share a line, in the order they first appear.
+pub fn quote_lines(stay: &Stay, rates: &Rates, seasons: &[Season]) -> Vec<QuoteLine> {
+    let mut lines: Vec<QuoteLine> = Vec::new();
+    for night in stay.check_in.iter_days().take(stay.nights() as usize) {
+        let is_weekend = matches!(night.weekday(), Weekday::Fri | Weekday::Sat);
+        let (kind, unit_price) = match seasons.iter().find(|season| season.covers(night)) {
+            Some(season) => (QuoteLineKind::SeasonNights, season.nightly_price),
+            None => match rates.weekend_price {
+                Some(weekend_price) if is_weekend => (QuoteLineKind::WeekendNights, weekend_price),
+                _ => (QuoteLineKind::Nights, rates.nightly_price),
+            },
+        };
+        match lines
+            .iter_mut()
+            .find(|line| line.kind == kind && line.unit_price == unit_price)
+        {
+            Some(line) => {
+                line.quantity += 1;
+                line.amount += unit_price;
+            }
+            None => lines.push(QuoteLine {
+                kind,
+                quantity: 1,
+                unit_price,
+                amount: unit_price,
+            }),
+        }
+    }
+    if !rates.cleaning_fee.is_zero() {
+        lines.push(QuoteLine {
+            kind: QuoteLineKind::CleaningFee,
+            quantity: 1,
+            unit_price: rates.cleaning_fee,
+            amount: rates.cleaning_fee,
+        });
+    }
+    lines
+}
+
+//#[get("/lair/{id}/quote")]
+/// Tells what a stay in the lair would cost, line by line.
+#[tracing::instrument(name = "Quoting a stay in a lair", skip(path, pool))]
+pub async fn quote_lair(
+    path: web::Path<RoomId>,
+    query: web::Query<QuoteQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let stay = Stay::parse(query.check_in, query.check_out, Utc::now().date_naive())
+        .map_err(|e| ApiError::invalid("check_out", e))?;
+    let guests =
+        GuestCount::parse(query.guests.unwrap_or(1)).map_err(|e| ApiError::invalid("guests", e))?;
+
+    let lair = sqlx::query!(
+        r#"
+        SELECT nightly_price, weekend_price, cleaning_fee, currency, min_nights, max_nights,
+            max_guests
+        FROM rooms WHERE room_id = $1
+        "#,
+        room_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .context("Failed to perform a query to retrieve a lair.")?
+    .ok_or(ApiError::NotFound("The lair does not exist."))?;
+    let (Some(nightly_price), Some(currency)) = (lair.nightly_price, lair.currency) else {
+        return Err(ApiError::Conflict(
+            "The host has not set a price for this lair yet.".to_string(),
+        ));
+    };
+    StayLimits::parse(lair.min_nights, lair.max_nights)
+        .map_err(anyhow::Error::msg)
+        .context("The lair has invalid stay limits.")?
+        .check(&stay)
+        .map_err(|e| ApiError::invalid("check_out", e))?;
+    if *guests.as_ref() > lair.max_guests {
+        return Err(ApiError::invalid(
+            "guests",
+            format!("This lair can host {} guests at most.", lair.max_guests),
+        ));
+    }
+
+    let seasons = sqlx::query_as!(
+        Season,
+        r#"
+        SELECT season_id, starts_on, ends_on, nightly_price FROM lair_seasons
+        WHERE room_id = $1 AND daterange(starts_on, ends_on) && daterange($2, $3)
+        "#,
+        room_id,
+        stay.check_in,
+        stay.check_out,
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch the seasons of a lair.")?;
+    let rates = Rates {
+        nightly_price,
+        weekend_price: lair.weekend_price,
+        cleaning_fee: lair.cleaning_fee,
+    };
+    let lines = quote_lines(&stay, &rates, &seasons);
+    let available = !nights_are_taken(room_id, &stay, pool.get_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(Quote {
+        lair_id: room_id,
+        check_in: stay.check_in,
+        check_out: stay.check_out,
+        nights: stay.nights(),
+        guests: *guests.as_ref(),
+        currency,
+        total: lines.iter().map(|line| line.amount).sum(),
+        lines,
+        available,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct NewSeason {
+    from: NaiveDate,
+    to: NaiveDate,
+    nightly_price: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct SeasonPath {
+    id: Uuid,
+    season_id: Uuid,
+}
+
+//#[get("/lair/{id}/seasons")]
+#[tracing::instrument(name = "Listing the seasons of a lair", skip(path, pool), fields(user_id=%user))]
+pub async fn lair_seasons(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    check_owned_lair(room_id, user.user_id(), pool.get_ref()).await?;
+    let seasons = sqlx::query_as!(
+        Season,
+        r#"
+        SELECT season_id, starts_on, ends_on, nightly_price FROM lair_seasons
+        WHERE room_id = $1
+        ORDER BY starts_on
+        "#,
+        room_id
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch the seasons of a lair.")?;
+    Ok(HttpResponse::Ok().json(json!({ "seasons": seasons })))
+}
+
+//#[post("/lair/{id}/seasons")]
+/// Prices some nights of a lair differently from the rest of the year.
+#[tracing::instrument(
+    name = "Adding a season to a lair",
+    skip(path, pool, new_season),
+    fields(user_id=%user)
+)]
+pub async fn add_lair_season(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    new_season: web::Json<NewSeason>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let NewSeason {
+        from,
+        to,
+        nightly_price,
+    } = new_season.0;
+    let nights =
+        Stay::parse(from, to, Utc::now().date_naive()).map_err(|e| ApiError::invalid("to", e))?;
+    let nightly_price =
+        Price::parse(nightly_price).map_err(|e| ApiError::invalid("nightly_price", e))?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+    let season = sqlx::query_as!(
+        Season,
+        r#"
+        INSERT INTO lair_seasons (season_id, room_id, starts_on, ends_on, nightly_price)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING season_id, starts_on, ends_on, nightly_price
+        "#,
+        Uuid::new_v4(),
+        room_id,
+        nights.check_in,
+        nights.check_out,
+        nightly_price.as_ref(),
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| match e {
+        e if violates_constraint(&e, NO_OVERLAP_CONSTRAINT) => {
+            ApiError::Conflict("The season overlaps another season of this lair.".to_string())
+        }
+        e => ApiError::UnexpectedError(anyhow::Error::new(e).context("Failed to save a season.")),
+    })?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to add a season.")?;
+    Ok(HttpResponse::Created().json(season))
+}
+
+//#[delete("/lair/{id}/seasons/{season_id}")]
+#[tracing::instrument(name = "Removing a season of a lair", skip(path, pool), fields(user_id=%user))]
+pub async fn remove_lair_season(
+    path: web::Path<SeasonPath>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let SeasonPath {
+        id: room_id,
+        season_id,
+    } = path.into_inner();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    lock_owned_lair(room_id, user.user_id(), &mut transaction).await?;
+    let deleted = sqlx::query!(
+        "DELETE FROM lair_seasons WHERE room_id = $1 AND season_id = $2",
+        room_id,
+        season_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to delete a season.")?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound("The season does not exist."));
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to remove a season.")?;
+    Ok(HttpResponse::Ok().json(json!({"status": "success"})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote_lines, QuoteLine, QuoteLineKind, Rates, Season};
+    use crate::domain::Stay;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn decimal(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    /// 2026-11-02 is a Monday.
+    fn stay(check_in: &str, check_out: &str) -> Stay {
+        Stay::parse(date(check_in), date(check_out), date("2026-10-18")).unwrap()
+    }
+
+    fn rates(weekend_price: Option<&str>, cleaning_fee: &str) -> Rates {
+        Rates {
+            nightly_price: decimal("100.00"),
+            weekend_price: weekend_price.map(decimal),
+            cleaning_fee: decimal(cleaning_fee),
+        }
+    }
+
+    fn line(kind: QuoteLineKind, quantity: i64, unit_price: &str, amount: &str) -> QuoteLine {
+        QuoteLine {
+            kind,
+            quantity,
+            unit_price: decimal(unit_price),
+            amount: decimal(amount),
+        }
+    }
+
+    #[test]
+    fn weeknights_are_charged_the_nightly_price() {
+        let lines = quote_lines(
+            &stay("2026-11-02", "2026-11-05"),
+            &rates(Some("150"), "0"),
+            &[],
+        );
+        assert_eq!(
+            lines,
+            vec![line(QuoteLineKind::Nights, 3, "100.00", "300.00")]
+        );
+    }
+
+    #[test]
+    fn friday_and_saturday_nights_are_charged_the_weekend_price() {
+        // From Thursday to Monday.
+        let lines = quote_lines(
+            &stay("2026-11-05", "2026-11-09"),
+            &rates(Some("150.50"), "0"),
+            &[],
+        );
+        assert_eq!(
+            lines,
+            vec![
+                line(QuoteLineKind::Nights, 2, "100.00", "200.00"),
+                line(QuoteLineKind::WeekendNights, 2, "150.50", "301.00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekends_cost_the_same_without_a_weekend_price() {
+        let lines = quote_lines(&stay("2026-11-05", "2026-11-09"), &rates(None, "0"), &[]);
+        assert_eq!(
+            lines,
+            vec![line(QuoteLineKind::Nights, 4, "100.00", "400.00")]
+        );
+    }
+
+    #[test]
+    fn season_prices_override_weekend_prices() {
+        let seasons = vec![Season {
+            season_id: Uuid::new_v4(),
+            starts_on: date("2026-11-06"),
+            ends_on: date("2026-11-08"),
+            nightly_price: decimal("80.10"),
+        }];
+        let lines = quote_lines(
+            &stay("2026-11-05", "2026-11-09"),
+            &rates(Some("150"), "0"),
+            &seasons,
+        );
+        assert_eq!(
+            lines,
+            vec![
+                line(QuoteLineKind::Nights, 2, "100.00", "200.00"),
+                line(QuoteLineKind::SeasonNights, 2, "80.10", "160.20"),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_cleaning_fee_is_charged_once() {
+        let lines = quote_lines(
+            &stay("2026-11-02", "2026-11-04"),
+            &rates(None, "35.99"),
+            &[],
+        );
+        assert_eq!(
+            lines,
+            vec![
+                line(QuoteLineKind::Nights, 2, "100.00", "200.00"),
+                line(QuoteLineKind::CleaningFee, 1, "35.99", "35.99"),
+            ]
+        );
+    }
+
+    #[test]
+    fn amounts_are_exact() {
+        let rates = Rates {
+            nightly_price: decimal("0.10"),
+            weekend_price: None,
+            cleaning_fee: decimal("0.20"),
+        };
+        let lines = quote_lines(&stay("2026-11-02", "2026-11-05"), &rates, &[]);
+        let total: Decimal = lines.iter().map(|line| line.amount).sum();
+        assert_eq!(total, decimal("0.50"));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::api_error::ApiError;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const DEFAULT_SUGGESTIONS: i64 = 5;
+const MAX_SUGGESTIONS: i64 = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestionQuery {
+    q: String,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct LairSuggestion {
+    #[serde(rename = "id")]
+    room_id: Uuid,
+    title: String,
+    lon: f64,
+    lat: f64,
+    similarity: f32,
+}
+
+//#[get("/lair/suggest")]
+#[tracing::instrument(name = "Suggesting lair titles", skip(pool))]
+pub async fn suggest_lairs(
+    query: web::Query<SuggestionQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let typed = query.q.trim();
+    if typed.is_empty() {
+        return Err(ApiError::invalid(
+            "q",
+            "The 'q' parameter must not be empty.".to_string(),
+        ));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SUGGESTIONS)
+        .clamp(1, MAX_SUGGESTIONS);
+
+    let suggestions = fetch_suggestions(typed, limit, &pool).await?;
+    Ok(HttpResponse::Ok().json(suggestions))
+}
+
+/// Ranks titles by trigram similarity with `typed`, either as a whole or
+/// against one of their words, so that both a misspelt name ("vulcano") and
+/// the beginning of one ("volc") find "Volcano lair".
+#[tracing::instrument(name = "Fetching lair suggestions", skip(pool))]
+pub async fn fetch_suggestions(
+    typed: &str,
+    limit: i64,
+    pool: &PgPool,
+) -> Result<Vec<LairSuggestion>, anyhow::Error> {
+    let suggestions = sqlx::query_as!(
+        LairSuggestion,
+        r#"
+        SELECT room_id, title, lon, lat,
+            GREATEST(word_similarity($1, title), similarity($1, title)) AS "similarity!"
+        FROM rooms
+        WHERE $1 <% title OR title % $1
+        ORDER BY "similarity!" DESC, title
+        LIMIT $2
+            "#,
+        typed,
+        limit,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to suggest lairs.")?;
+    Ok(suggestions)
+}
@@ -0,0 +1,89 @@
+use crate::api_error::ApiError;
+use crate::domain::{BoundingBox, TileId};
+use crate::lairs_on_map::{push_map_filters, MapFilters};
+use crate::vector_tile::{encode_tile, TagValue, TileLayer, DEFAULT_EXTENT, MVT_MIME_TYPE};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use serde::Deserialize;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+/// Hard cap on the features of a tile, so that zoomed out tiles stay small.
+const MAX_LAIRS_PER_TILE: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct TilePath {
+    z: u8,
+    x: u32,
+    y: u32,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct TileLair {
+    room_id: Uuid,
+    title: String,
+    image: String,
+    lon: f64,
+    lat: f64,
+}
+
+//#[get("/tiles/{z}/{x}/{y}.mvt")]
+/// Serves the lairs inside an XYZ tile as the `lairs` layer of a Mapbox
+/// Vector Tile.
+#[tracing::instrument(name = "Getting a vector tile of lairs", skip(pool))]
+pub async fn lairs_tile(
+    path: web::Path<TilePath>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let tile = TileId::parse(path.z, path.x, path.y).map_err(|e| ApiError::invalid("tile", e))?;
+    let (north, west, south, east) = tile.bounds();
+    let bbox = BoundingBox::parse(north, west, south, east).map_err(anyhow::Error::msg)?;
+
+    let lairs = fetch_lairs_in_tile(&MapFilters::within(bbox), &pool).await?;
+
+    let mut layer = TileLayer::new("lairs", DEFAULT_EXTENT);
+    let extent = layer.extent() as i32;
+    for lair in lairs {
+        let (x, y) = tile.project(lair.lat, lair.lon, layer.extent());
+        // Lairs on the antimeridian belong to the tiles of both of its sides
+        // but can only be drawn on one.
+        if !(0..=extent).contains(&x) || !(0..=extent).contains(&y) {
+            continue;
+        }
+        layer.add_point(
+            x,
+            y,
+            vec![
+                ("id", TagValue::String(lair.room_id.to_string())),
+                ("title", TagValue::String(lair.title)),
+                ("image", TagValue::String(lair.image)),
+            ],
+        );
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType(MVT_MIME_TYPE.parse().unwrap()))
+        .body(encode_tile(&[layer])))
+}
+
+#[tracing::instrument(name = "Fetching lairs in a tile", skip(pool))]
+pub async fn fetch_lairs_in_tile(
+    filters: &MapFilters,
+    pool: &PgPool,
+) -> Result<Vec<TileLair>, anyhow::Error> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        "SELECT r.room_id, r.title, r.image, r.lon, r.lat FROM rooms r WHERE ",
+    );
+    push_map_filters(&mut query, filters);
+    query
+        .push(" ORDER BY r.room_id LIMIT ")
+        .push_bind(MAX_LAIRS_PER_TILE);
+
+    let lairs = query
+        .build_query_as::<TileLair>()
+        .fetch_all(pool)
+        .await
+        .context("Failed to perform a query to retrieve the lairs of a tile.")?;
+    Ok(lairs)
+}
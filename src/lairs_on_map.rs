@@ -1,11 +1,27 @@
-use crate:: routes::error_chain_fmt;
-use actix_web::{web, HttpResponse, ResponseError};
+use crate::api_error::ApiError;
+use crate::authentication::AuthenticatedUser;
+use crate::configuration::MapSettings;
+use crate::domain::{
+    AmenitySlug, BedroomCount, BoundingBox, Currency, GuestCount, LairLat, LairLon, Price, Stay,
+};
+use crate::geojson::{geojson_response, FeatureCollection, ResponseFormat};
+use crate::routes::InvalidFields;
+use actix_web::{web, HttpResponse};
 use anyhow::Context;
-use reqwest::StatusCode;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use serde_json::json;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
+const DEFAULT_PAGE_SIZE: i64 = 50;
+/// Hard cap on the page size, whatever the client asks for.
+const MAX_PAGE_SIZE: i64 = 200;
+const MAX_RADIUS_IN_METERS: f64 = 50_000.0;
+/// The deepest zoom level of the map tiles.
+const MAX_ZOOM: u8 = 22;
+
 #[derive(Debug, Deserialize)]
 pub struct LairsOnMap {
     br_lat: f64,
@@ -13,38 +29,256 @@ pub struct LairsOnMap {
     tl_lat: f64,
     tl_lng: f64,
     search: Option<String>,
+    limit: Option<i64>,
+    /// The `next_cursor` of the previous page.
+    after: Option<Uuid>,
+    sort: Option<LairSort>,
+    /// The zoom level of the map, to group lairs into clusters when zoomed out.
+    zoom: Option<u8>,
+    /// Together with `check_out`, only shows the lairs free for that stay.
+    check_in: Option<NaiveDate>,
+    check_out: Option<NaiveDate>,
+    /// Bounds on the nightly price, which only make sense with `currency`.
+    min_price: Option<Decimal>,
+    max_price: Option<Decimal>,
+    currency: Option<String>,
+    /// Only shows the lairs that can host that many guests.
+    guests: Option<i32>,
+    /// The fewest bedrooms a lair must have.
+    bedrooms: Option<i32>,
+    /// Comma-separated slugs of the amenities a lair must all offer.
+    amenities: Option<String>,
+}
+
+/// What the lairs shown on the map must match.
+#[derive(Debug)]
+pub struct MapFilters {
+    pub bbox: BoundingBox,
+    /// Matched case-insensitively on the title and the description, or
+    /// through the full-text `search_vector`.
+    pub search: Option<String>,
+    /// Only the lairs that are free for every night of this stay.
+    pub available_for: Option<Stay>,
+    /// Lairs without a price are left out when either bound is set.
+    pub min_price: Option<Price>,
+    pub max_price: Option<Price>,
+    pub currency: Option<Currency>,
+    pub guests: Option<GuestCount>,
+    pub bedrooms: Option<BedroomCount>,
+    /// Lairs must offer every one of these amenities.
+    pub amenities: Vec<AmenitySlug>,
+}
+
+impl MapFilters {
+    /// Every lair inside `bbox`.
+    pub fn within(bbox: BoundingBox) -> Self {
+        Self {
+            bbox,
+            search: None,
+            available_for: None,
+            min_price: None,
+            max_price: None,
+            currency: None,
+            guests: None,
+            bedrooms: None,
+            amenities: Vec::new(),
+        }
+    }
+
+    /// Validates the filters of a map query.
+    pub fn parse(info: &LairsOnMap) -> Result<Self, InvalidFields> {
+        let bbox = BoundingBox::parse(info.tl_lat, info.tl_lng, info.br_lat, info.br_lng)
+            .map_err(|e| InvalidFields::single("bbox", e))?;
+        let search = info
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|search| !search.is_empty())
+            .map(str::to_owned);
+        let available_for = match (info.check_in, info.check_out) {
+            (Some(check_in), Some(check_out)) => Some(
+                Stay::parse(check_in, check_out, Utc::now().date_naive())
+                    .map_err(|e| InvalidFields::single("check_out", e))?,
+            ),
+            (None, None) => None,
+            _ => {
+                return Err(InvalidFields::single(
+                    "check_in",
+                    "Filtering by availability requires both check_in and check_out.".to_string(),
+                ))
+            }
+        };
+        let min_price = info
+            .min_price
+            .map(Price::parse)
+            .transpose()
+            .map_err(|e| InvalidFields::single("min_price", e))?;
+        let max_price = info
+            .max_price
+            .map(Price::parse)
+            .transpose()
+            .map_err(|e| InvalidFields::single("max_price", e))?;
+        if let (Some(min_price), Some(max_price)) = (min_price, max_price) {
+            if min_price.as_ref() > max_price.as_ref() {
+                return Err(InvalidFields::single(
+                    "max_price",
+                    "The minimum price is above the maximum price.".to_string(),
+                ));
+            }
+        }
+        let currency = info
+            .currency
+            .clone()
+            .map(Currency::parse)
+            .transpose()
+            .map_err(|e| InvalidFields::single("currency", e))?;
+        if currency.is_none() && (min_price.is_some() || max_price.is_some()) {
+            return Err(InvalidFields::single(
+                "currency",
+                "Filtering by price requires a currency.".to_string(),
+            ));
+        }
+        let mut amenities = info
+            .amenities
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|slug| !slug.is_empty())
+            .map(|slug| AmenitySlug::parse(slug.to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| InvalidFields::single("amenities", e))?;
+        amenities.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        amenities.dedup();
+        Ok(Self {
+            bbox,
+            search,
+            available_for,
+            min_price,
+            max_price,
+            currency,
+            guests: info
+                .guests
+                .map(GuestCount::parse)
+                .transpose()
+                .map_err(|e| InvalidFields::single("guests", e))?,
+            bedrooms: info
+                .bedrooms
+                .map(BedroomCount::parse)
+                .transpose()
+                .map_err(|e| InvalidFields::single("bedrooms", e))?,
+            amenities,
+        })
+    }
 }
 
-#[tracing::instrument(name = "Getting lairs from map coordinates", skip(pool))]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LairSort {
+    /// Best full-text matches first, only available when searching.
+    Relevance,
+    Newest,
+    Title,
+    /// Closest to the centre of the viewport first.
+    Distance,
+}
+
+#[derive(Serialize)]
+pub struct LairsPage {
+    lairs: Vec<LairFetched>,
+    /// `None` on the last page.
+    next_cursor: Option<Uuid>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct LairCluster {
+    /// The centroid of the lairs of the cluster.
+    lat: f64,
+    lon: f64,
+    count: i64,
+}
+
+/// Lists the lairs inside the viewport, or clusters of them when the map is
+/// zoomed out below `MapSettings::cluster_below_zoom`, as JSON or GeoJSON.
+#[tracing::instrument(name = "Getting lairs from map coordinates", skip(pool, map_settings))]
 pub async fn lairs_based_on_coordinates(
     info: web::Query<LairsOnMap>,
     pool: web::Data<PgPool>,
-) -> Result<HttpResponse, InsertError> {
-    let transaction = pool
-        .begin()
+    map_settings: web::Data<MapSettings>,
+    format: ResponseFormat,
+    user: Option<AuthenticatedUser>,
+) -> Result<HttpResponse, ApiError> {
+    let viewer = user.map(|user| user.user_id());
+    let filters = MapFilters::parse(&info).map_err(ApiError::ValidationError)?;
+
+    if let Some(zoom) = info.zoom {
+        if zoom > MAX_ZOOM {
+            return Err(ApiError::invalid(
+                "zoom",
+                format!("The zoom level must be between 0 and {}.", MAX_ZOOM),
+            ));
+        }
+        if zoom < map_settings.cluster_below_zoom {
+            let cell_size =
+                360.0 / (2f64.powi(zoom.into()) * f64::from(map_settings.cluster_cells_per_tile));
+            let clusters = fetch_lair_clusters(&filters, cell_size, &pool).await?;
+            return match format {
+                ResponseFormat::Json => {
+                    Ok(HttpResponse::Ok().json(json!({ "clusters": clusters })))
+                }
+                ResponseFormat::GeoJson => {
+                    let collection = FeatureCollection::of_points(&clusters)
+                        .context("Failed to turn clusters into GeoJSON.")?;
+                    Ok(geojson_response(&collection))
+                }
+            };
+        }
+    }
+    let sort = match (info.sort, &filters.search) {
+        (Some(LairSort::Relevance), None) => {
+            return Err(ApiError::invalid(
+                "sort",
+                "Sorting by relevance requires a search.".to_string(),
+            ))
+        }
+        (Some(sort), _) => sort,
+        (None, Some(_)) => LairSort::Relevance,
+        (None, None) => LairSort::Newest,
+    };
+    let limit = info
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    if let Some(after) = info.after {
+        // An unknown cursor would silently restart from the first page.
+        let known = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM rooms WHERE room_id = $1) AS "known!""#,
+            after
+        )
+        .fetch_one(pool.get_ref())
         .await
-        .with_context(|| format!("Failed to launch the transaction"))?;
-
-    let search = info.search.clone();
-    println!("{:#?}", search);
-    if search != None {
-        let result = fetch_lair_by_coordinates_with_search(info, pool, search.unwrap()).await?;
-        transaction
-            .commit()
-            .await
-            .context("Failed to get lair on map.")?;
-        return Ok(HttpResponse::Ok().json(result));
-    } else {
-        let result = fetch_lair_by_coordinates_without_search(info, pool).await?;
-        transaction
-            .commit()
-            .await
-            .context("Failed to get lair on map.")?;
-        return Ok(HttpResponse::Ok().json(result));
+        .context("Failed to look up the cursor.")?;
+        if !known {
+            return Err(ApiError::invalid(
+                "after",
+                "The cursor does not match any lair.".to_string(),
+            ));
+        }
+    }
+
+    let page = fetch_lairs_page(&filters, sort, info.after, limit, viewer, &pool).await?;
+    match format {
+        ResponseFormat::Json => Ok(HttpResponse::Ok().json(page)),
+        ResponseFormat::GeoJson => {
+            let collection = FeatureCollection::of_points(&page.lairs)
+                .context("Failed to turn lairs into GeoJSON.")?
+                .with_member("next_cursor", json!(page.next_cursor));
+            Ok(geojson_response(&collection))
+        }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize, sqlx::FromRow)]
 pub struct LairFetched {
     account_id: Uuid,
     title: String,
@@ -53,69 +287,382 @@ pub struct LairFetched {
     lat: f64,
     #[serde(rename = "id")]
     room_id: Uuid,
+    /// `None` until the host sets a price.
+    nightly_price: Option<Decimal>,
+    currency: Option<String>,
+    max_guests: i32,
+    bedrooms: i32,
+    /// `None` until the lair gets its first review.
+    average_rating: Option<Decimal>,
+    review_count: i32,
+    /// Only set by the searches around a point.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_m: Option<f64>,
+    /// Whether the lair is on one of the wishlists of the user, only set
+    /// when the request is authenticated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_favourite: Option<bool>,
 }
 
-#[tracing::instrument(name = "Fetching lairs with search", skip(info, pool, search))]
-pub async fn fetch_lair_by_coordinates_with_search(
-    info: web::Query<LairsOnMap>,
-    pool: web::Data<PgPool>,
-    search: String,
-) -> Result<LairFetched, anyhow::Error> {
-    let query_with_search = sqlx::query_as!(
-            LairFetched,
-            r#"
-            SELECT account_id, title, image, lon, lat, room_id FROM rooms WHERE lat > $1 AND lat < $2 AND lon > $3 AND lon < $4 AND title LIKE $5
-                "#,
-            info.br_lat,
-            info.tl_lat,
-            info.tl_lng,
-            info.br_lng,
-            search
-        ).fetch_one(&*pool.clone().into_inner())
+impl LairFetched {
+    pub fn room_id(&self) -> Uuid {
+        self.room_id
+    }
+}
+
+/// Fetches up to `limit` lairs matching `filters` that come after the lair
+/// `after` in the `sort` order, flagging the favourites of `viewer`.
+#[tracing::instrument(name = "Fetching a page of lairs", skip(pool))]
+pub async fn fetch_lairs_page(
+    filters: &MapFilters,
+    sort: LairSort,
+    after: Option<Uuid>,
+    limit: i64,
+    viewer: Option<Uuid>,
+    pool: &PgPool,
+) -> Result<LairsPage, anyhow::Error> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        "SELECT r.account_id, r.title, r.image, r.lon, r.lat, r.room_id, \
+        r.nightly_price, r.currency, r.max_guests, r.bedrooms, r.average_rating, \
+        r.review_count, NULL::float8 AS distance_m, ",
+    );
+    match viewer {
+        Some(viewer) => {
+            query
+                .push(
+                    "EXISTS (SELECT 1 FROM wishlist_lairs wl JOIN wishlists w USING (wishlist_id) \
+                    WHERE wl.room_id = r.room_id AND w.user_id = ",
+                )
+                .push_bind(viewer)
+                .push(") AS is_favourite");
+        }
+        None => {
+            query.push("NULL::bool AS is_favourite");
+        }
+    }
+    query.push(" FROM rooms r WHERE ");
+    push_map_filters(&mut query, filters);
+
+    // Keyset pagination: only keep the lairs whose sort key, tie-broken by
+    // their id, comes after the one of the cursor.
+    let (comparison, direction) = match sort {
+        LairSort::Relevance | LairSort::Newest => ("<", "DESC"),
+        LairSort::Title | LairSort::Distance => (">", "ASC"),
+    };
+    if let Some(after) = after {
+        query.push(" AND (");
+        push_sort_key(&mut query, sort, "r", filters);
+        query
+            .push(", r.room_id) ")
+            .push(comparison)
+            .push(" (SELECT ");
+        push_sort_key(&mut query, sort, "c", filters);
+        query
+            .push(", c.room_id FROM rooms c WHERE c.room_id = ")
+            .push_bind(after)
+            .push(")");
+    }
+
+    query.push(" ORDER BY ");
+    push_sort_key(&mut query, sort, "r", filters);
+    query
+        .push(format!(" {direction}, r.room_id {direction} LIMIT "))
+        // One more than asked, to know whether there is a next page.
+        .push_bind(limit + 1);
+
+    let mut lairs = query
+        .build_query_as::<LairFetched>()
+        .fetch_all(pool)
         .await
-        .context("Failed to perform a query to retrieve a specific lair.")?;
-    return Ok(query_with_search);
+        .context("Failed to perform a query to retrieve lairs on the map.")?;
+
+    let next_cursor = if lairs.len() as i64 > limit {
+        lairs.truncate(limit as usize);
+        lairs.last().map(|lair| lair.room_id)
+    } else {
+        None
+    };
+    Ok(LairsPage { lairs, next_cursor })
 }
 
-#[tracing::instrument(name = "Fetching lairs without search", skip(info, pool,))]
-pub async fn fetch_lair_by_coordinates_without_search(
-    info: web::Query<LairsOnMap>,
+/// Groups the lairs matching `filters` by the cells of a grid of `cell_size`
+/// degrees, anchored at (-180, -90) so that no cell straddles the
+/// antimeridian.
+#[tracing::instrument(name = "Fetching clusters of lairs", skip(pool))]
+pub async fn fetch_lair_clusters(
+    filters: &MapFilters,
+    cell_size: f64,
+    pool: &PgPool,
+) -> Result<Vec<LairCluster>, anyhow::Error> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        "SELECT avg(r.lat) AS lat, avg(r.lon) AS lon, count(*) AS count FROM rooms r WHERE ",
+    );
+    push_map_filters(&mut query, filters);
+    query
+        .push(" GROUP BY floor((r.lon + 180) / ")
+        .push_bind(cell_size)
+        .push("), floor((r.lat + 90) / ")
+        .push_bind(cell_size)
+        .push(") ORDER BY count DESC, lat, lon");
+
+    let clusters = query
+        .build_query_as::<LairCluster>()
+        .fetch_all(pool)
+        .await
+        .context("Failed to perform a query to cluster lairs on the map.")?;
+    Ok(clusters)
+}
+
+/// Pushes the conditions `filters` put on the lairs of the `rooms` alias `r`.
+pub fn push_map_filters(query: &mut QueryBuilder<'_, Postgres>, filters: &MapFilters) {
+    let bbox = &filters.bbox;
+    query
+        .push("r.lat BETWEEN ")
+        .push_bind(bbox.south)
+        .push(" AND ")
+        .push_bind(bbox.north);
+    query.push(" AND (");
+    for (i, (west, east)) in bbox.lon_ranges.iter().enumerate() {
+        if i > 0 {
+            query.push(" OR ");
+        }
+        query
+            .push("r.lon BETWEEN ")
+            .push_bind(*west)
+            .push(" AND ")
+            .push_bind(*east);
+    }
+    query.push(")");
+
+    if let Some(search) = &filters.search {
+        let pattern = format!("%{}%", escape_like_pattern(search));
+        query
+            .push(" AND (r.title ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR r.description ILIKE ")
+            .push_bind(pattern)
+            .push(" OR r.search_vector @@ websearch_to_tsquery('english', ")
+            .push_bind(search.clone())
+            .push("))");
+    }
+
+    if let Some(min_price) = &filters.min_price {
+        query
+            .push(" AND r.nightly_price >= ")
+            .push_bind(*min_price.as_ref());
+    }
+    if let Some(max_price) = &filters.max_price {
+        query
+            .push(" AND r.nightly_price <= ")
+            .push_bind(*max_price.as_ref());
+    }
+    if let Some(currency) = &filters.currency {
+        query
+            .push(" AND r.currency = ")
+            .push_bind(currency.as_ref().to_string());
+    }
+    if let Some(guests) = &filters.guests {
+        query
+            .push(" AND r.max_guests >= ")
+            .push_bind(*guests.as_ref());
+    }
+    if let Some(bedrooms) = &filters.bedrooms {
+        query
+            .push(" AND r.bedrooms >= ")
+            .push_bind(*bedrooms.as_ref());
+    }
+
+    if !filters.amenities.is_empty() {
+        let slugs: Vec<String> = filters
+            .amenities
+            .iter()
+            .map(|slug| slug.as_ref().to_string())
+            .collect();
+        query
+            .push(
+                " AND (SELECT count(*) FROM room_amenities ra \
+                JOIN amenities a USING (amenity_id) \
+                WHERE ra.room_id = r.room_id AND a.slug = ANY(",
+            )
+            .push_bind(slugs.clone())
+            .push(")) = ")
+            .push_bind(slugs.len() as i64);
+    }
+
+    if let Some(stay) = &filters.available_for {
+        query
+            .push(
+                " AND NOT EXISTS (SELECT 1 FROM lair_unavailability u \
+                WHERE u.room_id = r.room_id AND u.nights && daterange(",
+            )
+            .push_bind(stay.check_in)
+            .push(", ")
+            .push_bind(stay.check_out)
+            .push("))");
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LairsNearby {
+    lat: f64,
+    lng: f64,
+    radius_m: f64,
+    limit: Option<i64>,
+}
+
+//#[get("/lair/nearby")]
+#[tracing::instrument(name = "Getting lairs around a point", skip(pool))]
+pub async fn lairs_nearby(
+    info: web::Query<LairsNearby>,
     pool: web::Data<PgPool>,
+    user: Option<AuthenticatedUser>,
+) -> Result<HttpResponse, ApiError> {
+    let lat = LairLat::parse(info.lat).map_err(|e| ApiError::invalid("lat", e))?;
+    let lon = LairLon::parse(info.lng).map_err(|e| ApiError::invalid("lng", e))?;
+    if !(info.radius_m > 0.0 && info.radius_m <= MAX_RADIUS_IN_METERS) {
+        return Err(ApiError::invalid(
+            "radius_m",
+            format!(
+                "The radius must be between 0 and {} meters.",
+                MAX_RADIUS_IN_METERS
+            ),
+        ));
+    }
+    let limit = info
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let viewer = user.map(|user| user.user_id());
+    let lairs = fetch_lairs_nearby(
+        *lat.as_ref(),
+        *lon.as_ref(),
+        info.radius_m,
+        limit,
+        viewer,
+        &pool,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(json!({ "lairs": lairs })))
+}
+
+/// Fetches the lairs within `radius_m` meters of a point, closest first,
+/// flagging the favourites of `viewer`.
+///
+/// `earth_box` is a bounding cube around the point that can use the GiST
+/// index on `ll_to_earth(lat, lon)`; `earth_distance` then drops the lairs in
+/// its corners.
+#[tracing::instrument(name = "Fetching lairs around a point", skip(pool))]
+pub async fn fetch_lairs_nearby(
+    lat: f64,
+    lon: f64,
+    radius_m: f64,
+    limit: i64,
+    viewer: Option<Uuid>,
+    pool: &PgPool,
 ) -> Result<Vec<LairFetched>, anyhow::Error> {
-    let query_without_search = sqlx::query_as!(
-            LairFetched,
-            r#"
-            SELECT account_id, title, image, lon, lat, room_id FROM rooms WHERE lat > $1 AND lat < $2 AND lon > $3 AND lon < $4
-                "#,
-            info.br_lat,
-            info.tl_lat,
-            info.tl_lng,
-            info.br_lng,
-            ).fetch_all(&*pool.clone().into_inner())
-            .await
-            .context("Failed to perform a query to retrieve a username.")?;
-    return Ok(query_without_search);
-}
-
-#[derive(thiserror::Error)]
-pub enum InsertError {
-    #[error("{0}")]
-    ValidationError(String),
-    #[error(transparent)]
-    UnexpectedError(#[from] anyhow::Error),
-}
-
-impl std::fmt::Debug for InsertError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        error_chain_fmt(self, f)
-    }
-}
-
-impl ResponseError for InsertError {
-    fn status_code(&self) -> StatusCode {
-        match self {
-            InsertError::ValidationError(_) => StatusCode::BAD_REQUEST,
-            InsertError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    let lairs = sqlx::query_as!(
+        LairFetched,
+        r#"
+        SELECT account_id, title, image, lon, lat, room_id, nightly_price, currency,
+            max_guests, bedrooms, average_rating, review_count,
+            earth_distance(ll_to_earth($1, $2), ll_to_earth(lat, lon)) AS "distance_m?",
+            CASE WHEN $5::uuid IS NULL THEN NULL ELSE EXISTS (
+                SELECT 1 FROM wishlist_lairs wl JOIN wishlists w USING (wishlist_id)
+                WHERE wl.room_id = rooms.room_id AND w.user_id = $5
+            ) END AS "is_favourite?"
+        FROM rooms
+        WHERE earth_box(ll_to_earth($1, $2), $3) @> ll_to_earth(lat, lon)
+        AND earth_distance(ll_to_earth($1, $2), ll_to_earth(lat, lon)) <= $3
+        ORDER BY "distance_m?", room_id
+        LIMIT $4
+            "#,
+        lat,
+        lon,
+        radius_m,
+        limit,
+        viewer,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve lairs around a point.")?;
+    Ok(lairs)
+}
+
+/// Fetches the lairs with the given ids, in no particular order, flagging the
+/// favourites of `viewer`.
+#[tracing::instrument(name = "Fetching lairs by id", skip(pool))]
+pub async fn fetch_lairs_by_id(
+    room_ids: &[Uuid],
+    viewer: Option<Uuid>,
+    pool: &PgPool,
+) -> Result<Vec<LairFetched>, anyhow::Error> {
+    let lairs = sqlx::query_as!(
+        LairFetched,
+        r#"
+        SELECT account_id, title, image, lon, lat, room_id, nightly_price, currency,
+            max_guests, bedrooms, average_rating, review_count,
+            NULL::float8 AS "distance_m?",
+            CASE WHEN $2::uuid IS NULL THEN NULL ELSE EXISTS (
+                SELECT 1 FROM wishlist_lairs wl JOIN wishlists w USING (wishlist_id)
+                WHERE wl.room_id = rooms.room_id AND w.user_id = $2
+            ) END AS "is_favourite?"
+        FROM rooms
+        WHERE room_id = ANY($1)
+            "#,
+        room_ids,
+        viewer,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve lairs by id.")?;
+    Ok(lairs)
+}
+
+/// Pushes the expression `sort` orders the lairs of the `rooms` alias `table` by.
+fn push_sort_key(
+    query: &mut QueryBuilder<'_, Postgres>,
+    sort: LairSort,
+    table: &str,
+    filters: &MapFilters,
+) {
+    match sort {
+        LairSort::Relevance => {
+            query
+                .push(format!(
+                    "ts_rank({table}.search_vector, websearch_to_tsquery('english', "
+                ))
+                .push_bind(filters.search.clone().unwrap_or_default())
+                .push("))");
+        }
+        LairSort::Newest => {
+            query.push(format!("{table}.created_at"));
+        }
+        LairSort::Title => {
+            query.push(format!("{table}.title"));
+        }
+        LairSort::Distance => {
+            // An equirectangular approximation is enough to order lairs on
+            // screen; longitudes are compared the short way around the globe.
+            let (lat, lon) = filters.bbox.centre();
+            query
+                .push(format!("power({table}.lat - "))
+                .push_bind(lat)
+                .push(format!(", 2) + power(LEAST(abs({table}.lon - "))
+                .push_bind(lon)
+                .push(format!("), 360 - abs({table}.lon - "))
+                .push_bind(lon)
+                .push(")) * cos(radians(")
+                .push_bind(lat)
+                .push(")), 2)");
         }
     }
 }
+
+/// Escapes the characters `ILIKE` treats as wildcards.
+fn escape_like_pattern(search: &str) -> String {
+    search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
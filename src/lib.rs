@@ -1,11 +1,25 @@
+pub mod amenities;
+pub mod api_error;
+pub mod authentication;
+pub mod bookings;
 pub mod configuration;
-pub mod create_cookie;
 pub mod domain;
 pub mod email_client;
+pub mod geojson;
 pub mod get_documents_from_id;
+pub mod image_storage;
+pub mod lair_calendar;
+pub mod lair_images;
+pub mod lair_pricing;
+pub mod lair_suggestions;
+pub mod lair_tiles;
 pub mod lairs_on_map;
+pub mod remote_images;
+pub mod reviews;
 pub mod routes;
 pub mod session_state;
 pub mod startup;
 pub mod telemetry;
 pub mod utils;
+pub mod vector_tile;
+pub mod wishlists;
@@ -1,6 +1,5 @@
 use lairbnb_rs::configuration::get_configuration;
 use lairbnb_rs::startup::Application;
-use lairbnb_rs::telemetry::{get_subscriber, init_subscriber};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
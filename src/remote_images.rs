@@ -0,0 +1,294 @@
+use crate::configuration::RemoteImageSettings;
+use crate::lair_images::{format_of, MAX_IMAGE_SIZE};
+use image::ImageFormat;
+use reqwest::{header, Client, Url};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use url::Host;
+
+/// How many redirects a remote image may go through.
+const MAX_REDIRECTS: usize = 3;
+
+/// Downloads the images that hosts link to, so that they can be rehosted.
+///
+/// Every hop is checked before it is requested: the URL must be http(s) and
+/// its host must only resolve to public addresses, which are then pinned for
+/// the request so that a second DNS lookup cannot point it elsewhere.
+pub struct RemoteImageFetcher {
+    enabled: bool,
+    timeout: std::time::Duration,
+    allow_private_addresses: bool,
+}
+
+impl RemoteImageFetcher {
+    pub fn new(settings: &RemoteImageSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            timeout: settings.timeout(),
+            allow_private_addresses: settings.allow_private_addresses,
+        }
+    }
+
+    /// Whether `image` points to another host over http(s) and should be
+    /// rehosted. Relative URLs are the ones we serve ourselves.
+    pub fn should_rehost(&self, image: &str) -> bool {
+        self.enabled && Url::parse(image).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+    }
+
+    /// Downloads the image at `url`, giving up after the configured timeout.
+    #[tracing::instrument(name = "Fetching a remote image", skip(self))]
+    pub async fn fetch(&self, url: &str) -> Result<(ImageFormat, Vec<u8>), RemoteImageError> {
+        let url = Url::parse(url).map_err(|_| RemoteImageError::InvalidUrl(url.to_string()))?;
+        tokio::time::timeout(self.timeout, self.download(url))
+            .await
+            .map_err(|_| RemoteImageError::DownloadFailed("the request timed out".to_string()))?
+    }
+
+    async fn download(&self, mut url: Url) -> Result<(ImageFormat, Vec<u8>), RemoteImageError> {
+        for _ in 0..=MAX_REDIRECTS {
+            let client = self.client_for(&url).await?;
+            let mut response = client
+                .get(url.clone())
+                .header(header::ACCEPT, "image/*")
+                .send()
+                .await
+                .map_err(|e| RemoteImageError::DownloadFailed(e.to_string()))?;
+
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(header::LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .ok_or_else(|| {
+                        RemoteImageError::DownloadFailed("a redirect has no location".to_string())
+                    })?;
+                url = url
+                    .join(location)
+                    .map_err(|_| RemoteImageError::InvalidUrl(location.to_string()))?;
+                continue;
+            }
+            if !response.status().is_success() {
+                return Err(RemoteImageError::DownloadFailed(format!(
+                    "the server answered {}",
+                    response.status()
+                )));
+            }
+
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .and_then(|content_type| content_type.split(';').next())
+                .map(|essence| essence.trim().to_ascii_lowercase())
+                .unwrap_or_default();
+            let format = format_of(&content_type)
+                .ok_or(RemoteImageError::UnsupportedMediaType(content_type))?;
+            if response.content_length().unwrap_or(0) > MAX_IMAGE_SIZE as u64 {
+                return Err(RemoteImageError::TooLarge);
+            }
+
+            let mut bytes = Vec::new();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| RemoteImageError::DownloadFailed(e.to_string()))?
+            {
+                if bytes.len() + chunk.len() > MAX_IMAGE_SIZE {
+                    return Err(RemoteImageError::TooLarge);
+                }
+                bytes.extend_from_slice(&chunk);
+            }
+            return Ok((format, bytes));
+        }
+        Err(RemoteImageError::DownloadFailed(
+            "there were too many redirects".to_string(),
+        ))
+    }
+
+    /// Builds a client that can only reach the public addresses of the host
+    /// of `url`.
+    async fn client_for(&self, url: &Url) -> Result<Client, RemoteImageError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(RemoteImageError::InvalidUrl(url.to_string()));
+        }
+        let host = url
+            .host()
+            .ok_or_else(|| RemoteImageError::InvalidUrl(url.to_string()))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| RemoteImageError::InvalidUrl(url.to_string()))?;
+        let addresses: Vec<SocketAddr> = match host {
+            Host::Ipv4(ip) => vec![SocketAddr::new(ip.into(), port)],
+            Host::Ipv6(ip) => vec![SocketAddr::new(ip.into(), port)],
+            Host::Domain(domain) => tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| RemoteImageError::DownloadFailed(e.to_string()))?
+                .collect(),
+        };
+        if addresses.is_empty()
+            || !self.allow_private_addresses
+                && addresses.iter().any(|address| !is_public(address.ip()))
+        {
+            return Err(RemoteImageError::ForbiddenAddress(host.to_string()));
+        }
+
+        // A proxy from the environment would connect on our behalf and
+        // bypass the addresses checked above.
+        let mut builder = Client::builder()
+            .no_proxy()
+            .redirect(reqwest::redirect::Policy::none());
+        // Literal IP addresses are not looked up again.
+        if let Host::Domain(domain) = host {
+            builder = builder.resolve_to_addrs(domain, &addresses);
+        }
+        builder
+            .build()
+            .map_err(|e| RemoteImageError::DownloadFailed(e.to_string()))
+    }
+}
+
+/// Whether `ip` belongs to the public internet rather than to a loopback,
+/// private, link-local or otherwise special-purpose range.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network"
+        || a == 0
+        // Shared address space used by carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    // Only global unicast addresses are public.
+    (segments[0] & 0xe000) == 0x2000
+        // Documentation
+        && !(segments[0] == 0x2001 && segments[1] == 0x0db8)
+        // Teredo and 6to4 tunnels can embed private IPv4 addresses.
+        && !(segments[0] == 0x2001 && segments[1] == 0)
+        && segments[0] != 0x2002
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RemoteImageError {
+    #[error("{0} is not a valid http(s) URL.")]
+    InvalidUrl(String),
+    #[error("{0} does not resolve to a public address.")]
+    ForbiddenAddress(String),
+    #[error("Failed to download the image: {0}.")]
+    DownloadFailed(String),
+    #[error("{0} is not a supported image type. Use JPEG, PNG, GIF or WebP.")]
+    UnsupportedMediaType(String),
+    #[error("The image is larger than {} bytes.", MAX_IMAGE_SIZE)]
+    TooLarge,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_public, RemoteImageError, RemoteImageFetcher};
+    use crate::configuration::RemoteImageSettings;
+    use claims::assert_err;
+    use std::net::IpAddr;
+
+    fn fetcher() -> RemoteImageFetcher {
+        RemoteImageFetcher::new(&RemoteImageSettings {
+            enabled: true,
+            timeout_milliseconds: 1000,
+            allow_private_addresses: false,
+        })
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        for address in ["93.184.216.34", "8.8.8.8", "2606:4700::1111"] {
+            assert!(is_public(ip(address)), "{} should be public", address);
+        }
+    }
+
+    #[test]
+    fn internal_addresses_are_refused() {
+        for address in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "2002:7f00:1::",
+        ] {
+            assert!(!is_public(ip(address)), "{} should not be public", address);
+        }
+    }
+
+    #[test]
+    fn only_absolute_http_urls_are_rehosted() {
+        let fetcher = fetcher();
+        assert!(fetcher.should_rehost("https://example.com/lair.png"));
+        assert!(fetcher.should_rehost("http://example.com/lair.png"));
+        assert!(!fetcher.should_rehost("/uploads/lairs/lair.png"));
+        assert!(!fetcher.should_rehost("data:image/png;base64,iVBORw0KGgo="));
+        assert!(!fetcher.should_rehost("ftp://example.com/lair.png"));
+    }
+
+    #[test]
+    fn nothing_is_rehosted_when_disabled() {
+        let fetcher = RemoteImageFetcher::new(&RemoteImageSettings {
+            enabled: false,
+            timeout_milliseconds: 1000,
+            allow_private_addresses: false,
+        });
+        assert!(!fetcher.should_rehost("https://example.com/lair.png"));
+    }
+
+    #[tokio::test]
+    async fn loopback_and_non_http_urls_are_refused() {
+        let fetcher = fetcher();
+        assert!(matches!(
+            fetcher.fetch("http://127.0.0.1/lair.png").await,
+            Err(RemoteImageError::ForbiddenAddress(_))
+        ));
+        assert!(matches!(
+            fetcher.fetch("http://localhost:8000/lair.png").await,
+            Err(RemoteImageError::ForbiddenAddress(_))
+        ));
+        assert!(matches!(
+            fetcher.fetch("http://[::1]/lair.png").await,
+            Err(RemoteImageError::ForbiddenAddress(_))
+        ));
+        assert_err!(fetcher.fetch("file:///etc/passwd").await);
+        assert_err!(fetcher.fetch("ftp://example.com/lair.png").await);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::api_error::{violates_constraint, ApiError};
+use crate::authentication::AuthenticatedUser;
+use crate::domain::{BookingStatus, ReviewRating, ReviewText};
+use crate::get_documents_from_id::RoomId;
+use crate::routes::InvalidFields;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, PgPool, Postgres};
+use uuid::Uuid;
+
+/// The constraint that keeps guests to a single review per lair.
+const ONE_REVIEW_PER_GUEST_CONSTRAINT: &str = "reviews_one_per_guest";
+const DEFAULT_PAGE_SIZE: i64 = 20;
+/// Hard cap on the page size, whatever the client asks for.
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct NewReview {
+    rating: i32,
+    text: String,
+}
+
+#[derive(Deserialize)]
+pub struct NewReply {
+    text: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewPath {
+    id: Uuid,
+    review_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewsQuery {
+    limit: Option<i64>,
+    /// The `next_cursor` of the previous page.
+    after: Option<Uuid>,
+}
+
+/// A review of a lair, along with the reply of its host once there is one.
+#[derive(Serialize)]
+pub struct Review {
+    #[serde(rename = "id")]
+    review_id: Uuid,
+    #[serde(rename = "lair_id")]
+    room_id: Uuid,
+    guest_id: Uuid,
+    guest_name: String,
+    rating: i32,
+    #[serde(rename = "text")]
+    body: String,
+    created_at: DateTime<Utc>,
+    host_reply: Option<String>,
+    host_replied_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct ReviewsPage {
+    /// `None` until the lair gets its first review.
+    average_rating: Option<Decimal>,
+    review_count: i32,
+    reviews: Vec<Review>,
+    /// `None` on the last page.
+    next_cursor: Option<Uuid>,
+}
+
+//#[post("/lair/{id}/reviews")]
+/// Lets a guest who completed a stay in a lair review it, once.
+#[tracing::instrument(
+    name = "Reviewing a lair",
+    skip(path, pool, new_review),
+    fields(user_id=%user)
+)]
+pub async fn review_lair(
+    path: web::Path<RoomId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    new_review: web::Json<NewReview>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let NewReview { rating, text } = new_review.0;
+    let mut invalid_fields = InvalidFields::default();
+    let rating = invalid_fields.check("rating", ReviewRating::parse(rating));
+    let text = invalid_fields.check("text", ReviewText::parse(text));
+    let (rating, text) = match (rating, text) {
+        (Some(rating), Some(text)) => (rating, text),
+        _ => return Err(ApiError::ValidationError(invalid_fields)),
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    let lair = sqlx::query!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM bookings
+            WHERE room_id = $1 AND guest_id = $2 AND status = $3
+        ) AS "stayed!"
+        FROM rooms WHERE room_id = $1
+        "#,
+        room_id,
+        user.user_id(),
+        BookingStatus::Completed as BookingStatus,
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to perform a query to retrieve a lair.")?
+    .ok_or(ApiError::NotFound("The lair does not exist."))?;
+    if !lair.stayed {
+        return Err(ApiError::Forbidden(
+            "Only guests who completed a stay can review this lair.".to_string(),
+        ));
+    }
+
+    let review_id = Uuid::new_v4();
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO reviews (review_id, room_id, guest_id, rating, body)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        review_id,
+        room_id,
+        user.user_id(),
+        rating.as_ref(),
+        text.as_ref(),
+    );
+    transaction.execute(query).await.map_err(|e| {
+        if violates_constraint(&e, ONE_REVIEW_PER_GUEST_CONSTRAINT) {
+            ApiError::Conflict("You have already reviewed this lair.".to_string())
+        } else {
+            anyhow::Error::new(e)
+                .context("Failed to insert a new review.")
+                .into()
+        }
+    })?;
+    let review = fetch_review(review_id, &mut *transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to store a new review.")?;
+    Ok(HttpResponse::Created().json(review))
+}
+
+//#[post("/lair/{id}/reviews/{review_id}/reply")]
+/// Lets the host of a lair reply to one of its reviews, once and in public.
+#[tracing::instrument(
+    name = "Replying to a review",
+    skip(path, pool, new_reply),
+    fields(user_id=%user)
+)]
+pub async fn reply_to_review(
+    path: web::Path<ReviewPath>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+    new_reply: web::Json<NewReply>,
+) -> Result<HttpResponse, ApiError> {
+    let text = ReviewText::parse(new_reply.0.text)
+        .map_err(|message| ApiError::ValidationError(InvalidFields::single("text", message)))?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to launch the transaction")?;
+    let current = sqlx::query!(
+        r#"
+        SELECT r.account_id AS host_id, v.host_reply
+        FROM reviews v JOIN rooms r USING (room_id)
+        WHERE v.review_id = $1 AND v.room_id = $2
+        FOR UPDATE OF v
+        "#,
+        path.review_id,
+        path.id,
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to perform a query to retrieve a review.")?
+    .ok_or(ApiError::NotFound("The lair or the review does not exist."))?;
+    if current.host_id != user.user_id() {
+        return Err(ApiError::Forbidden(
+            "Only the host can reply to the reviews of a lair.".to_string(),
+        ));
+    }
+    if current.host_reply.is_some() {
+        return Err(ApiError::Conflict(
+            "You have already replied to this review.".to_string(),
+        ));
+    }
+
+    let query = sqlx::query!(
+        "UPDATE reviews SET host_reply = $2, host_replied_at = now() WHERE review_id = $1",
+        path.review_id,
+        text.as_ref(),
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to save the reply to a review.")?;
+    let review = fetch_review(path.review_id, &mut *transaction).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to reply to a review.")?;
+    Ok(HttpResponse::Ok().json(review))
+}
+
+//#[get("/lair/{id}/reviews")]
+/// The reviews of a lair, newest first, along with its average rating.
+#[tracing::instrument(name = "Listing the reviews of a lair", skip(path, pool))]
+pub async fn lair_reviews(
+    path: web::Path<RoomId>,
+    query: web::Query<ReviewsQuery>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiError> {
+    let room_id = path.id;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let summary = sqlx::query!(
+        "SELECT average_rating, review_count FROM rooms WHERE room_id = $1",
+        room_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .context("Failed to perform a query to retrieve a lair.")?
+    .ok_or(ApiError::NotFound("The lair does not exist."))?;
+    if let Some(after) = query.after {
+        // An unknown cursor would silently restart from the first page.
+        let known = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM reviews WHERE review_id = $1 AND room_id = $2
+            ) AS "known!"
+            "#,
+            after,
+            room_id
+        )
+        .fetch_one(pool.get_ref())
+        .await
+        .context("Failed to look up the cursor.")?;
+        if !known {
+            return Err(ApiError::ValidationError(InvalidFields::single(
+                "after",
+                "The cursor does not match any review of this lair.".to_string(),
+            )));
+        }
+    }
+
+    // Keyset pagination, like the lairs on the map.
+    let mut reviews = sqlx::query_as!(
+        Review,
+        r#"
+        SELECT v.review_id, v.room_id, v.guest_id, u.account_name AS guest_name, v.rating,
+            v.body, v.created_at, v.host_reply, v.host_replied_at
+        FROM reviews v
+        JOIN users u ON u.id = v.guest_id
+        WHERE v.room_id = $1
+        AND ($2::uuid IS NULL OR (v.created_at, v.review_id) < (
+            SELECT c.created_at, c.review_id FROM reviews c
+            WHERE c.review_id = $2 AND c.room_id = $1
+        ))
+        ORDER BY v.created_at DESC, v.review_id DESC
+        LIMIT $3
+        "#,
+        room_id,
+        query.after,
+        // One more than asked, to know whether there is a next page.
+        limit + 1,
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch the reviews of a lair.")?;
+
+    let next_cursor = if reviews.len() as i64 > limit {
+        reviews.truncate(limit as usize);
+        reviews.last().map(|review| review.review_id)
+    } else {
+        None
+    };
+    Ok(HttpResponse::Ok().json(ReviewsPage {
+        average_rating: summary.average_rating,
+        review_count: summary.review_count,
+        reviews,
+        next_cursor,
+    }))
+}
+
+#[tracing::instrument(name = "Fetching a review", skip(executor))]
+pub async fn fetch_review<'c, E>(review_id: Uuid, executor: E) -> Result<Review, anyhow::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query_as!(
+        Review,
+        r#"
+        SELECT v.review_id, v.room_id, v.guest_id, u.account_name AS guest_name, v.rating,
+            v.body, v.created_at, v.host_reply, v.host_replied_at
+        FROM reviews v
+        JOIN users u ON u.id = v.guest_id
+        WHERE v.review_id = $1
+        "#,
+        review_id
+    )
+    .fetch_one(executor)
+    .await
+    .context("Failed to fetch a review.")
+}
@@ -1,118 +1,293 @@
-use crate::{
-    create_cookie::{extract_cookie, UserInfo},
-    routes::admin::{dashboard::get_username},
-    utils::{e500, see_other},
+use crate::api_error::ApiError;
+use crate::authentication::AuthenticatedUser;
+use crate::domain::{
+    BedroomCount, Currency, GuestCount, LairDescription, LairImage, LairLat, LairLon, LairPricing,
+    LairTitle, NewLair, Price, StayLimits,
 };
-use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use crate::image_storage::ImageStorage;
+use crate::lair_images::{delete_stored_files, insert_cover_photo, rehost_image};
+use crate::remote_images::RemoteImageFetcher;
+use actix_web::{web, HttpResponse};
 use actix_web_flash_messages::FlashMessage;
 use anyhow::Context;
-use reqwest::StatusCode;
-use serde::Deserialize;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Executor, PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 #[derive(Deserialize)]
 pub struct LairInfo {
-    title: String,
-    description: String,
-    image: String,
-    lon: f64,
-    lat: f64,
+    pub title: String,
+    pub description: String,
+    pub image: String,
+    pub lon: f64,
+    pub lat: f64,
+    pub max_guests: Option<i32>,
+    pub bedrooms: Option<i32>,
+    pub nightly_price: Option<Decimal>,
+    pub weekend_price: Option<Decimal>,
+    pub cleaning_fee: Option<Decimal>,
+    pub currency: Option<String>,
+    pub min_nights: Option<i32>,
+    pub max_nights: Option<i32>,
+}
+
+impl TryFrom<LairInfo> for NewLair {
+    type Error = InvalidFields;
+
+    fn try_from(value: LairInfo) -> Result<Self, Self::Error> {
+        let mut invalid_fields = InvalidFields::default();
+        let title = invalid_fields.check("title", LairTitle::parse(value.title));
+        let description =
+            invalid_fields.check("description", LairDescription::parse(value.description));
+        let image = invalid_fields.check("image", LairImage::parse(value.image));
+        let lon = invalid_fields.check("lon", LairLon::parse(value.lon));
+        let lat = invalid_fields.check("lat", LairLat::parse(value.lat));
+        let max_guests = invalid_fields.check(
+            "max_guests",
+            GuestCount::parse(value.max_guests.unwrap_or(1)),
+        );
+        let bedrooms =
+            invalid_fields.check("bedrooms", BedroomCount::parse(value.bedrooms.unwrap_or(0)));
+        let pricing = parse_pricing(
+            value.nightly_price,
+            value.weekend_price,
+            value.cleaning_fee,
+            value.currency,
+            &mut invalid_fields,
+        );
+        let min_nights = value.min_nights.unwrap_or(1);
+        let stay_limits = invalid_fields
+            .check("min_nights", StayLimits::parse(min_nights, None))
+            .and_then(|_| {
+                invalid_fields.check(
+                    "max_nights",
+                    StayLimits::parse(min_nights, value.max_nights),
+                )
+            });
+
+        match (
+            title,
+            description,
+            image,
+            lon,
+            lat,
+            max_guests,
+            bedrooms,
+            pricing,
+            stay_limits,
+        ) {
+            (
+                Some(title),
+                Some(description),
+                Some(image),
+                Some(lon),
+                Some(lat),
+                Some(max_guests),
+                Some(bedrooms),
+                Some(pricing),
+                Some(stay_limits),
+            ) => Ok(Self {
+                title,
+                description,
+                image,
+                lon,
+                lat,
+                max_guests,
+                bedrooms,
+                pricing,
+                stay_limits,
+            }),
+            _ => Err(invalid_fields),
+        }
+    }
+}
+
+/// Returns `Some(None)` for a lair without any price, and `None` when some
+/// price is invalid.
+fn parse_pricing(
+    nightly_price: Option<Decimal>,
+    weekend_price: Option<Decimal>,
+    cleaning_fee: Option<Decimal>,
+    currency: Option<String>,
+    invalid_fields: &mut InvalidFields,
+) -> Option<Option<LairPricing>> {
+    let nightly_price =
+        invalid_fields.check("nightly_price", nightly_price.map(Price::parse).transpose());
+    let weekend_price =
+        invalid_fields.check("weekend_price", weekend_price.map(Price::parse).transpose());
+    let cleaning_fee =
+        invalid_fields.check("cleaning_fee", cleaning_fee.map(Price::parse).transpose());
+    let currency = invalid_fields.check("currency", currency.map(Currency::parse).transpose());
+
+    match (nightly_price?, weekend_price?, cleaning_fee?, currency?) {
+        (Some(nightly_price), weekend_price, cleaning_fee, Some(currency)) => {
+            Some(Some(LairPricing {
+                nightly_price,
+                weekend_price,
+                cleaning_fee: cleaning_fee.unwrap_or(Price::ZERO),
+                currency,
+            }))
+        }
+        (Some(_), _, _, None) => {
+            invalid_fields.push(
+                "currency",
+                "A currency is required along with a nightly price.".to_string(),
+            );
+            None
+        }
+        (None, None, None, None) => Some(None),
+        (None, ..) => {
+            invalid_fields.push(
+                "nightly_price",
+                "A nightly price is required to set a currency or other prices.".to_string(),
+            );
+            None
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvalidField {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Every field of a request body that failed validation, so that clients can
+/// fix them all in one go.
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct InvalidFields(Vec<InvalidField>);
+
+impl InvalidFields {
+    pub fn single(field: &'static str, message: String) -> Self {
+        Self(vec![InvalidField { field, message }])
+    }
+
+    pub fn check<T>(&mut self, field: &'static str, parsed: Result<T, String>) -> Option<T> {
+        parsed.map_err(|message| self.push(field, message)).ok()
+    }
+
+    fn push(&mut self, field: &'static str, message: String) {
+        self.0.push(InvalidField { field, message });
+    }
+
+    /// A 400 whose body lists every invalid field.
+    pub fn error_response(&self) -> HttpResponse {
+        HttpResponse::BadRequest().json(json!({
+            "status": "error",
+            "message": self.to_string(),
+            "errors": self,
+        }))
+    }
+}
+
+impl std::fmt::Display for InvalidFields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<_> = self.0.iter().map(|invalid| invalid.field).collect();
+        write!(f, "Invalid fields: {}.", fields.join(", "))
+    }
 }
 
 #[tracing::instrument(
     name = "Saving new lair details in the database",
-    skip(pool, lair_info)
+    skip(pool, lair_info, fetcher, storage),
+    fields(user_id=%user)
 )]
 pub async fn insert_lair(
     pool: web::Data<PgPool>,
     lair_info: web::Json<LairInfo>,
-    request: HttpRequest,
-) -> Result<HttpResponse, InsertError> {
-    let account_information = extract_cookie(request).unwrap();
+    fetcher: web::Data<RemoteImageFetcher>,
+    storage: web::Data<dyn ImageStorage>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let new_lair: NewLair = lair_info.0.try_into().map_err(ApiError::ValidationError)?;
     let room_id = Uuid::new_v4();
+    let account_id = user.user_id();
 
-    let account_id = get_account_info(account_information, &pool).await?;
-
-    let mut transaction = pool
-        .begin()
-        .await
-        .with_context(|| format!("Failed to launch the transaction"))?;
+    let rehosted = if fetcher.should_rehost(new_lair.image.as_ref()) {
+        Some(
+            rehost_image(
+                room_id,
+                new_lair.image.as_ref(),
+                &fetcher,
+                storage.get_ref(),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
 
-    insert_lair_into_db(lair_info, &mut transaction, account_id, room_id)
-        .await
-        .context("Failed to store lair issue details")?;
-    transaction
-        .commit()
-        .await
-        .context("Failed to commit SQL transaction to store a new lair.")?;
+    let saved: Result<(), anyhow::Error> = async {
+        let mut transaction = pool
+            .begin()
+            .await
+            .context("Failed to launch the transaction")?;
+        insert_lair_into_db(&new_lair, &mut transaction, account_id, room_id)
+            .await
+            .context("Failed to store lair issue details")?;
+        if let Some(stored) = &rehosted {
+            insert_cover_photo(room_id, stored, &mut transaction).await?;
+        }
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit SQL transaction to store a new lair.")?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = saved {
+        if let Some(stored) = rehosted {
+            delete_stored_files(storage.get_ref(), &stored.keys()).await;
+        }
+        return Err(e.into());
+    }
     FlashMessage::info("You've added the lair successfully!").send();
-    Ok(HttpResponse::Ok().json(json!({"status":"success"})))
+    Ok(HttpResponse::Ok().json(json!({"status":"success", "id": room_id})))
 }
 
 #[tracing::instrument(
     name = "Saving new lair details in the database",
-    skip(lair_info, transaction, user_id, room_id)
+    skip(new_lair, transaction, user_id, room_id)
 )]
 pub async fn insert_lair_into_db(
-    lair_info: web::Json<LairInfo>,
+    new_lair: &NewLair,
     transaction: &mut Transaction<'_, Postgres>,
     user_id: Uuid,
     room_id: Uuid,
 ) -> Result<HttpResponse, sqlx::Error> {
+    let pricing = new_lair.pricing.as_ref();
     let query = sqlx::query!(
         r#"
-    INSERT INTO rooms (account_id, title, image, description, lon, lat, room_id)
-    VALUES ($1, $2, $3, $4, $5, $6, $7)
+    INSERT INTO rooms (account_id, title, image, description, lon, lat, room_id,
+        nightly_price, weekend_price, cleaning_fee, currency, min_nights, max_nights,
+        max_guests, bedrooms)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
             "#,
         user_id,
-        lair_info.title,
-        lair_info.image,
-        lair_info.description,
-        lair_info.lon,
-        lair_info.lat,
+        new_lair.title.as_ref(),
+        new_lair.image.as_ref(),
+        new_lair.description.as_ref(),
+        new_lair.lon.as_ref(),
+        new_lair.lat.as_ref(),
         room_id,
+        pricing.map(|pricing| *pricing.nightly_price.as_ref()),
+        pricing
+            .and_then(|pricing| pricing.weekend_price)
+            .map(|price| *price.as_ref()),
+        pricing.map_or(Decimal::ZERO, |pricing| *pricing.cleaning_fee.as_ref()),
+        pricing.map(|pricing| pricing.currency.as_ref()),
+        new_lair.stay_limits.min_nights(),
+        new_lair.stay_limits.max_nights(),
+        new_lair.max_guests.as_ref(),
+        new_lair.bedrooms.as_ref(),
     );
     transaction.execute(query).await?;
     Ok(HttpResponse::Ok().finish())
 }
 
-#[tracing::instrument(name = "Gatting account id", skip(account_information, pool))]
-pub async fn get_account_info(
-    account_information: UserInfo,
-    pool: &web::Data<PgPool>,
-) -> Result<Uuid, anyhow::Error> {
-    let name: String = account_information.name;
-    let password: String = account_information.password;
-    let query = sqlx::query!(
-        r#"
-    SELECT id FROM users 
-    WHERE account_name = $1 AND account_password = $2
-            "#,
-        name,
-        password,
-    )
-    .fetch_one(&*pool.clone().into_inner())
-    .await
-    .context("Failed to perform a query to retrieve a username.")?;
-    Ok(query.id)
-}
-
-#[derive(thiserror::Error)]
-pub enum InsertError {
-    #[error("{0}")]
-    ValidationError(String),
-    #[error(transparent)]
-    UnexpectedError(#[from] anyhow::Error),
-}
-
-impl std::fmt::Debug for InsertError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        error_chain_fmt(self, f)
-    }
-}
-
 pub fn error_chain_fmt(
     e: &impl std::error::Error,
     f: &mut std::fmt::Formatter<'_>,
@@ -125,11 +300,3 @@ pub fn error_chain_fmt(
     }
     Ok(())
 }
-impl ResponseError for InsertError {
-    fn status_code(&self) -> StatusCode {
-        match self {
-            InsertError::ValidationError(_) => StatusCode::BAD_REQUEST,
-            InsertError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        }
-    }
-}
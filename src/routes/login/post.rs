@@ -1,16 +1,16 @@
-use crate::create_cookie::create_cookie;
-use crate::domain::{SubscriberName, SubscriberPassword};
+use crate::authentication::{
+    issue_token, revoke_token, validate_credentials, AuthError, BearerToken, Credentials,
+};
 use crate::routes::error_chain_fmt;
-use actix_web::http::header::LOCATION;
+use crate::session_state::TypedSession;
+use crate::startup::HmacSecret;
 use actix_web::HttpResponse;
 use actix_web::{web, ResponseError};
-use actix_web_flash_messages::FlashMessage;
 use anyhow::Context;
 use reqwest::StatusCode;
-use secrecy::{ExposeSecret, Secret};
+use secrecy::Secret;
 use serde_json::json;
 use sqlx::PgPool;
-use uuid::Uuid;
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -20,58 +20,68 @@ pub struct FormData {
 }
 
 #[tracing::instrument(
-    skip(form, pool),
+    skip(form, pool, secret, session),
     fields(username=tracing::field::Empty, user_id=tracing::field::Empty)
 )]
 // We are now injecting `PgPool` to retrieve stored credentials from the database
 pub async fn login(
     form: web::Json<FormData>,
     pool: web::Data<PgPool>,
+    secret: web::Data<HmacSecret>,
+    session: TypedSession,
 ) -> Result<HttpResponse, FetchError> {
-    let credentials_check = check_user_exists(&form, pool).await?;
+    let credentials = Credentials {
+        username: form.0.full_name,
+        password: form.0.password,
+    };
+    tracing::Span::current().record("username", tracing::field::display(&credentials.username));
 
-    if credentials_check == None {
-        FlashMessage::info(
-            "Account doesn't exist, please create an account before trying to login!",
-        )
-        .send();
-        HttpResponse::SeeOther()
-            .insert_header((LOCATION, "/registration"))
-            .finish();
-    }
+    let user_id = validate_credentials(credentials, &pool)
+        .await
+        .map_err(|e| match e {
+            AuthError::InvalidCredentials(_) => FetchError::InvalidCredentials(e.into()),
+            AuthError::UnexpectedError(_) => FetchError::UnexpectedError(e.into()),
+        })?;
+    tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let cookie = issue_token(user_id, &secret, &mut transaction)
+        .await
+        .context("Failed to issue a token.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to store a new token.")?;
 
-    let cookie = create_cookie(
-        &SubscriberName::parse(form.0.full_name).unwrap(),
-        &SubscriberPassword::parse(form.0.password.expose_secret().to_string()).unwrap(),
-    );
+    // Browsers also get a session, which the HTML admin pages rely on.
+    session.renew();
+    session
+        .insert_user_id(user_id)
+        .context("Failed to store the user id in the session.")?;
 
     Ok(HttpResponse::Ok().json(json!({"status":"success", "cookie":cookie})))
 }
 
-#[tracing::instrument(name = "checking if user exists", skip(form, pool))]
-pub async fn check_user_exists(
-    form: &web::Json<FormData>,
+#[tracing::instrument(name = "Revoking the current token", skip(token, pool, session))]
+pub async fn logout(
+    token: BearerToken,
     pool: web::Data<PgPool>,
-) -> Result<Option<Uuid>, FetchError> {
-    let name = &form.0.full_name;
-    let password = form.0.password.expose_secret();
-    let query = sqlx::query!(
-        r#"
-    SELECT id FROM users WHERE account_name = $1 AND account_password = $2
-            "#,
-        name,
-        password,
-    )
-    .fetch_one(&*pool.clone().into_inner())
-    .await
-    .context("Failed to perform a query to retrieve a username.")?;
-    Ok(Some(query.id))
+    session: TypedSession,
+) -> Result<HttpResponse, FetchError> {
+    revoke_token(token.token_id(), &pool).await?;
+    session.log_out();
+    Ok(HttpResponse::Ok().json(json!({"status":"success"})))
 }
 
 #[derive(thiserror::Error)]
 pub enum FetchError {
     #[error("{0}")]
     ValidationError(String),
+    #[error("Authentication failed")]
+    InvalidCredentials(#[source] anyhow::Error),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -86,6 +96,7 @@ impl ResponseError for FetchError {
     fn status_code(&self) -> StatusCode {
         match self {
             FetchError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            FetchError::InvalidCredentials(_) => StatusCode::UNAUTHORIZED,
             FetchError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
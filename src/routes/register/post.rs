@@ -1,11 +1,13 @@
-use crate::create_cookie::create_cookie;
+use crate::authentication::{compute_password_hash, issue_token};
 use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName, SubscriberPassword};
 use crate::routes::error_chain_fmt;
-use crate::utils::see_other;
+use crate::startup::HmacSecret;
+use crate::telemetry::spawn_blocking_with_tracing;
 use actix_web::{web, HttpResponse, ResponseError};
 use actix_web_flash_messages::FlashMessage;
 use anyhow::Context;
 use reqwest::StatusCode;
+use secrecy::{ExposeSecret, Secret};
 use serde::Deserialize;
 use serde_json::json;
 use sqlx::{Executor, PgPool, Postgres, Transaction};
@@ -59,32 +61,35 @@ impl ResponseError for SubscribeError {
 #[allow(clippy::async_yields_async)]
 #[tracing::instrument(
     name = "Adding a new subscriber",
-    skip(form, pool),
+    skip(form, pool, secret),
     fields(
         subscriber_name = %form.full_name,
         subscriber_email = %form.email,
-        subscriber_password = %form.password,
     )
 )]
 pub async fn register(
     form: web::Json<FormData>,
     pool: web::Data<PgPool>,
+    secret: web::Data<HmacSecret>,
 ) -> Result<HttpResponse, SubscribeError> {
-    let new_subscriber = NewSubscriber {
-        name: SubscriberName::parse(form.0.full_name).expect("Name check failed"),
-        email: SubscriberEmail::parse(form.0.email).expect("Email check failed"),
-        password: SubscriberPassword::parse(form.0.password).expect("Password check failed"),
-    };
+    let new_subscriber: NewSubscriber =
+        form.0.try_into().map_err(SubscribeError::ValidationError)?;
 
-    let cookie = create_cookie(&new_subscriber.name, &new_subscriber.password);
+    let password = Secret::new(new_subscriber.password.as_ref().to_owned());
+    let password_hash = spawn_blocking_with_tracing(move || compute_password_hash(password))
+        .await
+        .context("Failed to spawn blocking task.")??;
 
     let mut transaction = pool
         .begin()
         .await
         .context("Failed to acquire a Postgres connection from the pool")?;
-    let _subscriber_id = insert_user(&new_subscriber, &mut transaction)
+    let subscriber_id = insert_user(&new_subscriber, password_hash, &mut transaction)
         .await
         .context("Failed to insert new subscriber in the database.")?;
+    let cookie = issue_token(subscriber_id, &secret, &mut transaction)
+        .await
+        .context("Failed to issue a token for the new subscriber.")?;
     transaction
         .commit()
         .await
@@ -95,10 +100,11 @@ pub async fn register(
 
 #[tracing::instrument(
     name = "Saving new subscriber details in the database",
-    skip(new_subscriber, transaction)
+    skip(new_subscriber, password_hash, transaction)
 )]
 pub async fn insert_user(
     new_subscriber: &NewSubscriber,
+    password_hash: Secret<String>,
     transaction: &mut Transaction<'_, Postgres>,
 ) -> Result<Uuid, sqlx::Error> {
     let subscriber_id = Uuid::new_v4();
@@ -109,7 +115,7 @@ pub async fn insert_user(
             "#,
         subscriber_id,
         new_subscriber.name.as_ref(),
-        new_subscriber.password.as_ref(),
+        password_hash.expose_secret(),
         new_subscriber.email.as_ref(),
     );
     transaction.execute(query).await.map_err(|e| {
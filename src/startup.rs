@@ -1,8 +1,25 @@
-use crate::get_documents_from_id::{deleting_lair, looking_at_lair};
-use crate::lairs_on_map::lairs_based_on_coordinates;
+use crate::amenities::{
+    add_amenity, list_amenities, remove_amenity, replace_amenity, set_lair_amenities,
+};
+use crate::authentication::reject_anonymous_users;
+use crate::bookings::{book_lair, incoming_bookings, my_bookings, transition_booking};
+use crate::get_documents_from_id::{deleting_lair, looking_at_lair, patching_lair, replacing_lair};
+use crate::image_storage::{ImageStorage, LocalImageStorage};
+use crate::lair_calendar::{block_lair_nights, lair_blocks, lair_calendar, unblock_lair_nights};
+use crate::lair_images::{deleting_lair_image, reordering_lair_images, upload_lair_image};
+use crate::lair_pricing::{add_lair_season, lair_seasons, quote_lair, remove_lair_season};
+use crate::lair_suggestions::suggest_lairs;
+use crate::lair_tiles::lairs_tile;
+use crate::lairs_on_map::{lairs_based_on_coordinates, lairs_nearby};
+use crate::remote_images::RemoteImageFetcher;
+use crate::reviews::{lair_reviews, reply_to_review, review_lair};
 use crate::routes::{
-    admin_dashboard, health_check, home, insert_lair,
-    insert_lair_form, log_out, login, login_form, register, sign_up_form,
+    admin_dashboard, health_check, insert_lair, insert_lair_form, log_out, login, login_form,
+    logout, register,
+};
+use crate::wishlists::{
+    add_to_wishlist, create_wishlist, delete_wishlist, my_wishlists, remove_from_wishlist,
+    rotate_share_link, shared_wishlist,
 };
 use crate::{
     configuration::{DatabaseSettings, Settings},
@@ -24,6 +41,7 @@ use actix_web_lab::middleware::from_fn;
 use secrecy::{ExposeSecret, Secret};
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::net::TcpListener;
+use std::sync::Arc;
 use tracing_actix_web::TracingLogger;
 
 pub struct Application {
@@ -40,9 +58,9 @@ impl Application {
             .expect("Invalid sender email address.");
         let timeout = configuration.email_client.timeout();
         let email_client = EmailClient::new(
-            configuration.email_client.base_url,
+            configuration.email_client.base_url.clone(),
             sender_email,
-            configuration.email_client.authorization_token,
+            configuration.email_client.authorization_token.clone(),
             timeout,
         );
 
@@ -50,17 +68,10 @@ impl Application {
             "{}:{}",
             configuration.application.host, configuration.application.port
         );
+        std::fs::create_dir_all(&configuration.storage.local_directory)?;
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr().unwrap().port();
-        let server = run(
-            listener,
-            connection_pool,
-            email_client,
-            configuration.application.base_url,
-            configuration.application.hmac_secret,
-            configuration.redis_uri,
-        )
-        .await?;
+        let server = run(listener, connection_pool, email_client, configuration).await?;
 
         Ok(Self { port, server })
     }
@@ -84,13 +95,25 @@ async fn run(
     listener: TcpListener,
     db_pool: PgPool,
     email_client: EmailClient,
-    base_url: String,
-    hmac_secret: Secret<String>,
-    redis_uri: Secret<String>,
+    configuration: Settings,
 ) -> Result<Server, anyhow::Error> {
+    let Settings {
+        application,
+        map: map_settings,
+        storage: storage_settings,
+        remote_images: remote_image_settings,
+        redis_uri,
+        ..
+    } = configuration;
+    let base_url = application.base_url;
+    let hmac_secret = application.hmac_secret;
     let db_pool = Data::new(db_pool);
     let email_client = Data::new(email_client);
     let base_url = Data::new(ApplicationBaseUrl(base_url));
+    let map_settings = Data::new(map_settings);
+    let remote_image_fetcher = Data::new(RemoteImageFetcher::new(&remote_image_settings));
+    let image_storage: Data<dyn ImageStorage> =
+        Data::from(Arc::new(LocalImageStorage::new(&storage_settings)) as Arc<dyn ImageStorage>);
     let secret_key = Key::from(hmac_secret.expose_secret().as_bytes());
     let message_store = CookieMessageStore::builder(secret_key.clone()).build();
     let message_framework = FlashMessagesFramework::builder(message_store).build();
@@ -112,16 +135,117 @@ async fn run(
             ))
             .wrap(TracingLogger::default())
             .service(fs::Files::new("/static", "static").show_files_listing())
+            .service(fs::Files::new(
+                &storage_settings.public_path,
+                &storage_settings.local_directory,
+            ))
+            .route("/health_check", web::get().to(health_check))
+            .route("/login", web::get().to(login_form))
+            .service(
+                web::scope("/admin")
+                    .wrap(from_fn(reject_anonymous_users))
+                    .route("/dashboard", web::get().to(admin_dashboard))
+                    .route("/dashboard/insert_lair", web::get().to(insert_lair_form))
+                    .route("/logout", web::post().to(log_out)),
+            )
             .route("/user", web::post().to(register))
             .route("/user/login", web::post().to(login))
-            .route("/lair", web::post().to(insert_lair))
-            .service(web::resource("/lair/{id}").route(web::get().to(looking_at_lair)))
-            .service(web::resource("/lair/{id}").route(web::delete().to(deleting_lair)))
-            .service(web::resource("/lair").route(web::get().to(lairs_based_on_coordinates)))
+            .route("/user/logout", web::post().to(logout))
+            .service(
+                web::resource("/lair")
+                    .route(web::get().to(lairs_based_on_coordinates))
+                    .route(web::post().to(insert_lair)),
+            )
+            // Registered before `/lair/{id}`, which would match them too.
+            .route("/lair/suggest", web::get().to(suggest_lairs))
+            .route("/lair/nearby", web::get().to(lairs_nearby))
+            .route("/lair/{id}/images", web::post().to(upload_lair_image))
+            .route(
+                "/lair/{id}/images/order",
+                web::put().to(reordering_lair_images),
+            )
+            .route(
+                "/lair/{id}/images/{image_id}",
+                web::delete().to(deleting_lair_image),
+            )
+            .route("/lair/{id}/bookings", web::post().to(book_lair))
+            .route("/lair/{id}/calendar", web::get().to(lair_calendar))
+            .service(
+                web::resource("/lair/{id}/blocks")
+                    .route(web::get().to(lair_blocks))
+                    .route(web::post().to(block_lair_nights)),
+            )
+            .route(
+                "/lair/{id}/blocks/{block_id}",
+                web::delete().to(unblock_lair_nights),
+            )
+            .route("/lair/{id}/quote", web::get().to(quote_lair))
+            .route("/lair/{id}/amenities", web::put().to(set_lair_amenities))
+            .service(
+                web::resource("/lair/{id}/reviews")
+                    .route(web::get().to(lair_reviews))
+                    .route(web::post().to(review_lair)),
+            )
+            .route(
+                "/lair/{id}/reviews/{review_id}/reply",
+                web::post().to(reply_to_review),
+            )
+            .service(
+                web::resource("/lair/{id}/seasons")
+                    .route(web::get().to(lair_seasons))
+                    .route(web::post().to(add_lair_season)),
+            )
+            .route(
+                "/lair/{id}/seasons/{season_id}",
+                web::delete().to(remove_lair_season),
+            )
+            .route(
+                "/bookings/{booking_id}/{action}",
+                web::post().to(transition_booking),
+            )
+            .service(
+                web::resource("/amenities")
+                    .route(web::get().to(list_amenities))
+                    .route(web::post().to(add_amenity)),
+            )
+            .service(
+                web::resource("/amenities/{id}")
+                    .route(web::put().to(replace_amenity))
+                    .route(web::delete().to(remove_amenity)),
+            )
+            .route("/me/bookings", web::get().to(my_bookings))
+            .route("/me/hosting/bookings", web::get().to(incoming_bookings))
+            .service(
+                web::resource("/me/wishlists")
+                    .route(web::get().to(my_wishlists))
+                    .route(web::post().to(create_wishlist)),
+            )
+            .route("/me/wishlists/{id}", web::delete().to(delete_wishlist))
+            .route(
+                "/me/wishlists/{id}/share-link",
+                web::post().to(rotate_share_link),
+            )
+            .service(
+                web::resource("/me/wishlists/{id}/lairs/{room_id}")
+                    .route(web::post().to(add_to_wishlist))
+                    .route(web::delete().to(remove_from_wishlist)),
+            )
+            .route("/wishlists/{id}", web::get().to(shared_wishlist))
+            .route("/tiles/{z}/{x}/{y}.mvt", web::get().to(lairs_tile))
+            .service(
+                web::resource("/lair/{id}")
+                    .route(web::get().to(looking_at_lair))
+                    .route(web::put().to(replacing_lair))
+                    .route(web::patch().to(patching_lair))
+                    .route(web::delete().to(deleting_lair)),
+            )
             .app_data(db_pool.clone())
             .app_data(email_client.clone())
             .app_data(base_url.clone())
             .app_data(Data::new(HmacSecret(hmac_secret.clone())))
+            .app_data(map_settings.clone())
+            .app_data(image_storage.clone())
+            .app_data(remote_image_fetcher.clone())
     })
     .listen(listener)?
     .run();
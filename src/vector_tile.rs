@@ -0,0 +1,248 @@
+//! A minimal encoder for Mapbox Vector Tiles (version 2.1) holding points.
+//!
+//! See <https://github.com/mapbox/vector-tile-spec/tree/master/2.1> for the
+//! protobuf schema the field numbers below come from.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub const MVT_MIME_TYPE: &str = "application/vnd.mapbox-vector-tile";
+pub const DEFAULT_EXTENT: u32 = 4096;
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_64_BIT: u32 = 1;
+const WIRE_LENGTH_DELIMITED: u32 = 2;
+
+const GEOM_TYPE_POINT: u64 = 1;
+const COMMAND_MOVE_TO: u32 = 1;
+
+/// The value of a feature property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagValue {
+    String(String),
+    Double(f64),
+    Int(i64),
+}
+
+impl TagValue {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            TagValue::String(value) => write_bytes(&mut buf, 1, value.as_bytes()),
+            TagValue::Double(value) => {
+                write_key(&mut buf, 3, WIRE_64_BIT);
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            TagValue::Int(value) => write_uint(&mut buf, 6, zigzag(*value)),
+        }
+        buf
+    }
+}
+
+/// A layer of point features, with its deduplicated keys and values.
+pub struct TileLayer {
+    name: String,
+    extent: u32,
+    keys: Vec<String>,
+    values: Vec<TagValue>,
+    /// The index of each key, to deduplicate them without scanning `keys`.
+    key_indices: HashMap<String, u32>,
+    /// The index of each value by its encoding, as doubles cannot be hashed.
+    value_indices: HashMap<Vec<u8>, u32>,
+    features: Vec<Vec<u8>>,
+}
+
+impl TileLayer {
+    pub fn new(name: &str, extent: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            extent,
+            keys: Vec::new(),
+            values: Vec::new(),
+            key_indices: HashMap::new(),
+            value_indices: HashMap::new(),
+            features: Vec::new(),
+        }
+    }
+
+    pub fn extent(&self) -> u32 {
+        self.extent
+    }
+
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Adds a point at `(x, y)` in tile coordinates.
+    pub fn add_point(&mut self, x: i32, y: i32, properties: Vec<(&str, TagValue)>) {
+        let mut tags = Vec::with_capacity(properties.len() * 2);
+        for (key, value) in properties {
+            tags.push(index_of(
+                &mut self.key_indices,
+                &mut self.keys,
+                key.to_string(),
+                key.to_string(),
+            ));
+            tags.push(index_of(
+                &mut self.value_indices,
+                &mut self.values,
+                value.encode(),
+                value,
+            ));
+        }
+
+        let mut feature = Vec::new();
+        write_packed(&mut feature, 2, &tags);
+        write_uint(&mut feature, 3, GEOM_TYPE_POINT);
+        let geometry = [
+            command(COMMAND_MOVE_TO, 1),
+            zigzag(x.into()) as u32,
+            zigzag(y.into()) as u32,
+        ];
+        write_packed(&mut feature, 4, &geometry);
+        self.features.push(feature);
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_uint(&mut buf, 15, 2);
+        write_bytes(&mut buf, 1, self.name.as_bytes());
+        for feature in &self.features {
+            write_bytes(&mut buf, 2, feature);
+        }
+        for key in &self.keys {
+            write_bytes(&mut buf, 3, key.as_bytes());
+        }
+        for value in &self.values {
+            write_bytes(&mut buf, 4, &value.encode());
+        }
+        write_uint(&mut buf, 5, self.extent.into());
+        buf
+    }
+}
+
+/// Encodes a whole tile made of `layers`.
+pub fn encode_tile(layers: &[TileLayer]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for layer in layers {
+        write_bytes(&mut buf, 3, &layer.encode());
+    }
+    buf
+}
+
+/// The index of `item` in `items`, pushing it first when `key` is new.
+fn index_of<K: Hash + Eq, T>(
+    indices: &mut HashMap<K, u32>,
+    items: &mut Vec<T>,
+    key: K,
+    item: T,
+) -> u32 {
+    *indices.entry(key).or_insert_with(|| {
+        items.push(item);
+        (items.len() - 1) as u32
+    })
+}
+
+fn command(id: u32, count: u32) -> u32 {
+    (id & 0x7) | (count << 3)
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_key(buf: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(buf, u64::from((field << 3) | wire_type));
+}
+
+fn write_uint(buf: &mut Vec<u8>, field: u32, value: u64) {
+    write_key(buf, field, WIRE_VARINT);
+    write_varint(buf, value);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_key(buf, field, WIRE_LENGTH_DELIMITED);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_packed(buf: &mut Vec<u8>, field: u32, values: &[u32]) {
+    let mut packed = Vec::new();
+    for value in values {
+        write_varint(&mut packed, u64::from(*value));
+    }
+    write_bytes(buf, field, &packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_tile, write_varint, zigzag, TagValue, TileLayer};
+
+    #[test]
+    fn varints_use_seven_bits_per_byte() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 1);
+        write_varint(&mut buf, 300);
+        assert_eq!(buf, vec![0x01, 0xAC, 0x02]);
+    }
+
+    #[test]
+    fn zigzag_interleaves_negative_and_positive_values() {
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+        assert_eq!(zigzag(-2), 3);
+    }
+
+    #[test]
+    fn an_empty_tile_has_no_bytes() {
+        assert!(encode_tile(&[]).is_empty());
+    }
+
+    #[test]
+    fn a_point_layer_is_encoded_as_in_the_specification() {
+        let mut layer = TileLayer::new("lairs", 4096);
+        layer.add_point(25, 17, vec![("title", TagValue::String("a".into()))]);
+
+        let tile = encode_tile(&[layer]);
+
+        #[rustfmt::skip]
+        let expected = vec![
+            0x1A, 0x25, // layers, 37 bytes
+                0x78, 0x02, // version 2
+                0x0A, 0x05, b'l', b'a', b'i', b'r', b's', // name
+                0x12, 0x0B, // features, 11 bytes
+                    0x12, 0x02, 0x00, 0x00, // tags: key 0, value 0
+                    0x18, 0x01, // type: point
+                    0x22, 0x03, 0x09, 0x32, 0x22, // geometry: MoveTo(25, 17)
+                0x1A, 0x05, b't', b'i', b't', b'l', b'e', // keys
+                0x22, 0x03, 0x0A, 0x01, b'a', // values
+                0x28, 0x80, 0x20, // extent: 4096
+        ];
+        assert_eq!(tile, expected);
+    }
+
+    #[test]
+    fn keys_and_values_are_deduplicated() {
+        let mut layer = TileLayer::new("lairs", 4096);
+        layer.add_point(0, 0, vec![("count", TagValue::Int(1))]);
+        layer.add_point(1, 1, vec![("count", TagValue::Int(1))]);
+        layer.add_point(2, 2, vec![("count", TagValue::Int(2))]);
+
+        assert_eq!(layer.len(), 3);
+        assert_eq!(layer.keys.len(), 1);
+        assert_eq!(layer.values, vec![TagValue::Int(1), TagValue::Int(2)]);
+    }
+}
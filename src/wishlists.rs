@@ -0,0 +1,486 @@
+use crate::api_error::{violates_constraint, ApiError};
+use crate::authentication::{mac, AuthenticatedUser};
+use crate::domain::WishlistName;
+use crate::lairs_on_map::{fetch_lairs_by_id, LairFetched};
+use crate::startup::{ApplicationBaseUrl, HmacSecret};
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use hmac::Mac;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The constraint that keeps the wishlists of a user from sharing a name.
+const UNIQUE_NAME_CONSTRAINT: &str = "wishlists_name_per_user";
+/// Keeps a wishlist signature from ever passing for another kind of
+/// signature made with the same secret.
+const SIGNATURE_CONTEXT: &[u8] = b"wishlist:";
+
+#[derive(Deserialize)]
+pub struct WishlistInfo {
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct WishlistId {
+    id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct WishlistLairPath {
+    id: Uuid,
+    room_id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct SharedWishlistQuery {
+    signature: String,
+}
+
+/// A wishlist, along with the lairs on it, most recently added first.
+#[derive(Serialize)]
+pub struct Wishlist {
+    id: Uuid,
+    name: String,
+    created_at: DateTime<Utc>,
+    /// A read-only link to the wishlist anyone can open. Only shown to the
+    /// owner of the wishlist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    share_url: Option<String>,
+    lairs: Vec<LairFetched>,
+}
+
+/// The signature that lets anyone read the wishlist `wishlist_id`, as
+/// URL-safe base64 of an HMAC-SHA256 keyed with the configured `HmacSecret`.
+/// It only holds as long as the wishlist keeps its `share_nonce`.
+pub fn sign_wishlist(wishlist_id: Uuid, share_nonce: Uuid, secret: &HmacSecret) -> String {
+    URL_SAFE_NO_PAD.encode(
+        wishlist_mac(wishlist_id, share_nonce, secret)
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+/// Checks that `signature` was made by `sign_wishlist` for `wishlist_id`
+/// and its current `share_nonce`.
+pub fn verify_wishlist_signature(
+    wishlist_id: Uuid,
+    share_nonce: Uuid,
+    signature: &str,
+    secret: &HmacSecret,
+) -> Result<(), anyhow::Error> {
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .context("The wishlist signature is not valid base64.")?;
+    wishlist_mac(wishlist_id, share_nonce, secret)
+        .verify_slice(&signature)
+        .context("The wishlist signature does not match the wishlist.")
+}
+
+fn wishlist_mac(wishlist_id: Uuid, share_nonce: Uuid, secret: &HmacSecret) -> impl Mac {
+    let payload = [wishlist_id.as_bytes().as_slice(), share_nonce.as_bytes()].concat();
+    mac(secret, SIGNATURE_CONTEXT, &payload)
+}
+
+fn share_url(
+    wishlist_id: Uuid,
+    share_nonce: Uuid,
+    base_url: &ApplicationBaseUrl,
+    secret: &HmacSecret,
+) -> String {
+    format!(
+        "{}/wishlists/{}?signature={}",
+        base_url.0,
+        wishlist_id,
+        sign_wishlist(wishlist_id, share_nonce, secret)
+    )
+}
+
+//#[post("/me/wishlists")]
+#[tracing::instrument(
+    name = "Creating a wishlist",
+    skip(pool, base_url, secret, wishlist_info),
+    fields(user_id=%user)
+)]
+pub async fn create_wishlist(
+    pool: web::Data<PgPool>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    secret: web::Data<HmacSecret>,
+    user: AuthenticatedUser,
+    wishlist_info: web::Json<WishlistInfo>,
+) -> Result<HttpResponse, ApiError> {
+    let name = WishlistName::parse(wishlist_info.0.name)
+        .map_err(|message| ApiError::invalid("name", message))?;
+    let wishlist = sqlx::query!(
+        r#"
+        INSERT INTO wishlists (wishlist_id, user_id, name)
+        VALUES ($1, $2, $3)
+        RETURNING wishlist_id, name, created_at, share_nonce
+        "#,
+        Uuid::new_v4(),
+        user.user_id(),
+        name.as_ref(),
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| {
+        if violates_constraint(&e, UNIQUE_NAME_CONSTRAINT) {
+            ApiError::Conflict(format!(
+                "You already have a wishlist called {}.",
+                name.as_ref()
+            ))
+        } else {
+            anyhow::Error::new(e)
+                .context("Failed to insert a new wishlist.")
+                .into()
+        }
+    })?;
+    Ok(HttpResponse::Created().json(Wishlist {
+        id: wishlist.wishlist_id,
+        name: wishlist.name,
+        created_at: wishlist.created_at,
+        share_url: Some(share_url(
+            wishlist.wishlist_id,
+            wishlist.share_nonce,
+            &base_url,
+            &secret,
+        )),
+        lairs: Vec::new(),
+    }))
+}
+
+//#[delete("/me/wishlists/{id}")]
+#[tracing::instrument(name = "Deleting a wishlist", skip(path, pool), fields(user_id=%user))]
+pub async fn delete_wishlist(
+    path: web::Path<WishlistId>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let deleted = sqlx::query!(
+        "DELETE FROM wishlists WHERE wishlist_id = $1 AND user_id = $2",
+        path.id,
+        user.user_id(),
+    )
+    .execute(pool.get_ref())
+    .await
+    .context("Failed to delete a wishlist.")?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound("The wishlist does not exist."));
+    }
+    Ok(HttpResponse::Ok().json(json!({"status": "success"})))
+}
+
+//#[post("/me/wishlists/{id}/share-link")]
+/// Replaces the share link of a wishlist, so the links handed out so far
+/// stop working.
+#[tracing::instrument(
+    name = "Rotating the share link of a wishlist",
+    skip(path, pool, base_url, secret),
+    fields(user_id=%user)
+)]
+pub async fn rotate_share_link(
+    path: web::Path<WishlistId>,
+    pool: web::Data<PgPool>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    secret: web::Data<HmacSecret>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let share_nonce = sqlx::query_scalar!(
+        r#"
+        UPDATE wishlists SET share_nonce = gen_random_uuid()
+        WHERE wishlist_id = $1 AND user_id = $2
+        RETURNING share_nonce
+        "#,
+        path.id,
+        user.user_id(),
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .context("Failed to rotate the share link of a wishlist.")?
+    .ok_or(ApiError::NotFound("The wishlist does not exist."))?;
+    Ok(HttpResponse::Ok().json(json!({
+        "share_url": share_url(path.id, share_nonce, &base_url, &secret)
+    })))
+}
+
+//#[post("/me/wishlists/{id}/lairs/{room_id}")]
+/// Adds a lair to a wishlist of the user. Adding it twice changes nothing.
+#[tracing::instrument(name = "Adding a lair to a wishlist", skip(path, pool), fields(user_id=%user))]
+pub async fn add_to_wishlist(
+    path: web::Path<WishlistLairPath>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let added = sqlx::query!(
+        r#"
+        INSERT INTO wishlist_lairs (wishlist_id, room_id)
+        SELECT w.wishlist_id, r.room_id
+        FROM wishlists w, rooms r
+        WHERE w.wishlist_id = $1 AND w.user_id = $2 AND r.room_id = $3
+        ON CONFLICT (wishlist_id, room_id) DO NOTHING
+        RETURNING room_id
+        "#,
+        path.id,
+        user.user_id(),
+        path.room_id,
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .context("Failed to add a lair to a wishlist.")?;
+    if added.is_none() && !is_on_wishlist(path.id, user.user_id(), path.room_id, &pool).await? {
+        return Err(ApiError::NotFound(
+            "The wishlist or the lair does not exist.",
+        ));
+    }
+    Ok(HttpResponse::Ok().json(json!({"status": "success"})))
+}
+
+//#[delete("/me/wishlists/{id}/lairs/{room_id}")]
+#[tracing::instrument(
+    name = "Removing a lair from a wishlist",
+    skip(path, pool),
+    fields(user_id=%user)
+)]
+pub async fn remove_from_wishlist(
+    path: web::Path<WishlistLairPath>,
+    pool: web::Data<PgPool>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let removed = sqlx::query!(
+        r#"
+        DELETE FROM wishlist_lairs wl
+        USING wishlists w
+        WHERE wl.wishlist_id = w.wishlist_id
+            AND w.wishlist_id = $1 AND w.user_id = $2 AND wl.room_id = $3
+        "#,
+        path.id,
+        user.user_id(),
+        path.room_id,
+    )
+    .execute(pool.get_ref())
+    .await
+    .context("Failed to remove a lair from a wishlist.")?;
+    if removed.rows_affected() == 0 {
+        return Err(ApiError::NotFound(
+            "The wishlist or the lair does not exist.",
+        ));
+    }
+    Ok(HttpResponse::Ok().json(json!({"status": "success"})))
+}
+
+/// Whether the lair already is on the wishlist `wishlist_id` of `user_id`.
+async fn is_on_wishlist(
+    wishlist_id: Uuid,
+    user_id: Uuid,
+    room_id: Uuid,
+    pool: &PgPool,
+) -> Result<bool, anyhow::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM wishlist_lairs wl JOIN wishlists w USING (wishlist_id)
+            WHERE w.wishlist_id = $1 AND w.user_id = $2 AND wl.room_id = $3
+        ) AS "exists!"
+        "#,
+        wishlist_id,
+        user_id,
+        room_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to check whether a lair is on a wishlist.")
+}
+
+//#[get("/me/wishlists")]
+/// The wishlists of the user, oldest first, along with their share links.
+#[tracing::instrument(
+    name = "Listing the wishlists of a user",
+    skip(pool, base_url, secret),
+    fields(user_id=%user)
+)]
+pub async fn my_wishlists(
+    pool: web::Data<PgPool>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    secret: web::Data<HmacSecret>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let wishlists = sqlx::query!(
+        r#"
+        SELECT wishlist_id, name, created_at, share_nonce FROM wishlists
+        WHERE user_id = $1
+        ORDER BY created_at, wishlist_id
+        "#,
+        user.user_id()
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch the wishlists of a user.")?;
+    let ids: Vec<Uuid> = wishlists.iter().map(|w| w.wishlist_id).collect();
+    let mut lairs = fetch_wishlist_lairs(&ids, Some(user.user_id()), &pool).await?;
+
+    let wishlists: Vec<Wishlist> = wishlists
+        .into_iter()
+        .map(|wishlist| Wishlist {
+            id: wishlist.wishlist_id,
+            name: wishlist.name,
+            created_at: wishlist.created_at,
+            share_url: Some(share_url(
+                wishlist.wishlist_id,
+                wishlist.share_nonce,
+                &base_url,
+                &secret,
+            )),
+            lairs: lairs.remove(&wishlist.wishlist_id).unwrap_or_default(),
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(json!({ "wishlists": wishlists })))
+}
+
+//#[get("/wishlists/{id}")]
+/// A wishlist shared through the link of its owner, read-only. Requests
+/// without a valid signature get a 404, as if the wishlist did not exist.
+#[tracing::instrument(name = "Viewing a shared wishlist", skip(path, query, pool, secret))]
+pub async fn shared_wishlist(
+    path: web::Path<WishlistId>,
+    query: web::Query<SharedWishlistQuery>,
+    pool: web::Data<PgPool>,
+    secret: web::Data<HmacSecret>,
+    user: Option<AuthenticatedUser>,
+) -> Result<HttpResponse, ApiError> {
+    let wishlist_id = path.id;
+    let wishlist = sqlx::query!(
+        "SELECT name, created_at, share_nonce FROM wishlists WHERE wishlist_id = $1",
+        wishlist_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .context("Failed to fetch a wishlist.")?
+    .ok_or(ApiError::NotFound("The wishlist does not exist."))?;
+    verify_wishlist_signature(wishlist_id, wishlist.share_nonce, &query.signature, &secret)
+        .map_err(|_| ApiError::NotFound("The wishlist does not exist."))?;
+    let viewer = user.map(|user| user.user_id());
+    let mut lairs = fetch_wishlist_lairs(&[wishlist_id], viewer, &pool).await?;
+
+    Ok(HttpResponse::Ok().json(Wishlist {
+        id: wishlist_id,
+        name: wishlist.name,
+        created_at: wishlist.created_at,
+        share_url: None,
+        lairs: lairs.remove(&wishlist_id).unwrap_or_default(),
+    }))
+}
+
+/// The lairs on each of the wishlists, most recently added first.
+#[tracing::instrument(name = "Fetching the lairs of wishlists", skip(pool))]
+async fn fetch_wishlist_lairs(
+    wishlist_ids: &[Uuid],
+    viewer: Option<Uuid>,
+    pool: &PgPool,
+) -> Result<HashMap<Uuid, Vec<LairFetched>>, anyhow::Error> {
+    let entries = sqlx::query!(
+        r#"
+        SELECT wishlist_id, room_id FROM wishlist_lairs
+        WHERE wishlist_id = ANY($1)
+        ORDER BY added_at DESC, room_id
+        "#,
+        wishlist_ids
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to fetch the lairs of wishlists.")?;
+    let room_ids: Vec<Uuid> = entries.iter().map(|entry| entry.room_id).collect();
+    let lairs: HashMap<Uuid, LairFetched> = fetch_lairs_by_id(&room_ids, viewer, pool)
+        .await?
+        .into_iter()
+        .map(|lair| (lair.room_id(), lair))
+        .collect();
+
+    let mut wishlists: HashMap<Uuid, Vec<LairFetched>> = HashMap::new();
+    for entry in entries {
+        if let Some(lair) = lairs.get(&entry.room_id) {
+            wishlists
+                .entry(entry.wishlist_id)
+                .or_default()
+                .push(lair.clone());
+        }
+    }
+    Ok(wishlists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sign_wishlist, verify_wishlist_signature};
+    use crate::startup::HmacSecret;
+    use claims::{assert_err, assert_ok};
+    use secrecy::Secret;
+    use uuid::Uuid;
+
+    fn secret() -> HmacSecret {
+        HmacSecret(Secret::new("a-secret-only-used-in-tests".to_string()))
+    }
+
+    #[test]
+    fn a_signature_is_valid_for_its_wishlist() {
+        let (wishlist_id, share_nonce) = (Uuid::new_v4(), Uuid::new_v4());
+        let signature = sign_wishlist(wishlist_id, share_nonce, &secret());
+        assert_ok!(verify_wishlist_signature(
+            wishlist_id,
+            share_nonce,
+            &signature,
+            &secret()
+        ));
+    }
+
+    #[test]
+    fn a_signature_is_not_valid_for_another_wishlist() {
+        let share_nonce = Uuid::new_v4();
+        let signature = sign_wishlist(Uuid::new_v4(), share_nonce, &secret());
+        assert_err!(verify_wishlist_signature(
+            Uuid::new_v4(),
+            share_nonce,
+            &signature,
+            &secret()
+        ));
+    }
+
+    #[test]
+    fn a_signature_is_not_valid_once_the_nonce_changes() {
+        let wishlist_id = Uuid::new_v4();
+        let signature = sign_wishlist(wishlist_id, Uuid::new_v4(), &secret());
+        assert_err!(verify_wishlist_signature(
+            wishlist_id,
+            Uuid::new_v4(),
+            &signature,
+            &secret()
+        ));
+    }
+
+    #[test]
+    fn a_signature_made_with_another_secret_is_rejected() {
+        let (wishlist_id, share_nonce) = (Uuid::new_v4(), Uuid::new_v4());
+        let other_secret = HmacSecret(Secret::new("another-secret".to_string()));
+        let signature = sign_wishlist(wishlist_id, share_nonce, &other_secret);
+        assert_err!(verify_wishlist_signature(
+            wishlist_id,
+            share_nonce,
+            &signature,
+            &secret()
+        ));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for signature in ["", "not base64!", "YWJj"] {
+            assert_err!(verify_wishlist_signature(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                signature,
+                &secret()
+            ));
+        }
+    }
+}
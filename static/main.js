@@ -1,5 +1,6 @@
 const searchbar = document.getElementById("searchbar");
 const announcements = document.getElementById("announcements");
+const suggestions = document.getElementById("suggestions");
 
 const map = L.map('map').setView([51.505, -0.09], 13);
 const popup = L.popup();
@@ -65,18 +66,18 @@ async function refresh_announce(bounds) {
   let br_lat = bounds._southWest.lat;
   let br_lng = bounds._northEast.lng;
 
-  let query = `http://127.0.0.1:8000/lair?tl_lat=${tl_lat}&tl_lng=${tl_lng}&br_lat=${br_lat}&br_lng=${br_lng}`;
+  let query = `http://127.0.0.1:8000/lair?tl_lat=${tl_lat}&tl_lng=${tl_lng}&br_lat=${br_lat}&br_lng=${br_lng}&zoom=${map.getZoom()}`;
   if (searchbar.value != "") {
     let search = searchbar.value;
-    query += `&search=${search}`;
+    query += `&search=${encodeURIComponent(search)}`;
   }
 
   let results = await fetch(query, {
     method: "GET"
   });
-  let ret = await results.json();
+  let body = await results.json();
+  let ret = body.lairs || [];
 
-  console.log(ret);
   // Now that we have the results we can update the website:
   // 1. We should clear the left menu.
   // 2. We should clear all the marker on the map.
@@ -90,6 +91,20 @@ async function refresh_announce(bounds) {
     map.removeLayer(all_markers[i]);
   }
   all_markers = [];
+
+  // When zoomed out the backend sends clusters of lairs instead: clicking
+  // one zooms in on it.
+  let clusters = body.clusters || [];
+  for (var i = 0; i < clusters.length; i++) {
+    let cluster = clusters[i];
+    let icon = L.divIcon({ className: "cluster", html: `<span>${cluster.count}</span>` });
+    let marker = L.marker([cluster.lat, cluster.lon], { icon: icon }).addTo(map);
+    marker.on('click', () => map.setView([cluster.lat, cluster.lon], map.getZoom() + 2));
+    all_markers.push(marker);
+  }
+  if (clusters.length > 0) {
+    announcements.innerHTML = `<div class="announce"><span>Zoom in to see the lairs.</span></div>`;
+  }
     
   // 3.
   for (var i = 0; i < ret.length; i++) {
@@ -118,6 +133,26 @@ function update_points(e) {
 map.on('moveend', update_points);
 map.on('zoomend', update_points);
 searchbar.addEventListener('input', update_points);
+searchbar.addEventListener('input', update_suggestions);
+
+// Offers the closest lair titles while the user is typing, even misspelt.
+async function update_suggestions() {
+  let typed = searchbar.value.trim();
+  suggestions.innerHTML = "";
+  if (typed == "") {
+    return;
+  }
+
+  let results = await fetch(`http://127.0.0.1:8000/lair/suggest?q=${encodeURIComponent(typed)}`, {
+    method: "GET"
+  });
+  let ret = await results.json();
+  for (var i = 0; i < ret.length; i++) {
+    let option = document.createElement("option");
+    option.value = ret[i].title;
+    suggestions.appendChild(option);
+  }
+}
 
 
 function onMapClick(e) {
@@ -0,0 +1,247 @@
+use crate::helpers::{spawn_app, TestApp};
+use uuid::Uuid;
+
+const AROUND_TEN_TEN: &str = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20";
+
+/// The slugs of the amenities of a lair, as returned by `GET /lair/{id}`.
+async fn amenities_of(app: &TestApp, room_id: Uuid) -> Vec<String> {
+    let lair: serde_json::Value = app.get_lair(room_id).await.json().await.unwrap();
+    let mut slugs: Vec<String> = lair["amenities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|amenity| amenity["slug"].as_str().unwrap().to_string())
+        .collect();
+    slugs.sort();
+    slugs
+}
+
+/// Stores a lair around (10, 10) offering `amenities`.
+async fn store_lair_offering(app: &TestApp, title: &str, amenities: &[&str], token: &str) {
+    let room_id = app
+        .store_lair(app.test_user.user_id, title, 10.0, 10.0)
+        .await;
+    let response = app
+        .put_lair_amenities(
+            room_id,
+            &serde_json::json!({ "amenities": amenities }),
+            token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn anyone_can_list_the_catalogue_of_amenities() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_amenities().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let wifi = body["amenities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|amenity| amenity["slug"] == "wifi")
+        .expect("The catalogue does not list wifi.");
+    assert_eq!(wifi["name"], "Wi-Fi");
+    assert_eq!(wifi["category"], "Essentials");
+}
+
+#[tokio::test]
+async fn only_administrators_can_add_amenities() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let body = serde_json::json!({"slug": "hot-tub", "name": "Hot tub", "category": "Outdoors"});
+
+    // Act
+    let response = app.post_amenity(&body, &token).await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn an_administrator_can_add_and_remove_amenities() {
+    // Arrange
+    let app = spawn_app().await;
+    app.make_test_user_admin().await;
+    let token = app.test_user.login(&app).await;
+    let body = serde_json::json!({"slug": "hot-tub", "name": " Hot tub ", "category": "Outdoors"});
+
+    // Act - Part 1 - Add
+    let response = app.post_amenity(&body, &token).await;
+
+    // Assert - Part 1 - Add
+    assert_eq!(201, response.status().as_u16());
+    let amenity: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(amenity["name"], "Hot tub");
+    let response = app.post_amenity(&body, &token).await;
+    assert_eq!(409, response.status().as_u16());
+
+    // Act - Part 2 - Remove
+    let response = app
+        .delete_amenity(amenity["id"].as_str().unwrap(), &token)
+        .await;
+
+    // Assert - Part 2 - Remove
+    assert_eq!(200, response.status().as_u16());
+    let catalogue: serde_json::Value = app.get_amenities().await.json().await.unwrap();
+    assert!(!catalogue["amenities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|amenity| amenity["slug"] == "hot-tub"));
+}
+
+#[tokio::test]
+async fn an_invalid_amenity_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.make_test_user_admin().await;
+    let token = app.test_user.login(&app).await;
+    let body = serde_json::json!({"slug": "Hot Tub", "name": "", "category": "Outdoors"});
+
+    // Act
+    let response = app.post_amenity(&body, &token).await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["slug", "name"]);
+}
+
+#[tokio::test]
+async fn a_host_can_set_the_amenities_of_a_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Moated keep", 10.0, 10.0)
+        .await;
+
+    // Act
+    let response = app
+        .put_lair_amenities(
+            room_id,
+            &serde_json::json!({"amenities": ["moat", "wifi", "wifi"]}),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(amenities_of(&app, room_id).await, vec!["moat", "wifi"]);
+}
+
+#[tokio::test]
+async fn unknown_amenities_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Moated keep", 10.0, 10.0)
+        .await;
+
+    // Act
+    let response = app
+        .put_lair_amenities(
+            room_id,
+            &serde_json::json!({"amenities": ["wifi", "dragon"]}),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    assert!(amenities_of(&app, room_id).await.is_empty());
+}
+
+#[tokio::test]
+async fn removing_an_amenity_removes_it_from_lairs() {
+    // Arrange
+    let app = spawn_app().await;
+    app.make_test_user_admin().await;
+    let token = app.test_user.login(&app).await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Moated keep", 10.0, 10.0)
+        .await;
+    app.put_lair_amenities(
+        room_id,
+        &serde_json::json!({"amenities": ["moat", "wifi"]}),
+        &token,
+    )
+    .await;
+    let moat_id = sqlx::query_scalar!("SELECT amenity_id FROM amenities WHERE slug = 'moat'")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    app.delete_amenity(&moat_id.to_string(), &token).await;
+
+    // Assert
+    assert_eq!(amenities_of(&app, room_id).await, vec!["wifi"]);
+}
+
+#[tokio::test]
+async fn lairs_can_be_filtered_by_amenities() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    store_lair_offering(&app, "Cave", &[], &token).await;
+    store_lair_offering(&app, "Tower", &["wifi"], &token).await;
+    store_lair_offering(&app, "Castle", &["wifi", "parking", "moat"], &token).await;
+    store_lair_offering(&app, "Kennel", &["pet-friendly", "parking"], &token).await;
+    let test_cases = vec![
+        ("amenities=wifi", vec!["Castle", "Tower"]),
+        ("amenities=parking", vec!["Castle", "Kennel"]),
+        ("amenities=wifi,parking", vec!["Castle"]),
+        ("amenities=wifi,wifi", vec!["Castle", "Tower"]),
+        ("amenities=pet-friendly,moat", vec![]),
+    ];
+
+    for (filters, expected) in test_cases {
+        // Act
+        let response = app
+            .get_lairs_on_map(&format!("{}&{}", AROUND_TEN_TEN, filters))
+            .await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16());
+        let body: serde_json::Value = response.json().await.unwrap();
+        let mut titles: Vec<_> = body["lairs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|lair| lair["title"].as_str().unwrap().to_string())
+            .collect();
+        titles.sort();
+        assert_eq!(titles, expected, "Unexpected lairs for {}.", filters);
+    }
+}
+
+#[tokio::test]
+async fn an_invalid_amenity_filter_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map(&format!("{}&amenities=Wi-Fi", AROUND_TEN_TEN))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,487 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use chrono::{Duration, NaiveDate, Utc};
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+/// The date `days` from today.
+fn in_days(days: i64) -> NaiveDate {
+    Utc::now().date_naive() + Duration::days(days)
+}
+
+fn stay(check_in: i64, check_out: i64) -> serde_json::Value {
+    serde_json::json!({
+        "check_in": in_days(check_in),
+        "check_out": in_days(check_out),
+    })
+}
+
+/// A lair owned by the test user and a logged-in guest.
+async fn lair_and_guest(app: &TestApp) -> (Uuid, TestUser, String) {
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let guest = TestUser::generate();
+    guest.store(&app.db_pool).await;
+    let token = guest.login(app).await;
+    (room_id, guest, token)
+}
+
+/// Requests a stay and returns the id of the booking.
+async fn request_stay(
+    app: &TestApp,
+    room_id: Uuid,
+    body: serde_json::Value,
+    token: &str,
+) -> String {
+    let response = app.post_booking(room_id, &body, token).await;
+    assert_eq!(201, response.status().as_u16());
+    let booking: serde_json::Value = response.json().await.unwrap();
+    booking["id"].as_str().unwrap().to_string()
+}
+
+/// The recipients of the emails sent so far.
+async fn email_recipients(app: &TestApp) -> Vec<String> {
+    app.email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            body["To"].as_str().unwrap().to_string()
+        })
+        .collect()
+}
+
+async fn mock_email_api(app: &TestApp) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+}
+
+#[tokio::test]
+async fn a_guest_can_book_a_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, guest, token) = lair_and_guest(&app).await;
+
+    // Act
+    let response = app.post_booking(room_id, &stay(10, 13), &token).await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let booking: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(booking["lair_id"], room_id.to_string());
+    assert_eq!(booking["guest_id"], guest.user_id.to_string());
+    assert_eq!(booking["check_in"], in_days(10).to_string());
+    assert_eq!(booking["check_out"], in_days(13).to_string());
+    assert_eq!(booking["status"], "requested");
+}
+
+#[tokio::test]
+async fn stays_overlapping_an_accepted_one_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let other_guest = TestUser::generate();
+    other_guest.store(&app.db_pool).await;
+    let other_token = other_guest.login(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 15), &token).await;
+    let host_token = app.test_user.login(&app).await;
+    let accepted = app
+        .post_booking_action(&booking_id, "accept", &host_token)
+        .await;
+    assert_eq!(200, accepted.status().as_u16());
+    let test_cases = vec![
+        (stay(10, 15), "the same nights"),
+        (stay(8, 11), "the first night"),
+        (stay(14, 20), "the last night"),
+        (stay(11, 12), "nights in the middle"),
+        (stay(5, 25), "the whole stay"),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_booking(room_id, &body, &other_token).await;
+
+        // Assert
+        assert_eq!(
+            409,
+            response.status().as_u16(),
+            "The API did not fail with 409 Conflict for a stay overlapping {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn a_stay_can_start_on_the_day_the_previous_one_ends() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 15), &token).await;
+    let host_token = app.test_user.login(&app).await;
+    app.post_booking_action(&booking_id, "accept", &host_token)
+        .await;
+
+    // Act
+    let before = app.post_booking(room_id, &stay(7, 10), &token).await;
+    let after = app.post_booking(room_id, &stay(15, 17), &token).await;
+
+    // Assert
+    assert_eq!(201, before.status().as_u16());
+    assert_eq!(201, after.status().as_u16());
+}
+
+#[tokio::test]
+async fn invalid_stays_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let test_cases = vec![
+        (stay(10, 10), "no nights"),
+        (stay(10, 8), "a check-out before the check-in"),
+        (stay(-2, 3), "a check-in in the past"),
+        (stay(10, 400), "more than a year"),
+        (
+            serde_json::json!({"check_in": "tomorrow"}),
+            "malformed dates",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_booking(room_id, &body, &token).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn hosts_cannot_book_their_own_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app.post_booking(room_id, &stay(10, 13), &token).await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn booking_an_unknown_lair_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_booking(Uuid::new_v4(), &stay(10, 13), &token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn booking_requires_authentication() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/lair/{}/bookings", &app.address, room_id))
+        .json(&stay(10, 13))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn guests_and_hosts_see_their_own_bookings() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, guest, guest_token) = lair_and_guest(&app).await;
+    let other_lair = app
+        .store_lair(guest.user_id, "Guest's own lair", 20.0, 20.0)
+        .await;
+    let host_token = app.test_user.login(&app).await;
+    app.post_booking(room_id, &stay(20, 22), &guest_token).await;
+    app.post_booking(room_id, &stay(10, 12), &guest_token).await;
+    app.post_booking(other_lair, &stay(10, 12), &host_token)
+        .await;
+
+    // Act
+    let guest_bookings: serde_json::Value = app
+        .get_my_bookings(&guest_token)
+        .await
+        .json()
+        .await
+        .unwrap();
+    let host_bookings: serde_json::Value = app
+        .get_hosting_bookings(&host_token)
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    let guest_bookings = guest_bookings["bookings"].as_array().unwrap();
+    assert_eq!(guest_bookings.len(), 2);
+    assert_eq!(guest_bookings[0]["check_in"], in_days(10).to_string());
+    assert_eq!(guest_bookings[1]["check_in"], in_days(20).to_string());
+    assert_eq!(guest_bookings[0]["lair_title"], "Volcano lair");
+
+    let host_bookings = host_bookings["bookings"].as_array().unwrap();
+    assert_eq!(host_bookings.len(), 2);
+    assert!(host_bookings
+        .iter()
+        .all(|booking| booking["lair_id"] == room_id.to_string()
+            && booking["guest_name"] == guest.username.as_str()));
+}
+
+#[tokio::test]
+async fn the_host_is_emailed_about_a_new_request() {
+    // Arrange
+    let app = spawn_app().await;
+    mock_email_api(&app).await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+
+    // Act
+    request_stay(&app, room_id, stay(10, 13), &token).await;
+
+    // Assert
+    assert_eq!(
+        email_recipients(&app).await,
+        vec![app.test_user.email.clone()]
+    );
+}
+
+#[tokio::test]
+async fn a_stay_goes_from_request_to_completion_and_the_guest_hears_of_each_step() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, guest, token) = lair_and_guest(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 13), &token).await;
+    mock_email_api(&app).await;
+    let host_token = app.test_user.login(&app).await;
+    let sent_before = email_recipients(&app).await.len();
+
+    for (action, status) in [
+        ("accept", "accepted"),
+        ("check-in", "checked_in"),
+        ("complete", "completed"),
+    ] {
+        // Act
+        let response = app
+            .post_booking_action(&booking_id, action, &host_token)
+            .await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16(), "Failed to {}.", action);
+        let booking: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(booking["status"], status);
+    }
+    assert_eq!(
+        email_recipients(&app).await[sent_before..],
+        vec![guest.email.clone(); 3]
+    );
+}
+
+#[tokio::test]
+async fn the_host_is_emailed_when_the_guest_cancels() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 13), &token).await;
+    let host_token = app.test_user.login(&app).await;
+    app.post_booking_action(&booking_id, "accept", &host_token)
+        .await;
+    mock_email_api(&app).await;
+    let sent_before = email_recipients(&app).await.len();
+
+    // Act
+    let response = app.post_booking_action(&booking_id, "cancel", &token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let booking: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(booking["status"], "cancelled");
+    assert_eq!(
+        email_recipients(&app).await[sent_before..],
+        vec![app.test_user.email.clone()]
+    );
+}
+
+#[tokio::test]
+async fn a_cancelled_stay_frees_its_dates() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let host_token = app.test_user.login(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 13), &token).await;
+    app.post_booking_action(&booking_id, "accept", &host_token)
+        .await;
+    app.post_booking_action(&booking_id, "cancel", &host_token)
+        .await;
+
+    // Act
+    let response = app.post_booking(room_id, &stay(10, 13), &token).await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn guests_can_only_cancel_their_bookings() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 13), &token).await;
+
+    for action in ["accept", "decline", "check-in", "complete"] {
+        // Act
+        let response = app.post_booking_action(&booking_id, action, &token).await;
+
+        // Assert
+        assert_eq!(
+            403,
+            response.status().as_u16(),
+            "The API let the guest {} a booking.",
+            action
+        );
+    }
+}
+
+#[tokio::test]
+async fn transitions_out_of_order_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let host_token = app.test_user.login(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 13), &token).await;
+    let declined = app
+        .post_booking_action(&booking_id, "decline", &host_token)
+        .await;
+    assert_eq!(200, declined.status().as_u16());
+    let other_booking_id = request_stay(&app, room_id, stay(20, 23), &token).await;
+    let test_cases = vec![
+        (&booking_id, "accept", "accepting a declined booking"),
+        (&booking_id, "cancel", "cancelling a declined booking"),
+        (&other_booking_id, "check-in", "checking in a request"),
+        (&other_booking_id, "complete", "completing a request"),
+    ];
+
+    for (id, action, description) in test_cases {
+        // Act
+        let response = app.post_booking_action(id, action, &host_token).await;
+
+        // Assert
+        assert_eq!(
+            409,
+            response.status().as_u16(),
+            "The API did not fail with 409 Conflict when {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn overlapping_requests_cannot_both_be_accepted() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let other_guest = TestUser::generate();
+    other_guest.store(&app.db_pool).await;
+    let other_token = other_guest.login(&app).await;
+    let host_token = app.test_user.login(&app).await;
+    let first = request_stay(&app, room_id, stay(10, 15), &token).await;
+    let second = request_stay(&app, room_id, stay(12, 17), &other_token).await;
+
+    // Act
+    let accepted = app.post_booking_action(&first, "accept", &host_token).await;
+    let conflicting = app
+        .post_booking_action(&second, "accept", &host_token)
+        .await;
+
+    // Assert
+    assert_eq!(200, accepted.status().as_u16());
+    assert_eq!(409, conflicting.status().as_u16());
+}
+
+#[tokio::test]
+async fn other_users_cannot_see_or_change_a_booking() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 13), &token).await;
+    let stranger = TestUser::generate();
+    stranger.store(&app.db_pool).await;
+    let stranger_token = stranger.login(&app).await;
+
+    // Act
+    let response = app
+        .post_booking_action(&booking_id, "cancel", &stranger_token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn unknown_actions_return_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let booking_id = request_stay(&app, room_id, stay(10, 13), &token).await;
+
+    // Act
+    let response = app
+        .post_booking_action(&booking_id, "teleport", &token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_database_refuses_invalid_transitions_too() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _, token) = lair_and_guest(&app).await;
+    let booking_id: Uuid = request_stay(&app, room_id, stay(10, 13), &token)
+        .await
+        .parse()
+        .unwrap();
+
+    // Act
+    let skipped = sqlx::query!(
+        "UPDATE bookings SET status = 'completed' WHERE booking_id = $1",
+        booking_id
+    )
+    .execute(&app.db_pool)
+    .await;
+
+    // Assert
+    assert!(skipped.is_err());
+}
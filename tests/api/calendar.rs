@@ -0,0 +1,375 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use chrono::{Duration, NaiveDate, Utc};
+use uuid::Uuid;
+
+/// The date `days` from today.
+fn in_days(days: i64) -> NaiveDate {
+    Utc::now().date_naive() + Duration::days(days)
+}
+
+fn nights(from: i64, to: i64) -> serde_json::Value {
+    serde_json::json!({
+        "from": in_days(from),
+        "to": in_days(to),
+    })
+}
+
+fn stay(check_in: i64, check_out: i64) -> serde_json::Value {
+    serde_json::json!({
+        "check_in": in_days(check_in),
+        "check_out": in_days(check_out),
+    })
+}
+
+/// The query of a calendar from `from` days to `to` days from today.
+fn period(from: i64, to: i64) -> String {
+    format!("from={}&to={}", in_days(from), in_days(to))
+}
+
+/// A lair owned by the test user and the token of its host.
+async fn lair_and_host(app: &TestApp) -> (Uuid, String) {
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(app).await;
+    (room_id, token)
+}
+
+/// Has a new guest book `body` and the host accept it.
+async fn accepted_stay(app: &TestApp, room_id: Uuid, host_token: &str, body: serde_json::Value) {
+    let guest = TestUser::generate();
+    guest.store(&app.db_pool).await;
+    let token = guest.login(app).await;
+    let response = app.post_booking(room_id, &body, &token).await;
+    assert_eq!(201, response.status().as_u16());
+    let booking: serde_json::Value = response.json().await.unwrap();
+    let response = app
+        .post_booking_action(booking["id"].as_str().unwrap(), "accept", host_token)
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+/// The reason of each day of a calendar, or `None` when it is available.
+async fn reasons(app: &TestApp, room_id: Uuid, query: &str) -> Vec<Option<String>> {
+    let response = app.get_lair_calendar(room_id, query).await;
+    assert_eq!(200, response.status().as_u16());
+    let calendar: serde_json::Value = response.json().await.unwrap();
+    calendar["days"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|day| {
+            assert_eq!(day["available"], day["reason"].is_null());
+            day["reason"].as_str().map(str::to_string)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn the_calendar_lists_every_day_of_the_period() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _) = lair_and_host(&app).await;
+
+    // Act
+    let response = app.get_lair_calendar(room_id, &period(1, 8)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let calendar: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(calendar["lair_id"], room_id.to_string());
+    let days = calendar["days"].as_array().unwrap();
+    assert_eq!(days.len(), 7);
+    assert_eq!(days[0]["date"], in_days(1).to_string());
+    assert_eq!(days[6]["date"], in_days(7).to_string());
+    assert!(days.iter().all(|day| day["available"] == true));
+}
+
+#[tokio::test]
+async fn the_calendar_shows_accepted_stays_and_blocks() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, host_token) = lair_and_host(&app).await;
+    accepted_stay(&app, room_id, &host_token, stay(2, 4)).await;
+    let response = app
+        .post_lair_block(room_id, &nights(5, 6), &host_token)
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    // A stay that is only requested does not take the nights.
+    let guest = TestUser::generate();
+    guest.store(&app.db_pool).await;
+    let token = guest.login(&app).await;
+    let response = app.post_booking(room_id, &stay(6, 7), &token).await;
+    assert_eq!(201, response.status().as_u16());
+
+    // Act
+    let reasons = reasons(&app, room_id, &period(1, 8)).await;
+
+    // Assert
+    let booked = Some("booked".to_string());
+    let blocked = Some("blocked".to_string());
+    assert_eq!(
+        reasons,
+        vec![None, booked.clone(), booked, None, blocked, None, None]
+    );
+}
+
+#[tokio::test]
+async fn the_calendar_rejects_invalid_periods() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _) = lair_and_host(&app).await;
+    let test_cases = vec![
+        (period(5, 5), "an empty period"),
+        (period(5, 1), "a period ending before it starts"),
+        (period(1, 400), "a period longer than a year"),
+        ("from=2026-01-01".to_string(), "a period without an end"),
+        ("from=tomorrow&to=2026-01-01".to_string(), "an invalid date"),
+    ];
+
+    for (query, description) in test_cases {
+        // Act
+        let response = app.get_lair_calendar(room_id, &query).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn the_calendar_of_an_unknown_lair_is_not_found() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_lair_calendar(Uuid::new_v4(), &period(1, 8)).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_host_can_list_and_remove_blocks() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, host_token) = lair_and_host(&app).await;
+    let body = serde_json::json!({
+        "from": in_days(5),
+        "to": in_days(8),
+        "note": "  Repainting the walls  ",
+    });
+    let response = app.post_lair_block(room_id, &body, &host_token).await;
+    assert_eq!(201, response.status().as_u16());
+    let block: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(block["note"], "Repainting the walls");
+
+    // Act
+    let response = app.get_lair_blocks(room_id, &host_token).await;
+    assert_eq!(200, response.status().as_u16());
+    let listed: serde_json::Value = response.json().await.unwrap();
+    let removed = app
+        .delete_lair_block(room_id, block["id"].as_str().unwrap(), &host_token)
+        .await;
+
+    // Assert
+    assert_eq!(listed["blocks"].as_array().unwrap().len(), 1);
+    assert_eq!(listed["blocks"][0]["from"], in_days(5).to_string());
+    assert_eq!(listed["blocks"][0]["to"], in_days(8).to_string());
+    assert_eq!(200, removed.status().as_u16());
+    assert!(reasons(&app, room_id, &period(5, 8))
+        .await
+        .iter()
+        .all(Option::is_none));
+    let removed_again = app
+        .delete_lair_block(room_id, block["id"].as_str().unwrap(), &host_token)
+        .await;
+    assert_eq!(404, removed_again.status().as_u16());
+}
+
+#[tokio::test]
+async fn only_the_host_can_manage_blocks() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, host_token) = lair_and_host(&app).await;
+    let response = app
+        .post_lair_block(room_id, &nights(5, 8), &host_token)
+        .await;
+    let block: serde_json::Value = response.json().await.unwrap();
+    let stranger = TestUser::generate();
+    stranger.store(&app.db_pool).await;
+    let token = stranger.login(&app).await;
+
+    // Act
+    let created = app.post_lair_block(room_id, &nights(10, 12), &token).await;
+    let listed = app.get_lair_blocks(room_id, &token).await;
+    let removed = app
+        .delete_lair_block(room_id, block["id"].as_str().unwrap(), &token)
+        .await;
+
+    // Assert
+    assert_eq!(403, created.status().as_u16());
+    assert_eq!(403, listed.status().as_u16());
+    assert_eq!(403, removed.status().as_u16());
+}
+
+#[tokio::test]
+async fn blocks_cannot_overlap_accepted_stays_or_other_blocks() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, host_token) = lair_and_host(&app).await;
+    accepted_stay(&app, room_id, &host_token, stay(10, 15)).await;
+    let response = app
+        .post_lair_block(room_id, &nights(20, 25), &host_token)
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    let test_cases = vec![
+        (nights(12, 13), "an accepted stay"),
+        (nights(24, 30), "another block"),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_lair_block(room_id, &body, &host_token).await;
+
+        // Assert
+        assert_eq!(
+            409,
+            response.status().as_u16(),
+            "The API did not fail with 409 Conflict for a block overlapping {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn invalid_blocks_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, host_token) = lair_and_host(&app).await;
+    let test_cases = vec![
+        (nights(5, 5), "no night"),
+        (nights(5, 2), "an end before the start"),
+        (nights(-3, 2), "nights in the past"),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_lair_block(room_id, &body, &host_token).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for a block with {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn blocked_nights_cannot_be_booked() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, host_token) = lair_and_host(&app).await;
+    app.post_lair_block(room_id, &nights(10, 12), &host_token)
+        .await;
+    let guest = TestUser::generate();
+    guest.store(&app.db_pool).await;
+    let token = guest.login(&app).await;
+
+    // Act
+    let response = app.post_booking(room_id, &stay(11, 14), &token).await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_stay_cannot_be_accepted_once_its_nights_are_blocked() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, host_token) = lair_and_host(&app).await;
+    let guest = TestUser::generate();
+    guest.store(&app.db_pool).await;
+    let token = guest.login(&app).await;
+    let response = app.post_booking(room_id, &stay(10, 14), &token).await;
+    let booking: serde_json::Value = response.json().await.unwrap();
+    let response = app
+        .post_lair_block(room_id, &nights(13, 15), &host_token)
+        .await;
+    assert_eq!(201, response.status().as_u16());
+
+    // Act
+    let response = app
+        .post_booking_action(booking["id"].as_str().unwrap(), "accept", &host_token)
+        .await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_map_can_be_limited_to_lairs_available_for_a_stay() {
+    // Arrange
+    let app = spawn_app().await;
+    let host_token = app.test_user.login(&app).await;
+    let owner = app.test_user.user_id;
+    let booked = app.store_lair(owner, "Booked", 10.0, 10.0).await;
+    let blocked = app.store_lair(owner, "Blocked", 10.0, 11.0).await;
+    app.store_lair(owner, "Free", 10.0, 12.0).await;
+    accepted_stay(&app, booked, &host_token, stay(10, 15)).await;
+    app.post_lair_block(blocked, &nights(14, 16), &host_token)
+        .await;
+    let viewport = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20";
+
+    // Act
+    let response = app
+        .get_lairs_on_map(&format!(
+            "{}&check_in={}&check_out={}",
+            viewport,
+            in_days(12),
+            in_days(15)
+        ))
+        .await;
+    let after_stays = app
+        .get_lairs_on_map(&format!(
+            "{}&check_in={}&check_out={}",
+            viewport,
+            in_days(16),
+            in_days(18)
+        ))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let page: serde_json::Value = response.json().await.unwrap();
+    let titles: Vec<_> = page["lairs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|lair| lair["title"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(titles, vec!["Free"]);
+    let page: serde_json::Value = after_stays.json().await.unwrap();
+    assert_eq!(page["lairs"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn the_map_needs_both_dates_of_a_stay() {
+    // Arrange
+    let app = spawn_app().await;
+    let viewport = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20";
+
+    // Act
+    let response = app
+        .get_lairs_on_map(&format!("{}&check_in={}", viewport, in_days(12)))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
@@ -1,34 +1,15 @@
-use crate::helpers::{spawn_app, TestApp};
+use crate::helpers::spawn_app;
 use anyhow::Context;
 use sqlx::Executor;
 use uuid::Uuid;
 
-async fn create_subscriber(app: &TestApp) {
-    // Arrange
-    let app = spawn_app().await;
-    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com&password=password{";
-
-    // Act
-    app.post_subscriptions(body.into()).await;
-
-    // Assert
-    let saved = sqlx::query!("SELECT account_email, account_name, account_password FROM users",)
-        .fetch_one(&app.db_pool)
-        .await
-        .expect("Failed to fetch saved subscription.");
-
-    assert_eq!(saved.account_email, "ursula_le_guin@gmail.com");
-    assert_eq!(saved.account_name, "le guin");
-    assert_eq!(saved.account_password, "password{");
-}
-
 #[tokio::test]
 async fn requests_missing_authorization_are_rejected() {
     // Arrange
     let app = spawn_app().await;
 
     let response = reqwest::Client::new()
-        .post(&format!("{}/new_lair", &app.address))
+        .post(format!("{}/lair", &app.address))
         .json(&serde_json::json!({
             "title": "Newsletter title",
             "image": "Newsletter title",
@@ -43,7 +24,7 @@ async fn requests_missing_authorization_are_rejected() {
     // Assert
     assert_eq!(401, response.status().as_u16());
     assert_eq!(
-        r#"Basic realm="publish""#,
+        r#"Bearer realm="publish""#,
         response.headers()["WWW-Authenticate"]
     );
 }
@@ -54,7 +35,7 @@ async fn content_registered_in_db() {
     let app = spawn_app().await;
 
     let response = reqwest::Client::new()
-        .post(&format!("{}/new_lair", &app.address))
+        .post(format!("{}/lair", &app.address))
         .json(&serde_json::json!({
             "title": "Newsletter title",
             "image": "Newsletter title",
@@ -69,7 +50,7 @@ async fn content_registered_in_db() {
     // Assert
     assert_eq!(401, response.status().as_u16());
     assert_eq!(
-        r#"Basic realm="publish""#,
+        r#"Bearer realm="publish""#,
         response.headers()["WWW-Authenticate"]
     );
 }
@@ -95,7 +76,7 @@ async fn subscribe_persists_the_new_lair() {
     // Act
     let include = sqlx::query!(
         r#"
-    INSERT INTO rooms (id, title, image, description, lon, lat, room_id)
+    INSERT INTO rooms (account_id, title, image, description, lon, lat, room_id)
     VALUES ($1, $2, $3, $4, $5, $6, $7)
             "#,
         app.test_user.user_id,
@@ -121,7 +102,7 @@ async fn subscribe_persists_the_new_lair() {
 
     // Assert
     let saved = sqlx::query!(
-        "SELECT title, description, image, lon, lat FROM rooms WHERE id=$1",
+        "SELECT title, description, image, lon, lat FROM rooms WHERE account_id=$1",
         app.test_user.user_id
     )
     .fetch_one(&app.db_pool)
@@ -134,3 +115,150 @@ async fn subscribe_persists_the_new_lair() {
     assert_eq!(saved.lon, 1.5);
     assert_eq!(saved.lat, 1.5);
 }
+
+#[tokio::test]
+async fn requests_with_an_invalid_token_get_a_structured_401() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_lair(
+            &serde_json::json!({
+                "title": "Newsletter title",
+                "image": "Newsletter title",
+                "description": "Newsletter title",
+                "lon": 1.5,
+                "lat": 1.5,
+            }),
+            "not-a-valid-token",
+        )
+        .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        r#"Bearer realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "error");
+}
+
+#[tokio::test]
+async fn deleting_a_lair_requires_authentication() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .delete(format!("{}/lair/{}", &app.address, Uuid::new_v4()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        r#"Bearer realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+}
+
+#[tokio::test]
+async fn a_logged_in_session_can_insert_a_lair_without_a_token() {
+    // Arrange
+    let app = spawn_app().await;
+    // Logging in stores the session cookie in `api_client`.
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/lair", &app.address))
+        .json(&serde_json::json!({
+            "title": "Newsletter title",
+            "image": "Newsletter title",
+            "description": "Newsletter title",
+            "lon": 1.5,
+            "lat": 1.5,
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn an_invalid_lair_is_rejected_with_every_invalid_field() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_lair(
+            &serde_json::json!({
+                "title": "   ",
+                "image": "",
+                "description": "A perfectly fine description",
+                "lon": 1.5,
+                "lat": 1.5,
+            }),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "error");
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["title", "image"]);
+
+    let stored = sqlx::query!("SELECT COUNT(*) AS count FROM rooms")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(stored.count, Some(0));
+}
+
+#[tokio::test]
+async fn impossible_coordinates_are_rejected_and_lons_are_wrapped() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let lair = |lon: f64, lat: f64| {
+        serde_json::json!({
+            "title": "Newsletter title",
+            "image": "Newsletter title",
+            "description": "Newsletter title",
+            "lon": lon,
+            "lat": lat,
+        })
+    };
+
+    // Act
+    let impossible = app.post_lair(&lair(10.0, 9000.0), &token).await;
+    let wrapped = app.post_lair(&lair(190.0, 45.0), &token).await;
+
+    // Assert
+    assert_eq!(400, impossible.status().as_u16());
+    let body: serde_json::Value = impossible.json().await.unwrap();
+    assert_eq!(body["errors"][0]["field"], "lat");
+
+    assert_eq!(200, wrapped.status().as_u16());
+    let saved = sqlx::query!("SELECT lon, lat FROM rooms")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved lair.");
+    assert_eq!(saved.lon, -170.0);
+    assert_eq!(saved.lat, 45.0);
+}
@@ -0,0 +1,122 @@
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn map_queries_can_be_fetched_as_a_feature_collection() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 20.0)
+        .await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=15&tl_lng=15&br_lat=5&br_lng=25&format=geojson")
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        "application/geo+json",
+        response.headers()["Content-Type"].to_str().unwrap()
+    );
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["type"], "FeatureCollection");
+    assert!(body["next_cursor"].is_null());
+    let feature = &body["features"][0];
+    assert_eq!(feature["type"], "Feature");
+    assert_eq!(feature["id"], room_id.to_string());
+    assert_eq!(feature["geometry"]["type"], "Point");
+    assert_eq!(
+        feature["geometry"]["coordinates"],
+        serde_json::json!([20.0, 10.0])
+    );
+    assert_eq!(feature["properties"]["title"], "Volcano lair");
+    assert!(feature["properties"].get("lat").is_none());
+}
+
+#[tokio::test]
+async fn the_accept_header_selects_geojson() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 20.0)
+        .await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/lair/{}", &app.address, room_id))
+        .header("Accept", "application/geo+json, application/json;q=0.5")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        "application/geo+json",
+        response.headers()["Content-Type"].to_str().unwrap()
+    );
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["type"], "Feature");
+    assert_eq!(body["id"], room_id.to_string());
+    assert_eq!(
+        body["geometry"]["coordinates"],
+        serde_json::json!([20.0, 10.0])
+    );
+    assert_eq!(
+        body["properties"]["account_id"],
+        app.test_user.user_id.to_string()
+    );
+}
+
+#[tokio::test]
+async fn clusters_are_features_too() {
+    // Arrange
+    let app = spawn_app().await;
+    app.store_lair(app.test_user.user_id, "Volcano lair", 10.0, 20.0)
+        .await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=85&tl_lng=-180&br_lat=-85&br_lng=180&zoom=2&format=geojson")
+        .await;
+
+    // Assert
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["type"], "FeatureCollection");
+    assert_eq!(body["features"][0]["properties"]["count"], 1);
+}
+
+#[tokio::test]
+async fn plain_json_stays_the_default() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=15&tl_lng=15&br_lat=5&br_lng=25")
+        .await;
+
+    // Assert
+    assert_eq!(
+        "application/json",
+        response.headers()["Content-Type"].to_str().unwrap()
+    );
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["lairs"].is_array());
+}
+
+#[tokio::test]
+async fn an_unknown_format_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=15&tl_lng=15&br_lat=5&br_lng=25&format=kml")
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
@@ -1,6 +1,6 @@
 use argon2::password_hash::SaltString;
 use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
-use lairbnb_rs::configuration::{get_configuration, DatabaseSettings};
+use lairbnb_rs::configuration::{get_configuration, DatabaseSettings, Settings};
 use lairbnb_rs::startup::{get_connection_pool, Application};
 use lairbnb_rs::telemetry::{get_subscriber, init_subscriber};
 use once_cell::sync::Lazy;
@@ -22,7 +22,6 @@ static TRACING: Lazy<()> = Lazy::new(|| {
 });
 pub struct TestApp {
     pub address: String,
-    pub port: u16,
     pub db_pool: PgPool,
     pub email_server: MockServer,
     pub test_user: TestUser,
@@ -30,76 +29,577 @@ pub struct TestApp {
 }
 
 impl TestApp {
-    pub async fn post_subscriptions(&self, body: String) -> reqwest::Response {
+    pub async fn post_registration<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
         self.api_client
-            .post(&format!("{}/registration", &self.address))
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .body(body)
+            .post(format!("{}/user", &self.address))
+            .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn post_lair(&self, body: String) -> reqwest::Response {
+    pub async fn post_user_login<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
         self.api_client
-            .post(&format!("{}/new_lair", &self.address))
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .body(body)
+            .post(format!("{}/user/login", &self.address))
+            .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
+    pub async fn post_user_logout(&self, token: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/user/logout", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_lair<Body>(&self, body: &Body, token: &str) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/login", &self.address))
-            .form(body)
+            .post(format!("{}/lair", &self.address))
+            .bearer_auth(token)
+            .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn get_login_html(&self) -> String {
+    pub async fn put_lair<Body>(&self, room_id: Uuid, body: &Body, token: &str) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
         self.api_client
-            .get(&format!("{}/login", &self.address))
+            .put(format!("{}/lair/{}", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
-            .text()
+    }
+
+    pub async fn patch_lair<Body>(
+        &self,
+        room_id: Uuid,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .patch(format!("{}/lair/{}", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
             .await
-            .unwrap()
+            .expect("Failed to execute request.")
     }
 
-    pub async fn get_admin_dashboard(&self) -> reqwest::Response {
+    pub async fn get_lairs_on_map(&self, query: &str) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/dashboard", &self.address))
+            .get(format!("{}/lair?{}", &self.address, query))
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn get_admin_dashboard_html(&self) -> String {
-        self.get_admin_dashboard().await.text().await.unwrap()
+    pub async fn get_lairs_on_map_as(&self, query: &str, token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/lair?{}", &self.address, query))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
     }
 
-    pub async fn get_change_password(&self) -> reqwest::Response {
+    pub async fn get_lair_suggestions(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/lair/suggest?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lairs_nearby(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/lair/nearby?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_tile(&self, z: u32, x: u32, y: u32) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/tiles/{}/{}/{}.mvt", &self.address, z, x, y))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn upload_lair_image(
+        &self,
+        room_id: Uuid,
+        image: reqwest::multipart::Part,
+        token: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/lair/{}/images", &self.address, room_id))
+            .bearer_auth(token)
+            .multipart(reqwest::multipart::Form::new().part("image", image))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_lair_photo_form(
+        &self,
+        room_id: Uuid,
+        form: reqwest::multipart::Form,
+        token: &str,
+    ) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/password", &self.address))
+            .post(format!("{}/lair/{}/images", &self.address, room_id))
+            .bearer_auth(token)
+            .multipart(form)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
-    pub async fn get_change_password_html(&self) -> String {
-        self.get_change_password().await.text().await.unwrap()
+    pub async fn put_lair_image_order<Body>(
+        &self,
+        room_id: Uuid,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(format!("{}/lair/{}/images/order", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
     }
 
-    pub async fn post_logout(&self) -> reqwest::Response {
+    pub async fn delete_lair_image(
+        &self,
+        room_id: Uuid,
+        image_id: &str,
+        token: &str,
+    ) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/admin/logout", &self.address))
+            .delete(format!(
+                "{}/lair/{}/images/{}",
+                &self.address, room_id, image_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lair(&self, room_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/lair/{}", &self.address, room_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_booking<Body>(
+        &self,
+        room_id: Uuid,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/lair/{}/bookings", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_booking_action(
+        &self,
+        booking_id: &str,
+        action: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/bookings/{}/{}",
+                &self.address, booking_id, action
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_my_bookings(&self, token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/me/bookings", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_hosting_bookings(&self, token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/me/hosting/bookings", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lair_calendar(&self, room_id: Uuid, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/lair/{}/calendar?{}",
+                &self.address, room_id, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_lair_block<Body>(
+        &self,
+        room_id: Uuid,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/lair/{}/blocks", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lair_blocks(&self, room_id: Uuid, token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/lair/{}/blocks", &self.address, room_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_lair_block(
+        &self,
+        room_id: Uuid,
+        block_id: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .delete(format!(
+                "{}/lair/{}/blocks/{}",
+                &self.address, room_id, block_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lair_quote(&self, room_id: Uuid, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/lair/{}/quote?{}",
+                &self.address, room_id, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_lair_season<Body>(
+        &self,
+        room_id: Uuid,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/lair/{}/seasons", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lair_seasons(&self, room_id: Uuid, token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/lair/{}/seasons", &self.address, room_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_lair_season(
+        &self,
+        room_id: Uuid,
+        season_id: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .delete(format!(
+                "{}/lair/{}/seasons/{}",
+                &self.address, room_id, season_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_amenities(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/amenities", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_amenity<Body>(&self, body: &Body, token: &str) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/amenities", &self.address))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_amenity(&self, amenity_id: &str, token: &str) -> reqwest::Response {
+        self.api_client
+            .delete(format!("{}/amenities/{}", &self.address, amenity_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_lair_amenities<Body>(
+        &self,
+        room_id: Uuid,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(format!("{}/lair/{}/amenities", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_review<Body>(
+        &self,
+        room_id: Uuid,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/lair/{}/reviews", &self.address, room_id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_review_reply<Body>(
+        &self,
+        room_id: Uuid,
+        review_id: &str,
+        body: &Body,
+        token: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!(
+                "{}/lair/{}/reviews/{}/reply",
+                &self.address, room_id, review_id
+            ))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lair_reviews(&self, room_id: Uuid, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/lair/{}/reviews?{}",
+                &self.address, room_id, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_wishlist<Body>(&self, body: &Body, token: &str) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/me/wishlists", &self.address))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_my_wishlists(&self, token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/me/wishlists", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_wishlist_share_link(
+        &self,
+        wishlist_id: &str,
+        token: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/me/wishlists/{}/share-link",
+                &self.address, wishlist_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_wishlist_lair(
+        &self,
+        wishlist_id: &str,
+        room_id: Uuid,
+        token: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/me/wishlists/{}/lairs/{}",
+                &self.address, wishlist_id, room_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_wishlist_lair(
+        &self,
+        wishlist_id: &str,
+        room_id: Uuid,
+        token: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .delete(format!(
+                "{}/me/wishlists/{}/lairs/{}",
+                &self.address, wishlist_id, room_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Lets the test user manage the catalogue of amenities.
+    pub async fn make_test_user_admin(&self) {
+        sqlx::query!(
+            "UPDATE users SET is_admin = true WHERE id = $1",
+            self.test_user.user_id
+        )
+        .execute(&self.db_pool)
+        .await
+        .expect("Failed to make the test user an administrator.");
+    }
+
+    /// Stores a lair straight into the database and returns its id.
+    pub async fn store_lair(&self, owner: Uuid, title: &str, lat: f64, lon: f64) -> Uuid {
+        let room_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO rooms (room_id, account_id, title, description, image, lon, lat)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            room_id,
+            owner,
+            title,
+            format!("The description of {}", title),
+            "https://example.com/lair.png",
+            lon,
+            lat,
+        )
+        .execute(&self.db_pool)
+        .await
+        .expect("Failed to store test lair.");
+        room_id
+    }
+
+    pub async fn get_admin_dashboard(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/dashboard", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_dashboard_html(&self) -> String {
+        self.get_admin_dashboard().await.text().await.unwrap()
+    }
+
+    pub async fn get_change_password(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/password", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -110,7 +610,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/admin/password", &self.address))
+            .post(format!("{}/admin/password", &self.address))
             .form(body)
             .send()
             .await
@@ -119,6 +619,11 @@ impl TestApp {
 }
 
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Spawns the application after letting the test case adjust its settings.
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
     Lazy::force(&TRACING);
 
     // Launch a mock server to stand in for Postmark's API
@@ -133,6 +638,12 @@ pub async fn spawn_app() -> TestApp {
         c.application.port = 0;
         // Use the mock server as email API
         c.email_client.base_url = email_server.uri();
+        // Keep the uploads of each test case apart
+        c.storage.local_directory = std::env::temp_dir()
+            .join(Uuid::new_v4().to_string())
+            .to_string_lossy()
+            .into_owned();
+        configure(&mut c);
         c
     };
 
@@ -144,7 +655,7 @@ pub async fn spawn_app() -> TestApp {
         .await
         .expect("Failed to build application.");
     let application_port = application.port();
-    let _ = tokio::spawn(application.run_until_stopped());
+    drop(tokio::spawn(application.run_until_stopped()));
 
     let client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
@@ -154,7 +665,6 @@ pub async fn spawn_app() -> TestApp {
 
     let test_app = TestApp {
         address: format!("http://localhost:{}", application_port),
-        port: application_port,
         db_pool: get_connection_pool(&configuration.database),
         email_server,
         test_user: TestUser::generate(),
@@ -201,11 +711,23 @@ impl TestUser {
             user_id: Uuid::new_v4(),
             username: Uuid::new_v4().to_string(),
             password: Uuid::new_v4().to_string(),
-            email: Uuid::new_v4().to_string(),
+            email: format!("{}@example.com", Uuid::new_v4()),
         }
     }
 
-    async fn store(&self, pool: &PgPool) {
+    pub async fn login(&self, app: &TestApp) -> String {
+        let response = app
+            .post_user_login(&serde_json::json!({
+                "fullName": &self.username,
+                "password": &self.password
+            }))
+            .await;
+        assert_eq!(200, response.status().as_u16());
+        let body: serde_json::Value = response.json().await.unwrap();
+        body["cookie"].as_str().unwrap().to_owned()
+    }
+
+    pub async fn store(&self, pool: &PgPool) {
         let salt = SaltString::generate(&mut rand::thread_rng());
         // Match production parameters
         let password_hash = Argon2::new(
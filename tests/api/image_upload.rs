@@ -0,0 +1,176 @@
+use crate::helpers::{spawn_app, TestUser};
+use image::{ImageFormat, RgbImage};
+use reqwest::multipart::Part;
+use std::io::Cursor;
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    RgbImage::new(width, height)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+fn part(bytes: Vec<u8>, content_type: &str) -> Part {
+    Part::bytes(bytes)
+        .file_name("lair.png")
+        .mime_str(content_type)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn an_uploaded_image_is_served_and_becomes_the_lair_image() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let image = png(1200, 800);
+
+    // Act
+    let response = app
+        .upload_lair_image(room_id, part(image.clone(), "image/png"), &token)
+        .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let url = body["url"].as_str().unwrap();
+    let thumbnail_url = body["thumbnail_url"].as_str().unwrap();
+    assert!(url.starts_with(&format!("/uploads/lairs/{}/", room_id)));
+    assert!(url.ends_with(".png"));
+
+    let served = app
+        .api_client
+        .get(format!("{}{}", &app.address, url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, served.status().as_u16());
+    assert_eq!(served.bytes().await.unwrap().to_vec(), image);
+
+    let thumbnail = app
+        .api_client
+        .get(format!("{}{}", &app.address, thumbnail_url))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (400, 267));
+
+    let saved = sqlx::query!("SELECT image FROM rooms WHERE room_id = $1", room_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.image, url);
+}
+
+#[tokio::test]
+async fn unsupported_content_types_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .upload_lair_image(
+            room_id,
+            part(b"<svg onload='alert(1)'/>".to_vec(), "image/svg+xml"),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(415, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_file_lying_about_its_content_type_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .upload_lair_image(
+            room_id,
+            part(b"<html></html>".to_vec(), "image/png"),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["errors"][0]["field"], "image");
+}
+
+#[tokio::test]
+async fn images_over_the_size_limit_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let mut too_large = png(10, 10);
+    too_large.resize(5 * 1024 * 1024 + 1, 0);
+
+    // Act
+    let response = app
+        .upload_lair_image(room_id, part(too_large, "image/png"), &token)
+        .await;
+
+    // Assert
+    assert_eq!(413, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn only_the_owner_can_upload_images() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let intruder = TestUser::generate();
+    intruder.store(&app.db_pool).await;
+    let token = intruder.login(&app).await;
+
+    // Act
+    let response = app
+        .upload_lair_image(room_id, part(png(10, 10), "image/png"), &token)
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn uploading_requires_authentication() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/lair/{}/images", &app.address, room_id))
+        .multipart(reqwest::multipart::Form::new().part("image", part(png(10, 10), "image/png")))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,403 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use image::{ImageFormat, RgbImage};
+use reqwest::multipart::{Form, Part};
+use std::io::Cursor;
+use uuid::Uuid;
+
+fn png_part() -> Part {
+    let mut bytes = Vec::new();
+    RgbImage::new(20, 10)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    Part::bytes(bytes)
+        .file_name("lair.png")
+        .mime_str("image/png")
+        .unwrap()
+}
+
+/// Adds photos with the given captions and returns their ids, in order.
+async fn add_photos(app: &TestApp, room_id: Uuid, captions: &[&str], token: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    for caption in captions {
+        let form = Form::new()
+            .text("caption", caption.to_string())
+            .part("image", png_part());
+        let response = app.post_lair_photo_form(room_id, form, token).await;
+        assert_eq!(201, response.status().as_u16());
+        let body: serde_json::Value = response.json().await.unwrap();
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    ids
+}
+
+async fn lair_json(app: &TestApp, room_id: Uuid) -> serde_json::Value {
+    let response = app.get_lair(room_id).await;
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+fn captions_of(lair: &serde_json::Value) -> Vec<&str> {
+    lair["images"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|image| image["caption"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn photos_are_listed_in_order_with_the_first_one_as_cover() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    add_photos(
+        &app,
+        room_id,
+        &["Entrance", "Lava pool", "Shark tank"],
+        &token,
+    )
+    .await;
+
+    // Assert
+    let lair = lair_json(&app, room_id).await;
+    assert_eq!(
+        captions_of(&lair),
+        vec!["Entrance", "Lava pool", "Shark tank"]
+    );
+    let images = lair["images"].as_array().unwrap();
+    let positions: Vec<i64> = images
+        .iter()
+        .map(|image| image["position"].as_i64().unwrap())
+        .collect();
+    assert_eq!(positions, vec![0, 1, 2]);
+    assert_eq!(lair["image"], images[0]["url"]);
+    assert!(images[0]["thumbnail_url"].is_string());
+}
+
+#[tokio::test]
+async fn a_lair_without_photos_has_an_empty_images_array() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+
+    // Act
+    let lair = lair_json(&app, room_id).await;
+
+    // Assert
+    assert_eq!(lair["images"], serde_json::json!([]));
+    assert_eq!(lair["image"], "https://example.com/lair.png");
+}
+
+#[tokio::test]
+async fn a_blank_caption_is_left_out_and_an_overlong_one_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let blank = app
+        .post_lair_photo_form(
+            room_id,
+            Form::new().text("caption", "  ").part("image", png_part()),
+            &token,
+        )
+        .await;
+    let overlong = app
+        .post_lair_photo_form(
+            room_id,
+            Form::new()
+                .text("caption", "a".repeat(301))
+                .part("image", png_part()),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(201, blank.status().as_u16());
+    let body: serde_json::Value = blank.json().await.unwrap();
+    assert!(body["caption"].is_null());
+    assert_eq!(400, overlong.status().as_u16());
+}
+
+#[tokio::test]
+async fn reordering_photos_changes_the_cover() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let ids = add_photos(
+        &app,
+        room_id,
+        &["Entrance", "Lava pool", "Shark tank"],
+        &token,
+    )
+    .await;
+
+    // Act
+    let response = app
+        .put_lair_image_order(
+            room_id,
+            &serde_json::json!({"image_ids": [ids[2], ids[0], ids[1]]}),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let lair = lair_json(&app, room_id).await;
+    assert_eq!(
+        captions_of(&lair),
+        vec!["Shark tank", "Entrance", "Lava pool"]
+    );
+    assert_eq!(lair["image"], lair["images"][0]["url"]);
+    let on_map: serde_json::Value = app
+        .get_lairs_on_map("tl_lat=11&tl_lng=9&br_lat=9&br_lng=11")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(on_map["lairs"][0]["image"], lair["images"][0]["url"]);
+}
+
+#[tokio::test]
+async fn a_new_image_cannot_replace_the_cover_of_a_lair_with_photos() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    add_photos(&app, room_id, &["Entrance", "Lava pool"], &token).await;
+    let cover = lair_json(&app, room_id).await["image"].clone();
+
+    // Act
+    let response = app
+        .put_lair(
+            room_id,
+            &serde_json::json!({
+                "title": "Underwater base",
+                "image": "https://example.com/base.png",
+                "description": "Twenty thousand leagues down",
+                "lon": 10.0,
+                "lat": 10.0,
+            }),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+    let lair = lair_json(&app, room_id).await;
+    assert_eq!(lair["image"], cover);
+    assert_eq!(lair["title"], "Volcano lair");
+
+    // Keeping the cover still lets the rest of the lair change.
+    let response = app
+        .put_lair(
+            room_id,
+            &serde_json::json!({
+                "title": "Underwater base",
+                "image": cover,
+                "description": "Twenty thousand leagues down",
+                "lon": 10.0,
+                "lat": 10.0,
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(lair_json(&app, room_id).await["title"], "Underwater base");
+}
+
+#[tokio::test]
+async fn an_order_that_is_not_a_permutation_of_the_photos_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let ids = add_photos(&app, room_id, &["Entrance", "Lava pool"], &token).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"image_ids": [ids[0]]}),
+            "a missing photo",
+        ),
+        (
+            serde_json::json!({"image_ids": [ids[0], ids[0]]}),
+            "a repeated photo",
+        ),
+        (
+            serde_json::json!({"image_ids": [ids[0], ids[1], Uuid::new_v4()]}),
+            "an unknown photo",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.put_lair_image_order(room_id, &body, &token).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the order had {}.",
+            description
+        );
+    }
+    let lair = lair_json(&app, room_id).await;
+    assert_eq!(captions_of(&lair), vec!["Entrance", "Lava pool"]);
+}
+
+#[tokio::test]
+async fn deleting_a_photo_closes_the_gap_and_removes_its_files() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let ids = add_photos(
+        &app,
+        room_id,
+        &["Entrance", "Lava pool", "Shark tank"],
+        &token,
+    )
+    .await;
+    let cover_url = lair_json(&app, room_id).await["images"][0]["url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Act
+    let response = app.delete_lair_image(room_id, &ids[0], &token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let lair = lair_json(&app, room_id).await;
+    assert_eq!(captions_of(&lair), vec!["Lava pool", "Shark tank"]);
+    assert_eq!(lair["images"][0]["position"], 0);
+    assert_eq!(lair["images"][1]["position"], 1);
+    assert_eq!(lair["image"], lair["images"][0]["url"]);
+    let served = app
+        .api_client
+        .get(format!("{}{}", &app.address, cover_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(404, served.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_last_photo_cannot_be_deleted() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let ids = add_photos(&app, room_id, &["Entrance"], &token).await;
+
+    // Act
+    let response = app.delete_lair_image(room_id, &ids[0], &token).await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+    let lair = lair_json(&app, room_id).await;
+    assert_eq!(captions_of(&lair), vec!["Entrance"]);
+}
+
+#[tokio::test]
+async fn deleting_an_unknown_photo_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    add_photos(&app, room_id, &["Entrance", "Lava pool"], &token).await;
+
+    // Act
+    let response = app
+        .delete_lair_image(room_id, &Uuid::new_v4().to_string(), &token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn only_the_owner_can_reorder_or_delete_photos() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let ids = add_photos(&app, room_id, &["Entrance", "Lava pool"], &token).await;
+    let intruder = TestUser::generate();
+    intruder.store(&app.db_pool).await;
+    let intruder_token = intruder.login(&app).await;
+
+    // Act
+    let reorder = app
+        .put_lair_image_order(
+            room_id,
+            &serde_json::json!({"image_ids": [ids[1], ids[0]]}),
+            &intruder_token,
+        )
+        .await;
+    let delete = app
+        .delete_lair_image(room_id, &ids[0], &intruder_token)
+        .await;
+
+    // Assert
+    assert_eq!(403, reorder.status().as_u16());
+    assert_eq!(403, delete.status().as_u16());
+    let lair = lair_json(&app, room_id).await;
+    assert_eq!(captions_of(&lair), vec!["Entrance", "Lava pool"]);
+}
+
+#[tokio::test]
+async fn deleting_a_lair_removes_the_files_of_its_photos() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+    add_photos(&app, room_id, &["Entrance"], &token).await;
+    let lair = lair_json(&app, room_id).await;
+    let thumbnail_url = lair["images"][0]["thumbnail_url"].as_str().unwrap();
+
+    // Act
+    let response = app
+        .api_client
+        .delete(format!("{}/lair/{}", &app.address, room_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let served = app
+        .api_client
+        .get(format!("{}{}", &app.address, thumbnail_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(404, served.status().as_u16());
+}
@@ -0,0 +1,94 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn store_lairs(app: &TestApp) {
+    let owner = app.test_user.user_id;
+    app.store_lair(owner, "Volcano lair", 10.0, 20.0).await;
+    app.store_lair(owner, "Underwater base", 11.0, 21.0).await;
+    app.store_lair(owner, "Ice palace", 12.0, 22.0).await;
+}
+
+async fn suggested_titles(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app.get_lair_suggestions(query).await;
+    assert_eq!(200, response.status().as_u16());
+    let suggestions: Vec<serde_json::Value> = response.json().await.unwrap();
+    suggestions
+        .iter()
+        .map(|suggestion| suggestion["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn a_misspelt_title_is_suggested() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs(&app).await;
+
+    // Act
+    let titles = suggested_titles(&app, "q=vulcano").await;
+
+    // Assert
+    assert_eq!(titles, vec!["Volcano lair"]);
+}
+
+#[tokio::test]
+async fn the_beginning_of_a_title_is_enough() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs(&app).await;
+
+    // Act
+    let titles = suggested_titles(&app, "q=underw").await;
+
+    // Assert
+    assert_eq!(titles, vec!["Underwater base"]);
+}
+
+#[tokio::test]
+async fn suggestions_come_with_their_coordinates() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs(&app).await;
+
+    // Act
+    let response = app.get_lair_suggestions("q=ice%20palace").await;
+
+    // Assert
+    let suggestions: Vec<serde_json::Value> = response.json().await.unwrap();
+    assert_eq!(suggestions[0]["title"], "Ice palace");
+    assert_eq!(suggestions[0]["lat"], 12.0);
+    assert_eq!(suggestions[0]["lon"], 22.0);
+    assert!(suggestions[0]["id"].is_string());
+}
+
+#[tokio::test]
+async fn suggestions_are_limited() {
+    // Arrange
+    let app = spawn_app().await;
+    for i in 0..4 {
+        app.store_lair(
+            app.test_user.user_id,
+            &format!("Volcano lair {}", i),
+            10.0,
+            10.0,
+        )
+        .await;
+    }
+
+    // Act
+    let titles = suggested_titles(&app, "q=volcano&limit=2").await;
+
+    // Assert
+    assert_eq!(titles.len(), 2);
+}
+
+#[tokio::test]
+async fn an_empty_query_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_lair_suggestions("q=%20").await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
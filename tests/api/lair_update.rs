@@ -0,0 +1,173 @@
+use crate::helpers::{spawn_app, TestUser};
+use uuid::Uuid;
+
+fn replacement_body() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Underwater base",
+        "image": "https://example.com/base.png",
+        "description": "Twenty thousand leagues down",
+        "lon": 2.5,
+        "lat": 3.5,
+    })
+}
+
+#[tokio::test]
+async fn put_replaces_every_field_and_keeps_the_room_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 1.5, 1.5)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app.put_lair(room_id, &replacement_body(), &token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["id"], room_id.to_string());
+    assert_eq!(body["title"], "Underwater base");
+
+    let saved = sqlx::query!(
+        "SELECT title, description, image, lon, lat FROM rooms WHERE room_id = $1",
+        room_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch updated lair.");
+    assert_eq!(saved.title, "Underwater base");
+    assert_eq!(saved.description, "Twenty thousand leagues down");
+    assert_eq!(saved.image, "https://example.com/base.png");
+    assert_eq!(saved.lon, 2.5);
+    assert_eq!(saved.lat, 3.5);
+}
+
+#[tokio::test]
+async fn patch_only_updates_the_given_fields() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 1.5, 1.5)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .patch_lair(
+            room_id,
+            &serde_json::json!({"title": "Active volcano lair"}),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!(
+        "SELECT title, description, lon, lat FROM rooms WHERE room_id = $1",
+        room_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch updated lair.");
+    assert_eq!(saved.title, "Active volcano lair");
+    assert_eq!(saved.description, "The description of Volcano lair");
+    assert_eq!(saved.lon, 1.5);
+    assert_eq!(saved.lat, 1.5);
+}
+
+#[tokio::test]
+async fn only_the_owner_can_update_a_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 1.5, 1.5)
+        .await;
+    let intruder = TestUser::generate();
+    intruder.store(&app.db_pool).await;
+    let token = intruder.login(&app).await;
+
+    // Act
+    let put = app.put_lair(room_id, &replacement_body(), &token).await;
+    let patch = app
+        .patch_lair(room_id, &serde_json::json!({"title": "Mine now"}), &token)
+        .await;
+
+    // Assert
+    assert_eq!(403, put.status().as_u16());
+    assert_eq!(403, patch.status().as_u16());
+    let saved = sqlx::query!("SELECT title FROM rooms WHERE room_id = $1", room_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch lair.");
+    assert_eq!(saved.title, "Volcano lair");
+}
+
+#[tokio::test]
+async fn updating_an_unknown_lair_returns_a_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .put_lair(Uuid::new_v4(), &replacement_body(), &token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn looking_at_an_unknown_lair_returns_a_404() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_lair(Uuid::new_v4()).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn updates_with_invalid_fields_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 1.5, 1.5)
+        .await;
+    let token = app.test_user.login(&app).await;
+    let mut invalid_replacement = replacement_body();
+    invalid_replacement["title"] = serde_json::json!("   ");
+
+    // Act
+    let put = app.put_lair(room_id, &invalid_replacement, &token).await;
+    let patch = app
+        .patch_lair(room_id, &serde_json::json!({"description": ""}), &token)
+        .await;
+
+    // Assert
+    assert_eq!(400, put.status().as_u16());
+    assert_eq!(400, patch.status().as_u16());
+}
+
+#[tokio::test]
+async fn updating_a_lair_requires_authentication() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 1.5, 1.5)
+        .await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .put(format!("{}/lair/{}", &app.address, room_id))
+        .json(&replacement_body())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
@@ -1,43 +1,145 @@
 use crate::helpers::{assert_is_redirect_to, spawn_app};
+use argon2::PasswordHash;
+use uuid::Uuid;
 
 #[tokio::test]
-async fn an_error_flash_message_is_set_on_failure() {
+async fn a_failed_login_does_not_open_the_admin_dashboard() {
     // Arrange
     let app = spawn_app().await;
 
     // Act
-    let login_body = serde_json::json!({
-        "username": "random-username",
-        "password": "random-password"
-    });
-    let response = app.post_login(&login_body).await;
+    let response = app
+        .post_user_login(&serde_json::json!({
+            "fullName": &app.test_user.username,
+            "password": Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    // Act - Part 2 - Try the admin dashboard
+    let response = app.get_admin_dashboard().await;
 
     // Assert
     assert_is_redirect_to(&response, "/login");
-
-    // Act - Part 2 - Follow the redirect
-    let html_page = app.get_login_html().await;
-    assert!(html_page.contains("<p><i>Authentication failed</i></p>"));
-
-    // Act - Part 3 - Reload the login page
-    let html_page = app.get_login_html().await;
-    assert!(!html_page.contains("Authentication failed"));
 }
 
 #[tokio::test]
-async fn redirect_to_admin_dashboard_after_login_success() {
+async fn the_admin_dashboard_greets_the_user_after_login() {
     // Arrange
     let app = spawn_app().await;
 
     // Act
-    let login_body = serde_json::json!({
-        "username": &app.test_user.username,
-        "password": &app.test_user.password
-    });
-    let response = app.post_login(&login_body).await;
-    assert_is_redirect_to(&response, "/admin/dashboard");
-
-    // Act - Part 2 - Follow the redirect
+    let response = app
+        .post_user_login(&serde_json::json!({
+            "fullName": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    // Act - Part 2 - Open the dashboard with the session of the login
     let html_page = app.get_admin_dashboard_html().await;
+
+    // Assert
     assert!(html_page.contains(&format!("Welcome {}", app.test_user.username)));
 }
+
+#[tokio::test]
+async fn login_succeeds_with_valid_credentials() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_user_login(&serde_json::json!({
+            "fullName": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "success");
+}
+
+#[tokio::test]
+async fn login_is_rejected_with_invalid_credentials() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "fullName": &app.test_user.username,
+                "password": Uuid::new_v4().to_string()
+            }),
+            "a wrong password",
+        ),
+        (
+            serde_json::json!({
+                "fullName": Uuid::new_v4().to_string(),
+                "password": &app.test_user.password
+            }),
+            "an unknown username",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_user_login(&body).await;
+
+        // Assert
+        assert_eq!(
+            401,
+            response.status().as_u16(),
+            "The API did not return a 401 Unauthorized when logging in with {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn a_plaintext_password_is_rehashed_on_login() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let username = Uuid::new_v4().to_string();
+    let password = "legacy-password{";
+    sqlx::query!(
+        "INSERT INTO users (id, account_name, account_email, account_password)
+        VALUES ($1, $2, $3, $4)",
+        user_id,
+        username,
+        "legacy@example.com",
+        password,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to store legacy user.");
+
+    // Act
+    let response = app
+        .post_user_login(&serde_json::json!({
+            "fullName": &username,
+            "password": password
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT account_password FROM users WHERE id = $1", user_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch legacy user.");
+    assert_ne!(saved.account_password, password);
+    assert!(PasswordHash::new(&saved.account_password).is_ok());
+
+    // Act - Part 2 - Login again against the new hash
+    let response = app
+        .post_user_login(&serde_json::json!({
+            "fullName": &username,
+            "password": password
+        }))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
@@ -1,7 +1,23 @@
 mod admin_dashboard;
+mod amenities;
+mod bookings;
+mod calendar;
 mod change_password;
 mod content_insertion;
+mod geojson;
 mod health_check;
 mod helpers;
+mod image_upload;
+mod lair_photos;
+mod lair_suggestions;
+mod lair_update;
 mod login;
+mod map_search;
+mod nearby_search;
+mod pricing;
 mod registration;
+mod remote_images;
+mod reviews;
+mod tokens;
+mod vector_tiles;
+mod wishlists;
@@ -0,0 +1,527 @@
+use crate::helpers::{spawn_app, TestApp};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// Stores a lair on each side of the antimeridian and one in Greenwich.
+async fn store_lairs_around_the_globe(app: &TestApp) {
+    let owner = app.test_user.user_id;
+    app.store_lair(owner, "Fiji", -17.0, 179.0).await;
+    app.store_lair(owner, "Samoa", -14.0, -179.0).await;
+    app.store_lair(owner, "Greenwich", 51.0, 0.0).await;
+}
+
+async fn titles_in_view(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app.get_lairs_on_map(query).await;
+    assert_eq!(200, response.status().as_u16());
+    let mut titles = titles_of(response.json().await.unwrap());
+    titles.sort();
+    titles
+}
+
+/// The titles of a page of lairs, in the order they were returned.
+fn titles_of(page: serde_json::Value) -> Vec<String> {
+    page["lairs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|lair| lair["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn a_regular_viewport_only_returns_the_lairs_inside_it() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs_around_the_globe(&app).await;
+
+    // Act
+    let titles = titles_in_view(&app, "tl_lat=60&tl_lng=-10&br_lat=40&br_lng=10").await;
+
+    // Assert
+    assert_eq!(titles, vec!["Greenwich"]);
+}
+
+#[tokio::test]
+async fn a_viewport_crossing_the_antimeridian_returns_lairs_on_both_sides() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs_around_the_globe(&app).await;
+
+    // Act
+    // Leaflet keeps counting past 180 when panning east...
+    let unwrapped = titles_in_view(&app, "tl_lat=0&tl_lng=170&br_lat=-30&br_lng=190").await;
+    // ...while other clients send already wrapped longitudes.
+    let wrapped = titles_in_view(&app, "tl_lat=0&tl_lng=170&br_lat=-30&br_lng=-170").await;
+
+    // Assert
+    assert_eq!(unwrapped, vec!["Fiji", "Samoa"]);
+    assert_eq!(wrapped, vec!["Fiji", "Samoa"]);
+}
+
+#[tokio::test]
+async fn a_world_wide_viewport_returns_every_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs_around_the_globe(&app).await;
+
+    // Act
+    let titles = titles_in_view(&app, "tl_lat=85&tl_lng=-400&br_lat=-85&br_lng=400").await;
+
+    // Assert
+    assert_eq!(titles, vec!["Fiji", "Greenwich", "Samoa"]);
+}
+
+#[tokio::test]
+async fn an_upside_down_viewport_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=-30&tl_lng=170&br_lat=0&br_lng=190")
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn search_returns_every_matching_lair_case_insensitively() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner = app.test_user.user_id;
+    app.store_lair(owner, "Volcano lair", 10.0, 10.0).await;
+    app.store_lair(owner, "Dormant VOLCANO hideout", 11.0, 11.0)
+        .await;
+    let base = app.store_lair(owner, "Underwater base", 12.0, 12.0).await;
+    sqlx::query!(
+        "UPDATE rooms SET description = 'Built in the crater of a volcano' WHERE room_id = $1",
+        base
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    app.store_lair(owner, "Ice palace", 13.0, 13.0).await;
+    // Outside of the viewport.
+    app.store_lair(owner, "Far away volcano", -50.0, 100.0)
+        .await;
+
+    // Act
+    let titles = titles_in_view(&app, "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&search=volcano").await;
+
+    // Assert
+    assert_eq!(
+        titles,
+        vec!["Dormant VOLCANO hideout", "Underwater base", "Volcano lair"]
+    );
+}
+
+#[tokio::test]
+async fn search_ranks_title_matches_before_description_matches() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner = app.test_user.user_id;
+    let base = app.store_lair(owner, "Underwater base", 12.0, 12.0).await;
+    sqlx::query!(
+        "UPDATE rooms SET description = 'Built in the crater of a volcano' WHERE room_id = $1",
+        base
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    app.store_lair(owner, "Volcano lair", 10.0, 10.0).await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&search=volcano")
+        .await;
+
+    // Assert
+    let titles = titles_of(response.json().await.unwrap());
+    assert_eq!(titles, vec!["Volcano lair", "Underwater base"]);
+}
+
+#[tokio::test]
+async fn a_search_without_matches_returns_an_empty_list() {
+    // Arrange
+    let app = spawn_app().await;
+    app.store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+
+    // Act
+    let titles = titles_in_view(
+        &app,
+        "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&search=100%25_percent",
+    )
+    .await;
+
+    // Assert
+    assert!(titles.is_empty());
+}
+
+#[tokio::test]
+async fn following_the_cursor_walks_through_every_lair_once() {
+    // Arrange
+    let app = spawn_app().await;
+    for i in 0..5 {
+        app.store_lair(app.test_user.user_id, &format!("Lair {}", i), 10.0, 10.0)
+            .await;
+    }
+    let viewport = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&limit=2";
+
+    // Act
+    let mut titles = vec![];
+    let mut pages = 0;
+    let mut query = viewport.to_string();
+    loop {
+        let page: serde_json::Value = app.get_lairs_on_map(&query).await.json().await.unwrap();
+        pages += 1;
+        titles.extend(titles_of(page.clone()));
+        match page["next_cursor"].as_str() {
+            Some(cursor) => query = format!("{}&after={}", viewport, cursor),
+            None => break,
+        }
+    }
+
+    // Assert
+    assert_eq!(pages, 3);
+    titles.sort();
+    assert_eq!(
+        titles,
+        vec!["Lair 0", "Lair 1", "Lair 2", "Lair 3", "Lair 4"]
+    );
+}
+
+#[tokio::test]
+async fn lairs_can_be_sorted_by_title_newest_or_distance() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner = app.test_user.user_id;
+    let far = app.store_lair(owner, "Alpha", 18.0, 18.0).await;
+    let close = app.store_lair(owner, "Charlie", 10.0, 10.0).await;
+    let middle = app.store_lair(owner, "Bravo", 13.0, 13.0).await;
+    for (room_id, days_ago) in [(far, 1), (close, 3), (middle, 2)] {
+        sqlx::query!(
+            "UPDATE rooms SET created_at = now() - make_interval(days => $2) WHERE room_id = $1",
+            room_id,
+            days_ago
+        )
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    }
+    let viewport = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20";
+
+    // Act
+    let by_title = app
+        .get_lairs_on_map(&format!("{}&sort=title", viewport))
+        .await;
+    let newest = app
+        .get_lairs_on_map(&format!("{}&sort=newest", viewport))
+        .await;
+    let closest = app
+        .get_lairs_on_map(&format!("{}&sort=distance", viewport))
+        .await;
+
+    // Assert
+    assert_eq!(
+        titles_of(by_title.json().await.unwrap()),
+        vec!["Alpha", "Bravo", "Charlie"]
+    );
+    assert_eq!(
+        titles_of(newest.json().await.unwrap()),
+        vec!["Alpha", "Bravo", "Charlie"]
+    );
+    assert_eq!(
+        titles_of(closest.json().await.unwrap()),
+        vec!["Charlie", "Bravo", "Alpha"]
+    );
+}
+
+#[tokio::test]
+async fn a_sorted_page_continues_where_the_previous_one_stopped() {
+    // Arrange
+    let app = spawn_app().await;
+    for title in ["Delta", "Alpha", "Charlie", "Bravo"] {
+        app.store_lair(app.test_user.user_id, title, 10.0, 10.0)
+            .await;
+    }
+    let viewport = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&sort=title&limit=2";
+
+    // Act
+    let first: serde_json::Value = app.get_lairs_on_map(viewport).await.json().await.unwrap();
+    let cursor = first["next_cursor"].as_str().unwrap().to_string();
+    let second: serde_json::Value = app
+        .get_lairs_on_map(&format!("{}&after={}", viewport, cursor))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(titles_of(first), vec!["Alpha", "Bravo"]);
+    assert_eq!(titles_of(second.clone()), vec!["Charlie", "Delta"]);
+    assert!(second["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn an_unknown_cursor_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map(&format!(
+            "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&after={}",
+            Uuid::new_v4()
+        ))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_page_size_is_capped() {
+    // Arrange
+    let app = spawn_app().await;
+    sqlx::query!(
+        r#"
+        INSERT INTO rooms (room_id, account_id, title, description, image, lon, lat)
+        SELECT gen_random_uuid(), $1, 'Lair ' || i, 'A lair', 'https://example.com/lair.png', 10, 10
+        FROM generate_series(1, 201) AS i
+        "#,
+        app.test_user.user_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let page: serde_json::Value = app
+        .get_lairs_on_map("tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&limit=100000")
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(page["lairs"].as_array().unwrap().len(), 200);
+    assert!(page["next_cursor"].is_string());
+}
+
+#[tokio::test]
+async fn sorting_by_relevance_requires_a_search() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=20&tl_lng=0&br_lat=0&br_lng=20&sort=relevance")
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn zoomed_out_maps_receive_clusters_of_lairs() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner = app.test_user.user_id;
+    app.store_lair(owner, "Volcano lair", 10.0, 10.0).await;
+    app.store_lair(owner, "Underwater base", 11.0, 11.0).await;
+    app.store_lair(owner, "Ice palace", 12.0, 12.0).await;
+    store_lairs_around_the_globe(&app).await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=85&tl_lng=-180&br_lat=-85&br_lng=180&zoom=2")
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let clusters = body["clusters"].as_array().unwrap();
+    // With 4 cells per tile, cells are 22.5° wide at zoom 2: the three
+    // lairs around (11, 11) share one, Fiji, Samoa and Greenwich get their
+    // own.
+    assert_eq!(clusters.len(), 4);
+    assert_eq!(clusters[0]["count"], 3);
+    assert_eq!(clusters[0]["lat"], 11.0);
+    assert_eq!(clusters[0]["lon"], 11.0);
+    let total: i64 = clusters.iter().map(|c| c["count"].as_i64().unwrap()).sum();
+    assert_eq!(total, 6);
+}
+
+#[tokio::test]
+async fn zoomed_in_maps_receive_individual_lairs() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs_around_the_globe(&app).await;
+
+    // Act
+    let titles = titles_in_view(&app, "tl_lat=60&tl_lng=-10&br_lat=40&br_lng=10&zoom=10").await;
+
+    // Assert
+    assert_eq!(titles, vec!["Greenwich"]);
+}
+
+#[tokio::test]
+async fn an_impossible_zoom_level_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=60&tl_lng=-10&br_lat=40&br_lng=10&zoom=23")
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+/// Stores a lair around (10, 10) that sleeps `max_guests` guests in
+/// `bedrooms` bedrooms, for `price` a night if any.
+async fn store_lair_with(
+    app: &TestApp,
+    title: &str,
+    price: Option<(&str, &str)>,
+    max_guests: i32,
+    bedrooms: i32,
+) {
+    let room_id = app
+        .store_lair(app.test_user.user_id, title, 10.0, 10.0)
+        .await;
+    let (nightly_price, currency) = match price {
+        Some((amount, currency)) => (
+            Some(amount.parse::<Decimal>().unwrap()),
+            Some(currency.to_string()),
+        ),
+        None => (None, None),
+    };
+    sqlx::query!(
+        r#"
+        UPDATE rooms
+        SET nightly_price = $1, currency = $2, max_guests = $3, bedrooms = $4
+        WHERE room_id = $5
+        "#,
+        nightly_price,
+        currency,
+        max_guests,
+        bedrooms,
+        room_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to price the lair.");
+}
+
+/// Stores lairs of several prices and sizes around (10, 10).
+async fn store_lairs_of_every_size(app: &TestApp) {
+    store_lair_with(app, "Cave", Some(("40.00", "EUR")), 1, 0).await;
+    store_lair_with(app, "Tower", Some(("120.00", "EUR")), 4, 2).await;
+    store_lair_with(app, "Castle", Some(("300.00", "EUR")), 12, 6).await;
+    store_lair_with(app, "Bunker", Some(("120.00", "USD")), 6, 3).await;
+    store_lair_with(app, "Hut", None, 2, 1).await;
+}
+
+const AROUND_TEN_TEN: &str = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20";
+
+#[tokio::test]
+async fn lairs_can_be_filtered_by_nightly_price() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs_of_every_size(&app).await;
+    let test_cases = vec![
+        ("min_price=100&currency=EUR", vec!["Castle", "Tower"]),
+        ("max_price=120&currency=EUR", vec!["Cave", "Tower"]),
+        ("min_price=100&max_price=200&currency=USD", vec!["Bunker"]),
+        ("min_price=100&max_price=200&currency=eur", vec!["Tower"]),
+        ("currency=USD", vec!["Bunker"]),
+    ];
+
+    for (filters, expected) in test_cases {
+        // Act
+        let titles = titles_in_view(&app, &format!("{}&{}", AROUND_TEN_TEN, filters)).await;
+
+        // Assert
+        assert_eq!(titles, expected, "Unexpected lairs for {}.", filters);
+    }
+}
+
+#[tokio::test]
+async fn lairs_can_be_filtered_by_capacity() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs_of_every_size(&app).await;
+    let test_cases = vec![
+        ("guests=2", vec!["Bunker", "Castle", "Hut", "Tower"]),
+        ("guests=5", vec!["Bunker", "Castle"]),
+        ("bedrooms=2", vec!["Bunker", "Castle", "Tower"]),
+        (
+            "guests=4&bedrooms=3&max_price=150&currency=USD",
+            vec!["Bunker"],
+        ),
+    ];
+
+    for (filters, expected) in test_cases {
+        // Act
+        let titles = titles_in_view(&app, &format!("{}&{}", AROUND_TEN_TEN, filters)).await;
+
+        // Assert
+        assert_eq!(titles, expected, "Unexpected lairs for {}.", filters);
+    }
+}
+
+#[tokio::test]
+async fn filters_also_apply_to_clusters() {
+    // Arrange
+    let app = spawn_app().await;
+    store_lairs_of_every_size(&app).await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map(&format!("{}&zoom=2&guests=5", AROUND_TEN_TEN))
+        .await;
+
+    // Assert
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["clusters"][0]["count"], 2);
+}
+
+#[tokio::test]
+async fn invalid_filters_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        ("min_price=-5&currency=EUR", "a negative price"),
+        (
+            "min_price=200&max_price=100&currency=EUR",
+            "a minimum price above the maximum",
+        ),
+        (
+            "max_price=cheap&currency=EUR",
+            "a price that is not a number",
+        ),
+        ("max_price=100", "a price without a currency"),
+        ("currency=EURO", "an invalid currency"),
+        ("guests=0", "no guest"),
+        ("bedrooms=-1", "a negative number of bedrooms"),
+    ];
+
+    for (filters, description) in test_cases {
+        // Act
+        let response = app
+            .get_lairs_on_map(&format!("{}&{}", AROUND_TEN_TEN, filters))
+            .await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for {}.",
+            description
+        );
+    }
+}
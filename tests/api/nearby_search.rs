@@ -0,0 +1,71 @@
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn lairs_within_the_radius_are_returned_closest_first() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner = app.test_user.user_id;
+    app.store_lair(owner, "Louvre", 48.8606, 2.3376).await;
+    app.store_lair(owner, "Notre-Dame", 48.8530, 2.3499).await;
+    app.store_lair(owner, "Versailles", 48.8049, 2.1204).await;
+
+    // Act
+    let response = app
+        .get_lairs_nearby("lat=48.8530&lng=2.3499&radius_m=2000")
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let lairs = body["lairs"].as_array().unwrap();
+    assert_eq!(lairs.len(), 2);
+    assert_eq!(lairs[0]["title"], "Notre-Dame");
+    assert!(lairs[0]["distance_m"].as_f64().unwrap() < 1.0);
+    assert_eq!(lairs[1]["title"], "Louvre");
+    // About 1.2km between the two, as the crow flies.
+    let distance = lairs[1]["distance_m"].as_f64().unwrap();
+    assert!((1100.0..1300.0).contains(&distance), "{}", distance);
+}
+
+#[tokio::test]
+async fn the_radius_reaches_across_the_antimeridian() {
+    // Arrange
+    let app = spawn_app().await;
+    app.store_lair(app.test_user.user_id, "Taveuni", -16.8, -179.99)
+        .await;
+
+    // Act
+    let response = app
+        .get_lairs_nearby("lat=-16.8&lng=179.99&radius_m=5000")
+        .await;
+
+    // Assert
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["lairs"][0]["title"], "Taveuni");
+}
+
+#[tokio::test]
+async fn invalid_points_and_radiuses_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        ("lat=91&lng=0&radius_m=1000", "an impossible latitude"),
+        ("lat=0&lng=0&radius_m=0", "an empty radius"),
+        ("lat=0&lng=0&radius_m=-5", "a negative radius"),
+        ("lat=0&lng=0&radius_m=10000000", "a radius over the cap"),
+        ("lat=0&lng=0", "a missing radius"),
+    ];
+
+    for (query, error_message) in test_cases {
+        // Act
+        let response = app.get_lairs_nearby(query).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the query had {}.",
+            error_message
+        );
+    }
+}
@@ -0,0 +1,506 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// The first Monday at least a week from today, so that stays starting on it
+/// are never in the past.
+fn next_monday() -> NaiveDate {
+    let in_a_week = Utc::now().date_naive() + Duration::days(7);
+    in_a_week + Duration::days((7 - i64::from(in_a_week.weekday().num_days_from_monday())) % 7)
+}
+
+/// `days` after `next_monday`.
+fn monday_plus(days: i64) -> NaiveDate {
+    next_monday() + Duration::days(days)
+}
+
+fn stay_query(check_in: i64, check_out: i64) -> String {
+    format!(
+        "check_in={}&check_out={}",
+        monday_plus(check_in),
+        monday_plus(check_out)
+    )
+}
+
+fn priced_lair_body() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Volcano lair",
+        "image": "https://example.com/lair.png",
+        "description": "Hot all year round",
+        "lon": 10.0,
+        "lat": 10.0,
+        "max_guests": 4,
+        "bedrooms": 2,
+        "nightly_price": "120.00",
+        "weekend_price": "150.50",
+        "cleaning_fee": "35",
+        "currency": "eur",
+        "min_nights": 2,
+        "max_nights": 14,
+    })
+}
+
+/// Creates a priced lair for the test user and returns its id and the token
+/// of its host.
+async fn priced_lair(app: &TestApp) -> (Uuid, String) {
+    let token = app.test_user.login(app).await;
+    let response = app.post_lair(&priced_lair_body(), &token).await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let room_id = body["id"].as_str().unwrap().parse().unwrap();
+    (room_id, token)
+}
+
+#[tokio::test]
+async fn a_lair_can_be_created_with_prices() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let (room_id, _) = priced_lair(&app).await;
+
+    // Assert
+    let lair: serde_json::Value = app.get_lair(room_id).await.json().await.unwrap();
+    assert_eq!(lair["nightly_price"], "120.00");
+    assert_eq!(lair["weekend_price"], "150.50");
+    assert_eq!(lair["cleaning_fee"], "35.00");
+    assert_eq!(lair["currency"], "EUR");
+    assert_eq!(lair["min_nights"], 2);
+    assert_eq!(lair["max_nights"], 14);
+    assert_eq!(lair["max_guests"], 4);
+    assert_eq!(lair["bedrooms"], 2);
+}
+
+#[tokio::test]
+async fn a_lair_without_prices_can_still_be_created() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let mut body = priced_lair_body();
+    for field in [
+        "nightly_price",
+        "weekend_price",
+        "cleaning_fee",
+        "currency",
+        "min_nights",
+        "max_nights",
+    ] {
+        body.as_object_mut().unwrap().remove(field);
+    }
+
+    // Act
+    let response = app.post_lair(&body, &token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let room_id = body["id"].as_str().unwrap().parse().unwrap();
+    let lair: serde_json::Value = app.get_lair(room_id).await.json().await.unwrap();
+    assert!(lair["nightly_price"].is_null());
+    assert!(lair["currency"].is_null());
+    let cleaning_fee: Decimal = lair["cleaning_fee"].as_str().unwrap().parse().unwrap();
+    assert!(cleaning_fee.is_zero());
+    assert_eq!(lair["min_nights"], 1);
+}
+
+#[tokio::test]
+async fn invalid_prices_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let test_cases = vec![
+        (serde_json::json!({"nightly_price": "-1"}), "nightly_price"),
+        (
+            serde_json::json!({"nightly_price": "99.999"}),
+            "nightly_price",
+        ),
+        (serde_json::json!({"weekend_price": "abc"}), "weekend_price"),
+        (serde_json::json!({"currency": "EURO"}), "currency"),
+        (serde_json::json!({"currency": null}), "currency"),
+        (
+            serde_json::json!({"nightly_price": null, "currency": null}),
+            "nightly_price",
+        ),
+        (serde_json::json!({"min_nights": 0}), "min_nights"),
+        (serde_json::json!({"max_nights": 1}), "max_nights"),
+    ];
+
+    for (change, field) in test_cases {
+        let mut body = priced_lair_body();
+        for (key, value) in change.as_object().unwrap() {
+            body[key] = value.clone();
+        }
+
+        // Act
+        let response = app.post_lair(&body, &token).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for {}.",
+            change
+        );
+        if let Ok(body) = response.json::<serde_json::Value>().await {
+            assert_eq!(body["errors"][0]["field"], field, "for {}", change);
+        }
+    }
+}
+
+#[tokio::test]
+async fn patch_keeps_the_prices_it_is_not_given() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, token) = priced_lair(&app).await;
+
+    // Act
+    let response = app
+        .patch_lair(
+            room_id,
+            &serde_json::json!({"nightly_price": "99.90"}),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let lair: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(lair["nightly_price"], "99.90");
+    assert_eq!(lair["weekend_price"], "150.50");
+    assert_eq!(lair["cleaning_fee"], "35.00");
+    assert_eq!(lair["currency"], "EUR");
+    assert_eq!(lair["min_nights"], 2);
+}
+
+#[tokio::test]
+async fn an_unpriced_lair_can_be_priced_with_a_patch() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .patch_lair(
+            room_id,
+            &serde_json::json!({"nightly_price": "80", "currency": "GBP"}),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let lair: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(lair["nightly_price"], "80.00");
+    assert_eq!(lair["currency"], "GBP");
+}
+
+#[tokio::test]
+async fn null_clears_the_optional_fields_of_a_patch() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, token) = priced_lair(&app).await;
+
+    // Act
+    let response = app
+        .patch_lair(
+            room_id,
+            &serde_json::json!({"weekend_price": null, "max_nights": null}),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let lair: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(lair["nightly_price"], "120.00");
+    assert!(lair["weekend_price"].is_null());
+    assert!(lair["max_nights"].is_null());
+    assert_eq!(lair["min_nights"], 2);
+
+    // Act
+    let response = app
+        .patch_lair(
+            room_id,
+            &serde_json::json!({
+                "nightly_price": null,
+                "cleaning_fee": null,
+                "currency": null,
+            }),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let lair: serde_json::Value = response.json().await.unwrap();
+    assert!(lair["nightly_price"].is_null());
+    assert!(lair["currency"].is_null());
+}
+
+#[tokio::test]
+async fn clearing_the_nightly_price_alone_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, token) = priced_lair(&app).await;
+
+    // Act
+    let response = app
+        .patch_lair(room_id, &serde_json::json!({"nightly_price": null}), &token)
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let lair: serde_json::Value = app.get_lair(room_id).await.json().await.unwrap();
+    assert_eq!(lair["nightly_price"], "120.00");
+}
+
+#[tokio::test]
+async fn a_quote_itemises_nights_weekends_and_fees() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _) = priced_lair(&app).await;
+
+    // Act
+    // From Thursday to Monday.
+    let response = app
+        .get_lair_quote(room_id, &format!("{}&guests=2", stay_query(3, 7)))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let quote: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(quote["nights"], 4);
+    assert_eq!(quote["guests"], 2);
+    assert_eq!(quote["currency"], "EUR");
+    assert_eq!(quote["available"], true);
+    assert_eq!(
+        quote["lines"],
+        serde_json::json!([
+            {"kind": "nights", "quantity": 2, "unit_price": "120.00", "amount": "240.00"},
+            {"kind": "weekend_nights", "quantity": 2, "unit_price": "150.50", "amount": "301.00"},
+            {"kind": "cleaning_fee", "quantity": 1, "unit_price": "35.00", "amount": "35.00"},
+        ])
+    );
+    assert_eq!(quote["total"], "576.00");
+}
+
+#[tokio::test]
+async fn seasons_override_the_regular_prices() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, token) = priced_lair(&app).await;
+    // On Thursday and Friday nights.
+    let season = serde_json::json!({
+        "from": monday_plus(3),
+        "to": monday_plus(5),
+        "nightly_price": "200",
+    });
+    let response = app.post_lair_season(room_id, &season, &token).await;
+    assert_eq!(201, response.status().as_u16());
+
+    // Act
+    // From Thursday to Monday.
+    let response = app.get_lair_quote(room_id, &stay_query(3, 7)).await;
+
+    // Assert
+    let quote: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        quote["lines"],
+        serde_json::json!([
+            {"kind": "season_nights", "quantity": 2, "unit_price": "200.00", "amount": "400.00"},
+            {"kind": "weekend_nights", "quantity": 1, "unit_price": "150.50", "amount": "150.50"},
+            {"kind": "nights", "quantity": 1, "unit_price": "120.00", "amount": "120.00"},
+            {"kind": "cleaning_fee", "quantity": 1, "unit_price": "35.00", "amount": "35.00"},
+        ])
+    );
+    assert_eq!(quote["total"], "705.50");
+}
+
+#[tokio::test]
+async fn a_quote_tells_when_the_nights_are_taken() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, token) = priced_lair(&app).await;
+    let block = serde_json::json!({"from": monday_plus(1), "to": monday_plus(2)});
+    app.post_lair_block(room_id, &block, &token).await;
+
+    // Act
+    let response = app.get_lair_quote(room_id, &stay_query(0, 3)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let quote: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(quote["available"], false);
+}
+
+#[tokio::test]
+async fn stays_outside_the_limits_cannot_be_quoted_or_booked() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _) = priced_lair(&app).await;
+    let guest = TestUser::generate();
+    guest.store(&app.db_pool).await;
+    let token = guest.login(&app).await;
+    let test_cases = vec![(0, 1, "a stay too short"), (0, 15, "a stay too long")];
+
+    for (check_in, check_out, description) in test_cases {
+        // Act
+        let quote = app
+            .get_lair_quote(room_id, &stay_query(check_in, check_out))
+            .await;
+        let booking = app
+            .post_booking(
+                room_id,
+                &serde_json::json!({
+                    "check_in": monday_plus(check_in),
+                    "check_out": monday_plus(check_out),
+                }),
+                &token,
+            )
+            .await;
+
+        // Assert
+        assert_eq!(
+            400,
+            quote.status().as_u16(),
+            "The quote did not fail with 400 Bad Request for {}.",
+            description
+        );
+        assert_eq!(
+            400,
+            booking.status().as_u16(),
+            "The booking did not fail with 400 Bad Request for {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn a_lair_without_a_price_cannot_be_quoted() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+
+    // Act
+    let response = app.get_lair_quote(room_id, &stay_query(0, 3)).await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn invalid_quotes_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, _) = priced_lair(&app).await;
+
+    // Act
+    let no_night = app.get_lair_quote(room_id, &stay_query(3, 3)).await;
+    let no_guest = app
+        .get_lair_quote(room_id, &format!("{}&guests=0", stay_query(0, 3)))
+        .await;
+    let too_many_guests = app
+        .get_lair_quote(room_id, &format!("{}&guests=5", stay_query(0, 3)))
+        .await;
+    let unknown = app.get_lair_quote(Uuid::new_v4(), &stay_query(0, 3)).await;
+
+    // Assert
+    assert_eq!(400, no_night.status().as_u16());
+    assert_eq!(400, no_guest.status().as_u16());
+    assert_eq!(400, too_many_guests.status().as_u16());
+    assert_eq!(404, unknown.status().as_u16());
+}
+
+#[tokio::test]
+async fn seasons_cannot_overlap() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, token) = priced_lair(&app).await;
+    let season = |from, to| {
+        serde_json::json!({
+            "from": monday_plus(from),
+            "to": monday_plus(to),
+            "nightly_price": "200",
+        })
+    };
+    app.post_lair_season(room_id, &season(0, 7), &token).await;
+
+    // Act
+    let overlapping = app.post_lair_season(room_id, &season(6, 10), &token).await;
+    let following = app.post_lair_season(room_id, &season(7, 10), &token).await;
+
+    // Assert
+    assert_eq!(409, overlapping.status().as_u16());
+    assert_eq!(201, following.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_host_can_list_and_remove_seasons() {
+    // Arrange
+    let app = spawn_app().await;
+    let (room_id, token) = priced_lair(&app).await;
+    let body = serde_json::json!({
+        "from": monday_plus(0),
+        "to": monday_plus(7),
+        "nightly_price": "180.25",
+    });
+    let season: serde_json::Value = app
+        .post_lair_season(room_id, &body, &token)
+        .await
+        .json()
+        .await
+        .unwrap();
+    let stranger = TestUser::generate();
+    stranger.store(&app.db_pool).await;
+    let stranger_token = stranger.login(&app).await;
+
+    // Act
+    let listed: serde_json::Value = app
+        .get_lair_seasons(room_id, &token)
+        .await
+        .json()
+        .await
+        .unwrap();
+    let removed_by_stranger = app
+        .delete_lair_season(room_id, season["id"].as_str().unwrap(), &stranger_token)
+        .await;
+    let removed = app
+        .delete_lair_season(room_id, season["id"].as_str().unwrap(), &token)
+        .await;
+
+    // Assert
+    assert_eq!(listed["seasons"][0]["nightly_price"], "180.25");
+    assert_eq!(listed["seasons"][0]["from"], monday_plus(0).to_string());
+    assert_eq!(403, removed_by_stranger.status().as_u16());
+    assert_eq!(200, removed.status().as_u16());
+    let quote: serde_json::Value = app
+        .get_lair_quote(room_id, &stay_query(0, 2))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(quote["lines"][0]["kind"], "nights");
+}
+
+#[tokio::test]
+async fn map_results_show_the_nightly_price() {
+    // Arrange
+    let app = spawn_app().await;
+    priced_lair(&app).await;
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=20&tl_lng=0&br_lat=0&br_lng=20")
+        .await;
+
+    // Assert
+    let page: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(page["lairs"][0]["nightly_price"], "120.00");
+    assert_eq!(page["lairs"][0]["currency"], "EUR");
+}
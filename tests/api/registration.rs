@@ -1,5 +1,6 @@
 use crate::helpers::spawn_app;
 use anyhow::Context;
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use sqlx::Executor;
 use uuid::Uuid;
 use wiremock::matchers::{method, path};
@@ -9,7 +10,11 @@ use wiremock::{Mock, ResponseTemplate};
 async fn subscribe_returns_a_200_for_valid_form_data() {
     // Arrange
     let app = spawn_app().await;
-    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com&password=password{";
+    let body = serde_json::json!({
+        "fullName": "le guin",
+        "email": "ursula_le_guin@gmail.com",
+        "password": "password{",
+    });
 
     Mock::given(path("/email"))
         .and(method("POST"))
@@ -18,12 +23,40 @@ async fn subscribe_returns_a_200_for_valid_form_data() {
         .await;
 
     // Act
-    let response = app.post_subscriptions(body.into()).await;
+    let response = app.post_registration(&body).await;
 
     // Assert
     assert_eq!(200, response.status().as_u16());
 }
 
+#[tokio::test]
+async fn subscribe_stores_a_hash_of_the_password() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = serde_json::json!({
+        "fullName": "le guin",
+        "email": "ursula_le_guin@gmail.com",
+        "password": "password{",
+    });
+
+    // Act
+    app.post_registration(&body).await;
+
+    // Assert
+    let saved = sqlx::query!("SELECT account_password FROM users WHERE account_name = 'le guin'",)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved subscription.");
+
+    assert_ne!(saved.account_password, "password{");
+    let password_hash = PasswordHash::new(&saved.account_password)
+        .expect("The stored password is not a PHC string.");
+    assert_eq!(password_hash.algorithm.as_str(), "argon2id");
+    assert!(Argon2::default()
+        .verify_password(b"password{", &password_hash)
+        .is_ok());
+}
+
 #[tokio::test]
 async fn subscribe_persists_the_new_subscriber() {
     // Arrange
@@ -82,23 +115,32 @@ async fn subscribe_returns_a_400_when_data_is_missing() {
     // Arrange
     let app = spawn_app().await;
     let test_cases = vec![
-        ("name=le%20guin", "missing the email and password"),
-        ("name=le%20guin&password=password", "missing the email"),
         (
-            "email=ursula_le_guin%40gmail.com",
+            serde_json::json!({"fullName": "le guin"}),
+            "missing the email and password",
+        ),
+        (
+            serde_json::json!({"fullName": "le guin", "password": "password{"}),
+            "missing the email",
+        ),
+        (
+            serde_json::json!({"email": "ursula_le_guin@gmail.com"}),
             "missing the name and password",
         ),
         (
-            "email=ursula_le_guin%40gmail.com&password=password",
+            serde_json::json!({"email": "ursula_le_guin@gmail.com", "password": "password{"}),
             "missing the name",
         ),
-        ("password=password{", "missing the email and name"),
-        ("", "missing password, name and email"),
+        (
+            serde_json::json!({"password": "password{"}),
+            "missing the email and name",
+        ),
+        (serde_json::json!({}), "missing password, name and email"),
     ];
 
     for (invalid_body, error_message) in test_cases {
         // Act
-        let response = app.post_subscriptions(invalid_body.into()).await;
+        let response = app.post_registration(&invalid_body).await;
 
         // Assert
         assert_eq!(
@@ -116,23 +158,23 @@ async fn subscribe_returns_a_400_when_fields_are_present_but_invalid() {
     // Arrange
     let app = spawn_app().await;
     let test_cases = vec![
-        ("name=le%20guin", "missing the email and password"),
-        ("name=le%20guin&password=password", "missing the email"),
         (
-            "email=ursula_le_guin%40gmail.com",
-            "missing the name and password",
+            serde_json::json!({"fullName": "", "email": "ursula_le_guin@gmail.com", "password": "password{"}),
+            "empty name",
         ),
         (
-            "email=ursula_le_guin%40gmail.com&password=password",
-            "missing the name",
+            serde_json::json!({"fullName": "le guin", "email": "definitely-not-an-email", "password": "password{"}),
+            "invalid email",
+        ),
+        (
+            serde_json::json!({"fullName": "le guin", "email": "ursula_le_guin@gmail.com", "password": "password"}),
+            "password without a special character",
         ),
-        ("password=password{", "missing the email and name"),
-        ("", "missing password, name and email"),
     ];
 
     for (body, description) in test_cases {
         // Act
-        let response = app.post_subscriptions(body.into()).await;
+        let response = app.post_registration(&body).await;
 
         // Assert
         assert_eq!(
@@ -148,7 +190,11 @@ async fn subscribe_returns_a_400_when_fields_are_present_but_invalid() {
 async fn subscribe_fails_if_there_is_a_fatal_database_error() {
     // Arrange
     let app = spawn_app().await;
-    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com&password=password{";
+    let body = serde_json::json!({
+        "fullName": "le guin",
+        "email": "ursula_le_guin@gmail.com",
+        "password": "password{",
+    });
     // Sabotage the database
     sqlx::query!("ALTER TABLE users DROP COLUMN account_email;",)
         .execute(&app.db_pool)
@@ -156,7 +202,7 @@ async fn subscribe_fails_if_there_is_a_fatal_database_error() {
         .unwrap();
 
     // Act
-    let response = app.post_subscriptions(body.into()).await;
+    let response = app.post_registration(&body).await;
 
     // Assert
     assert_eq!(response.status().as_u16(), 500);
@@ -0,0 +1,233 @@
+use crate::helpers::{spawn_app_with, TestApp};
+use image::{ImageFormat, RgbImage};
+use std::io::Cursor;
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn png() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    RgbImage::new(40, 20)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+/// An application that rehosts remote images. The mock image server runs on
+/// localhost, so private addresses have to be allowed for it to be reached.
+async fn spawn_rehosting_app() -> TestApp {
+    spawn_app_with(|c| {
+        c.remote_images.enabled = true;
+        c.remote_images.allow_private_addresses = true;
+        c.remote_images.timeout_milliseconds = 500;
+    })
+    .await
+}
+
+fn lair_with_image(image: String) -> serde_json::Value {
+    serde_json::json!({
+        "title": "Volcano lair",
+        "description": "A lair inside a volcano",
+        "image": image,
+        "lon": 10.0,
+        "lat": 10.0,
+    })
+}
+
+async fn post_lair_with_image(app: &TestApp, image: String) -> reqwest::Response {
+    let token = app.test_user.login(app).await;
+    app.post_lair(&lair_with_image(image), &token).await
+}
+
+#[tokio::test]
+async fn a_remote_image_is_rehosted_as_the_cover_photo() {
+    // Arrange
+    let app = spawn_rehosting_app().await;
+    let image_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/lair.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(png(), "image/png"))
+        .expect(1)
+        .mount(&image_server)
+        .await;
+
+    // Act
+    let response = post_lair_with_image(&app, format!("{}/lair.png", image_server.uri())).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let room_id = body["id"].as_str().unwrap().parse().unwrap();
+    let lair: serde_json::Value = app.get_lair(room_id).await.json().await.unwrap();
+    let image = lair["image"].as_str().unwrap();
+    assert!(image.starts_with(&format!("/uploads/lairs/{}/", room_id)));
+    assert_eq!(lair["images"][0]["url"], image);
+    let served = app
+        .api_client
+        .get(format!("{}{}", &app.address, image))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(served.bytes().await.unwrap().to_vec(), png());
+}
+
+#[tokio::test]
+async fn redirects_are_followed() {
+    // Arrange
+    let app = spawn_rehosting_app().await;
+    let image_server = MockServer::start().await;
+    Mock::given(path("/old.png"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/lair.png"))
+        .mount(&image_server)
+        .await;
+    Mock::given(path("/lair.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(png(), "image/png"))
+        .mount(&image_server)
+        .await;
+
+    // Act
+    let response = post_lair_with_image(&app, format!("{}/old.png", image_server.uri())).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn remote_images_that_cannot_be_rehosted_are_rejected() {
+    // Arrange
+    let app = spawn_rehosting_app().await;
+    let image_server = MockServer::start().await;
+    let mut too_large = png();
+    too_large.resize(5 * 1024 * 1024 + 1, 0);
+    Mock::given(path("/page.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("<html></html>", "text/html"))
+        .mount(&image_server)
+        .await;
+    Mock::given(path("/lying.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("<html></html>", "image/png"))
+        .mount(&image_server)
+        .await;
+    Mock::given(path("/huge.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(too_large, "image/png"))
+        .mount(&image_server)
+        .await;
+    Mock::given(path("/slow.png"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(png(), "image/png")
+                .set_delay(Duration::from_secs(2)),
+        )
+        .mount(&image_server)
+        .await;
+    Mock::given(path("/gone.png"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&image_server)
+        .await;
+    let test_cases = vec![
+        ("/page.png", "a non-image content type"),
+        ("/lying.png", "a body that is not an image"),
+        ("/huge.png", "an image over the size limit"),
+        ("/slow.png", "a server slower than the timeout"),
+        ("/gone.png", "a missing image"),
+    ];
+
+    for (image_path, description) in test_cases {
+        // Act
+        let response =
+            post_lair_with_image(&app, format!("{}{}", image_server.uri(), image_path)).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for {}.",
+            description
+        );
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["errors"][0]["field"], "image");
+    }
+    let saved = sqlx::query!("SELECT COUNT(*) AS count FROM rooms")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.count, Some(0));
+}
+
+#[tokio::test]
+async fn images_on_private_addresses_are_never_requested() {
+    // Arrange
+    let app = spawn_app_with(|c| c.remote_images.enabled = true).await;
+    let image_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(png(), "image/png"))
+        .expect(0)
+        .mount(&image_server)
+        .await;
+    let port = image_server.address().port();
+
+    for image in [
+        format!("{}/lair.png", image_server.uri()),
+        format!("http://localhost:{}/lair.png", port),
+        format!("http://[::ffff:127.0.0.1]:{}/lair.png", port),
+        "http://169.254.169.254/latest/meta-data/".to_string(),
+        "file:///etc/passwd".to_string(),
+    ] {
+        // Act
+        let response = post_lair_with_image(&app, image.clone()).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not refuse {}.",
+            image
+        );
+    }
+}
+
+#[tokio::test]
+async fn a_new_remote_image_becomes_the_cover_of_an_existing_lair() {
+    // Arrange
+    let app = spawn_rehosting_app().await;
+    let image_server = MockServer::start().await;
+    Mock::given(path("/lair.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(png(), "image/png"))
+        .expect(1)
+        .mount(&image_server)
+        .await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .patch_lair(
+            room_id,
+            &serde_json::json!({"image": format!("{}/lair.png", image_server.uri())}),
+            &token,
+        )
+        .await;
+    // Sending the rehosted image back untouched does not fetch it again.
+    let lair: serde_json::Value = response.json().await.unwrap();
+    let resent = app
+        .put_lair(
+            room_id,
+            &serde_json::json!({
+                "title": lair["title"],
+                "description": lair["description"],
+                "image": lair["image"],
+                "lon": lair["lon"],
+                "lat": lair["lat"],
+            }),
+            &token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, resent.status().as_u16());
+    let image = lair["image"].as_str().unwrap();
+    assert!(image.starts_with("/uploads/"));
+    assert_eq!(lair["images"].as_array().unwrap().len(), 1);
+    assert_eq!(lair["images"][0]["url"], image);
+}
@@ -0,0 +1,348 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+fn review(rating: i32, text: &str) -> serde_json::Value {
+    serde_json::json!({ "rating": rating, "text": text })
+}
+
+/// A new guest who requested a stay in the lair of the test user, `weeks`
+/// weeks from now so that several guests never overlap, and the id of the
+/// booking.
+async fn guest_with_a_stay(app: &TestApp, room_id: Uuid, weeks: i64) -> (TestUser, String, String) {
+    let guest = TestUser::generate();
+    guest.store(&app.db_pool).await;
+    let token = guest.login(app).await;
+    let check_in = Utc::now().date_naive() + Duration::weeks(weeks + 1);
+    let response = app
+        .post_booking(
+            room_id,
+            &serde_json::json!({
+                "check_in": check_in,
+                "check_out": check_in + Duration::days(3),
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    let booking: serde_json::Value = response.json().await.unwrap();
+    let booking_id = booking["id"].as_str().unwrap().to_string();
+    (guest, token, booking_id)
+}
+
+/// A new guest who completed a stay in the lair of the test user, and their
+/// token.
+async fn guest_with_a_completed_stay(
+    app: &TestApp,
+    room_id: Uuid,
+    weeks: i64,
+) -> (TestUser, String) {
+    let (guest, token, booking_id) = guest_with_a_stay(app, room_id, weeks).await;
+    let host_token = app.test_user.login(app).await;
+    for action in ["accept", "check-in", "complete"] {
+        let response = app
+            .post_booking_action(&booking_id, action, &host_token)
+            .await;
+        assert_eq!(200, response.status().as_u16(), "Failed to {}.", action);
+    }
+    (guest, token)
+}
+
+/// Posts a review and returns its id.
+async fn post_review(app: &TestApp, room_id: Uuid, body: serde_json::Value, token: &str) -> String {
+    let response = app.post_review(room_id, &body, token).await;
+    assert_eq!(201, response.status().as_u16());
+    let review: serde_json::Value = response.json().await.unwrap();
+    review["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn a_guest_who_completed_a_stay_can_review_the_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let (guest, token) = guest_with_a_completed_stay(&app, room_id, 0).await;
+
+    // Act
+    let response = app
+        .post_review(room_id, &review(4, " Warm, if a little smoky. "), &token)
+        .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let review: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(review["lair_id"], room_id.to_string());
+    assert_eq!(review["guest_id"], guest.user_id.to_string());
+    assert_eq!(review["guest_name"], guest.username);
+    assert_eq!(review["rating"], 4);
+    assert_eq!(review["text"], "Warm, if a little smoky.");
+    assert!(review["host_reply"].is_null());
+}
+
+#[tokio::test]
+async fn guests_without_a_completed_stay_cannot_review_the_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let (_, token, _) = guest_with_a_stay(&app, room_id, 0).await;
+
+    // Act
+    let response = app
+        .post_review(room_id, &review(5, "Can't wait!"), &token)
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_guest_can_only_review_a_lair_once() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let (_, token) = guest_with_a_completed_stay(&app, room_id, 0).await;
+    post_review(&app, room_id, review(4, "Warm."), &token).await;
+
+    // Act
+    let response = app
+        .post_review(room_id, &review(1, "Too warm."), &token)
+        .await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn invalid_reviews_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let (_, token) = guest_with_a_completed_stay(&app, room_id, 0).await;
+    let test_cases = vec![
+        (review(0, "Warm."), "no star"),
+        (review(6, "Warm."), "six stars"),
+        (review(4, "  "), "a blank text"),
+        (serde_json::json!({"rating": 4}), "a missing text"),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_review(room_id, &body, &token).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn reviewing_an_unknown_lair_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_review(Uuid::new_v4(), &review(4, "Warm."), &token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_host_can_reply_once_to_a_review() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let (_, guest_token) = guest_with_a_completed_stay(&app, room_id, 0).await;
+    let review_id = post_review(&app, room_id, review(3, "Smoky."), &guest_token).await;
+    let host_token = app.test_user.login(&app).await;
+    let reply = serde_json::json!({"text": "The volcano is active, as advertised."});
+
+    // Act - Part 1 - Reply
+    let response = app
+        .post_review_reply(room_id, &review_id, &reply, &host_token)
+        .await;
+
+    // Assert - Part 1 - Reply
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["host_reply"], "The volcano is active, as advertised.");
+    assert!(!body["host_replied_at"].is_null());
+
+    // Act - Part 2 - Reply again
+    let response = app
+        .post_review_reply(room_id, &review_id, &reply, &host_token)
+        .await;
+
+    // Assert - Part 2 - Reply again
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn only_the_host_can_reply_to_a_review() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let (_, guest_token) = guest_with_a_completed_stay(&app, room_id, 0).await;
+    let review_id = post_review(&app, room_id, review(3, "Smoky."), &guest_token).await;
+
+    // Act
+    let response = app
+        .post_review_reply(
+            room_id,
+            &review_id,
+            &serde_json::json!({"text": "I agree with myself."}),
+            &guest_token,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn ratings_are_aggregated_on_the_map() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    app.store_lair(app.test_user.user_id, "Unknown lair", 10.0, 10.0)
+        .await;
+    for (weeks, rating) in [(0, 4), (1, 5)] {
+        let (_, token) = guest_with_a_completed_stay(&app, room_id, weeks).await;
+        post_review(&app, room_id, review(rating, "Warm."), &token).await;
+    }
+
+    // Act
+    let response = app
+        .get_lairs_on_map("tl_lat=20&tl_lng=0&br_lat=0&br_lng=20")
+        .await;
+
+    // Assert
+    let body: serde_json::Value = response.json().await.unwrap();
+    let lairs = body["lairs"].as_array().unwrap();
+    let rated = lairs
+        .iter()
+        .find(|lair| lair["title"] == "Volcano lair")
+        .unwrap();
+    assert_eq!(rated["average_rating"], "4.50");
+    assert_eq!(rated["review_count"], 2);
+    let unrated = lairs
+        .iter()
+        .find(|lair| lair["title"] == "Unknown lair")
+        .unwrap();
+    assert!(unrated["average_rating"].is_null());
+    assert_eq!(unrated["review_count"], 0);
+}
+
+#[tokio::test]
+async fn reviews_are_listed_newest_first_one_page_at_a_time() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    for (days_ago, text) in [(3, "Oldest"), (2, "Middle"), (1, "Newest")] {
+        let guest = TestUser::generate();
+        guest.store(&app.db_pool).await;
+        sqlx::query!(
+            r#"
+            INSERT INTO reviews (review_id, room_id, guest_id, rating, body, created_at)
+            VALUES ($1, $2, $3, 5, $4, now() - make_interval(days => $5))
+            "#,
+            Uuid::new_v4(),
+            room_id,
+            guest.user_id,
+            text,
+            days_ago,
+        )
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to store a review.");
+    }
+
+    // Act
+    let first: serde_json::Value = app
+        .get_lair_reviews(room_id, "limit=2")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let second: serde_json::Value = app
+        .get_lair_reviews(room_id, &format!("limit=2&after={}", cursor))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    let texts = |page: &serde_json::Value| -> Vec<String> {
+        page["reviews"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|review| review["text"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(texts(&first), vec!["Newest", "Middle"]);
+    assert_eq!(texts(&second), vec!["Oldest"]);
+    assert!(second["next_cursor"].is_null());
+    assert_eq!(first["review_count"], 3);
+    assert_eq!(first["average_rating"], "5.00");
+}
+
+#[tokio::test]
+async fn listing_the_reviews_of_an_unknown_lair_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_lair_reviews(Uuid::new_v4(), "").await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn a_cursor_from_another_lair_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Volcano lair", 10.0, 10.0)
+        .await;
+    let other_room_id = app
+        .store_lair(app.test_user.user_id, "Glacier lair", 10.0, 10.0)
+        .await;
+    let (_, token) = guest_with_a_completed_stay(&app, other_room_id, 0).await;
+    let review_id = post_review(&app, other_room_id, review(4, "Chilly."), &token).await;
+
+    for cursor in [review_id, Uuid::new_v4().to_string()] {
+        // Act
+        let response = app
+            .get_lair_reviews(room_id, &format!("after={}", cursor))
+            .await;
+
+        // Assert
+        assert_eq!(400, response.status().as_u16());
+    }
+}
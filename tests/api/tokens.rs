@@ -0,0 +1,113 @@
+use crate::helpers::spawn_app;
+use lairbnb_rs::authentication::AuthToken;
+use lairbnb_rs::startup::HmacSecret;
+use secrecy::Secret;
+
+fn lair_body() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Volcano lair",
+        "image": "https://example.com/volcano.png",
+        "description": "A lair inside a dormant volcano",
+        "lon": 1.5,
+        "lat": 1.5,
+    })
+}
+
+#[tokio::test]
+async fn a_token_from_login_authenticates_requests() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app.post_lair(&lair_body(), &token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let saved = sqlx::query!("SELECT account_id, title FROM rooms")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved lair.");
+    assert_eq!(saved.account_id, app.test_user.user_id);
+    assert_eq!(saved.title, "Volcano lair");
+}
+
+#[tokio::test]
+async fn a_token_from_registration_authenticates_requests() {
+    // Arrange
+    let app = spawn_app().await;
+    let response = app
+        .post_registration(&serde_json::json!({
+            "fullName": "le guin",
+            "email": "ursula_le_guin@gmail.com",
+            "password": "password{",
+        }))
+        .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let token = body["cookie"].as_str().unwrap();
+
+    // Act
+    let response = app.post_lair(&lair_body(), token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn forged_tokens_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let foreign_secret = HmacSecret(Secret::new("not-the-server-secret".to_string()));
+    let test_cases = vec![
+        ("garbage".to_string(), "a malformed token"),
+        (
+            AuthToken::new(app.test_user.user_id).encode(&foreign_secret),
+            "a token signed with another secret",
+        ),
+    ];
+
+    for (token, description) in test_cases {
+        // Act
+        let response = app.post_lair(&lair_body(), &token).await;
+
+        // Assert
+        assert_eq!(
+            401,
+            response.status().as_u16(),
+            "The API did not return a 401 Unauthorized for {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn revoked_tokens_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+
+    // Act
+    let response = app.post_user_logout(&token).await;
+    assert_eq!(200, response.status().as_u16());
+    let response = app.post_lair(&lair_body(), &token).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn expired_tokens_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    sqlx::query!("UPDATE tokens SET expires_at = now() - interval '1 minute'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.post_lair(&lair_body(), &token).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,151 @@
+use crate::helpers::spawn_app;
+
+/// Reads the `(field number, bytes)` of the length-delimited fields of a
+/// protobuf message, skipping the other ones.
+fn length_delimited_fields(mut message: &[u8]) -> Vec<(u64, Vec<u8>)> {
+    fn varint(message: &mut &[u8]) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = message[0];
+            *message = &message[1..];
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    let mut fields = vec![];
+    while !message.is_empty() {
+        let key = varint(&mut message);
+        match key & 0x7 {
+            0 => {
+                varint(&mut message);
+            }
+            1 => message = &message[8..],
+            2 => {
+                let length = varint(&mut message) as usize;
+                fields.push((key >> 3, message[..length].to_vec()));
+                message = &message[length..];
+            }
+            wire_type => panic!("Unexpected wire type {}", wire_type),
+        }
+    }
+    fields
+}
+
+/// The number of features and the keys of the `lairs` layer of a tile.
+fn lairs_layer(tile: &[u8]) -> (usize, Vec<String>) {
+    let layers = length_delimited_fields(tile);
+    assert_eq!(layers.len(), 1);
+    let (field, layer) = &layers[0];
+    assert_eq!(*field, 3);
+
+    let fields = length_delimited_fields(layer);
+    let name = fields.iter().find(|(field, _)| *field == 1).unwrap();
+    assert_eq!(name.1, b"lairs");
+    let features = fields.iter().filter(|(field, _)| *field == 2).count();
+    let keys = fields
+        .iter()
+        .filter(|(field, _)| *field == 3)
+        .map(|(_, key)| String::from_utf8(key.clone()).unwrap())
+        .collect();
+    (features, keys)
+}
+
+#[tokio::test]
+async fn a_tile_holds_the_lairs_inside_it() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner = app.test_user.user_id;
+    // Paris, in tile 10/518/352.
+    app.store_lair(owner, "Notre-Dame", 48.8530, 2.3499).await;
+    app.store_lair(owner, "Louvre", 48.8606, 2.3376).await;
+    app.store_lair(owner, "Fiji", -17.0, 179.0).await;
+
+    // Act
+    let response = app.get_tile(10, 518, 352).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        "application/vnd.mapbox-vector-tile",
+        response.headers()["Content-Type"].to_str().unwrap()
+    );
+    let (features, keys) = lairs_layer(&response.bytes().await.unwrap());
+    assert_eq!(features, 2);
+    assert_eq!(keys, vec!["id", "title", "image"]);
+}
+
+#[tokio::test]
+async fn the_world_tile_holds_every_lair() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner = app.test_user.user_id;
+    app.store_lair(owner, "Notre-Dame", 48.8530, 2.3499).await;
+    app.store_lair(owner, "Fiji", -17.0, 179.0).await;
+    app.store_lair(owner, "Samoa", -14.0, -179.0).await;
+
+    // Act
+    let response = app.get_tile(0, 0, 0).await;
+
+    // Assert
+    let (features, _) = lairs_layer(&response.bytes().await.unwrap());
+    assert_eq!(features, 3);
+}
+
+#[tokio::test]
+async fn the_features_of_a_tile_are_capped() {
+    // Arrange
+    let app = spawn_app().await;
+    sqlx::query!(
+        r#"
+        INSERT INTO rooms (room_id, account_id, title, description, image, lon, lat)
+        SELECT gen_random_uuid(), $1, 'Lair ' || i, 'A lair', 'https://example.com/lair.png', 10, 10
+        FROM generate_series(1, 1001) AS i
+        "#,
+        app.test_user.user_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let response = app.get_tile(0, 0, 0).await;
+
+    // Assert
+    let (features, _) = lairs_layer(&response.bytes().await.unwrap());
+    assert_eq!(features, 1000);
+}
+
+#[tokio::test]
+async fn a_tile_without_lairs_has_an_empty_layer() {
+    // Arrange
+    let app = spawn_app().await;
+    app.store_lair(app.test_user.user_id, "Notre-Dame", 48.8530, 2.3499)
+        .await;
+
+    // Act
+    let response = app.get_tile(10, 0, 0).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let (features, _) = lairs_layer(&response.bytes().await.unwrap());
+    assert_eq!(features, 0);
+}
+
+#[tokio::test]
+async fn tiles_outside_of_the_map_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let beyond_the_edge = app.get_tile(2, 4, 0).await;
+    let too_deep = app.get_tile(23, 0, 0).await;
+
+    // Assert
+    assert_eq!(400, beyond_the_edge.status().as_u16());
+    assert_eq!(400, too_deep.status().as_u16());
+}
@@ -0,0 +1,330 @@
+use crate::helpers::{spawn_app, TestApp, TestUser};
+use uuid::Uuid;
+
+const AROUND_TEN_TEN: &str = "tl_lat=20&tl_lng=0&br_lat=0&br_lng=20";
+
+/// Creates a wishlist and returns its body.
+async fn create_wishlist(app: &TestApp, name: &str, token: &str) -> serde_json::Value {
+    let response = app
+        .post_wishlist(&serde_json::json!({ "name": name }), token)
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+/// The titles of the lairs on each wishlist of the user, by wishlist name.
+async fn wishlisted_titles(app: &TestApp, token: &str) -> Vec<(String, Vec<String>)> {
+    let response = app.get_my_wishlists(token).await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["wishlists"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|wishlist| {
+            (
+                wishlist["name"].as_str().unwrap().to_string(),
+                titles_of(&wishlist["lairs"]),
+            )
+        })
+        .collect()
+}
+
+fn titles_of(lairs: &serde_json::Value) -> Vec<String> {
+    lairs
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|lair| lair["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+/// The `share_url` of a wishlist, pointed at the test server.
+fn shared_link(app: &TestApp, wishlist: &serde_json::Value) -> String {
+    let share_url = wishlist["share_url"].as_str().unwrap();
+    let path = &share_url[share_url.find("/wishlists/").unwrap()..];
+    format!("{}{}", app.address, path)
+}
+
+#[tokio::test]
+async fn a_user_can_save_lairs_on_named_wishlists() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let owner = app.test_user.user_id;
+    let tower = app.store_lair(owner, "Tower", 10.0, 10.0).await;
+    let cave = app.store_lair(owner, "Cave", 10.0, 10.0).await;
+    let summer = create_wishlist(&app, "Summer", &token).await;
+    create_wishlist(&app, "Winter", &token).await;
+    let summer_id = summer["id"].as_str().unwrap();
+
+    // Act
+    for room_id in [tower, cave, tower] {
+        let response = app.post_wishlist_lair(summer_id, room_id, &token).await;
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    // Assert
+    assert_eq!(
+        wishlisted_titles(&app, &token).await,
+        vec![
+            (
+                "Summer".to_string(),
+                vec!["Cave".to_string(), "Tower".to_string()]
+            ),
+            ("Winter".to_string(), vec![]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn a_lair_can_be_removed_from_a_wishlist() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Tower", 10.0, 10.0)
+        .await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+    let wishlist_id = wishlist["id"].as_str().unwrap();
+    app.post_wishlist_lair(wishlist_id, room_id, &token).await;
+
+    // Act - Part 1 - Remove
+    let response = app.delete_wishlist_lair(wishlist_id, room_id, &token).await;
+
+    // Assert - Part 1 - Remove
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        wishlisted_titles(&app, &token).await,
+        vec![("Summer".to_string(), vec![])]
+    );
+
+    // Act - Part 2 - Remove again
+    let response = app.delete_wishlist_lair(wishlist_id, room_id, &token).await;
+
+    // Assert - Part 2 - Remove again
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn wishlist_names_must_be_valid_and_unique() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    create_wishlist(&app, "Summer", &token).await;
+
+    // Act
+    let blank = app
+        .post_wishlist(&serde_json::json!({"name": "  "}), &token)
+        .await;
+    let duplicate = app
+        .post_wishlist(&serde_json::json!({"name": " Summer "}), &token)
+        .await;
+
+    // Assert
+    assert_eq!(400, blank.status().as_u16());
+    assert_eq!(409, duplicate.status().as_u16());
+}
+
+#[tokio::test]
+async fn users_cannot_add_lairs_to_the_wishlists_of_others() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Tower", 10.0, 10.0)
+        .await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+    let other_user = TestUser::generate();
+    other_user.store(&app.db_pool).await;
+    let other_token = other_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_wishlist_lair(wishlist["id"].as_str().unwrap(), room_id, &other_token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+    assert_eq!(
+        wishlisted_titles(&app, &token).await,
+        vec![("Summer".to_string(), vec![])]
+    );
+}
+
+#[tokio::test]
+async fn adding_an_unknown_lair_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+
+    // Act
+    let response = app
+        .post_wishlist_lair(wishlist["id"].as_str().unwrap(), Uuid::new_v4(), &token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn wishlists_require_authentication() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_my_wishlists("not-a-token").await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_map_flags_favourites_for_authenticated_users_only() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let owner = app.test_user.user_id;
+    let tower = app.store_lair(owner, "Tower", 10.0, 10.0).await;
+    app.store_lair(owner, "Cave", 10.0, 10.0).await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+    app.post_wishlist_lair(wishlist["id"].as_str().unwrap(), tower, &token)
+        .await;
+
+    // Act
+    // A fresh client, so that the session cookie of the login is not sent
+    let anonymous: serde_json::Value = reqwest::Client::new()
+        .get(format!("{}/lair?{}", app.address, AROUND_TEN_TEN))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    let authenticated: serde_json::Value = app
+        .get_lairs_on_map_as(AROUND_TEN_TEN, &token)
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    for lair in anonymous["lairs"].as_array().unwrap() {
+        assert!(lair.get("is_favourite").is_none());
+    }
+    for lair in authenticated["lairs"].as_array().unwrap() {
+        assert_eq!(lair["is_favourite"], lair["title"] == "Tower");
+    }
+}
+
+#[tokio::test]
+async fn anyone_with_the_link_can_see_a_wishlist() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let room_id = app
+        .store_lair(app.test_user.user_id, "Tower", 10.0, 10.0)
+        .await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+    app.post_wishlist_lair(wishlist["id"].as_str().unwrap(), room_id, &token)
+        .await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(shared_link(&app, &wishlist))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let shared: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(shared["name"], "Summer");
+    assert_eq!(titles_of(&shared["lairs"]), vec!["Tower"]);
+    assert!(shared.get("share_url").is_none());
+}
+
+#[tokio::test]
+async fn a_wishlist_link_with_a_forged_signature_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+    let other = create_wishlist(&app, "Winter", &token).await;
+    let (_, signature) = wishlist["share_url"]
+        .as_str()
+        .unwrap()
+        .split_once("signature=")
+        .unwrap();
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!(
+            "{}/wishlists/{}?signature={}",
+            app.address,
+            other["id"].as_str().unwrap(),
+            signature
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn rotating_the_share_link_revokes_the_old_one() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+    let other = create_wishlist(&app, "Winter", &token).await;
+    let wishlist_id = wishlist["id"].as_str().unwrap();
+
+    // Act
+    let response = app.post_wishlist_share_link(wishlist_id, &token).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let rotated: serde_json::Value = response.json().await.unwrap();
+    assert_ne!(rotated["share_url"], wishlist["share_url"]);
+    for (wishlist, expected) in [(&wishlist, 404), (&rotated, 200), (&other, 200)] {
+        let response = app
+            .api_client
+            .get(shared_link(&app, wishlist))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(expected, response.status().as_u16());
+    }
+}
+
+#[tokio::test]
+async fn users_cannot_rotate_the_share_links_of_others() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.test_user.login(&app).await;
+    let wishlist = create_wishlist(&app, "Summer", &token).await;
+    let other_user = TestUser::generate();
+    other_user.store(&app.db_pool).await;
+    let other_token = other_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_wishlist_share_link(wishlist["id"].as_str().unwrap(), &other_token)
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+    let response = app
+        .api_client
+        .get(shared_link(&app, &wishlist))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+}